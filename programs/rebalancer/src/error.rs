@@ -114,4 +114,268 @@ pub enum ErrorCode {
     
     #[msg("Invalid performance score for calculation")]
     InvalidPerformanceScore,
+    
+    #[msg("Impermanent loss threshold must be at most 10000 bps")]
+    InvalidIlThreshold,
+    
+    #[msg("Registered strategy counter does not match strategy accounts")]
+    StrategyCountMismatch,
+    
+    #[msg("Fee eligibility window must be between 0 seconds and 1 year")]
+    InvalidFeeEligibilityWindow,
+    
+    #[msg("Ranking finalization in progress, finish it before submitting scores")]
+    RankingInProgress,
+    
+    #[msg("Ranking buffer does not contain every registered strategy")]
+    RankingIncomplete,
+    
+    #[msg("Ranking buffer is full")]
+    RankingBufferFull,
+    
+    #[msg("Freshness window must be between 1 minute and 30 days")]
+    InvalidFreshnessWindow,
+    
+    #[msg("Fee treasury must be configured when its fee is nonzero")]
+    TreasuryNotConfigured,
+    
+    #[msg("Compound frequency must be between 1 minute and 30 days")]
+    InvalidCompoundFrequency,
+    
+    #[msg("Rewards were compounded too recently for this protocol")]
+    CompoundTooSoon,
+    
+    #[msg("Capital extraction and redistribution are paused")]
+    ExtractionPaused,
+    
+    #[msg("Oracle price feeds are required under the oracle-verified entry price policy")]
+    OracleFeedRequired,
+    
+    #[msg("Account is not a valid oracle price update")]
+    InvalidOracleAccount,
+    
+    #[msg("Entry price deviates from the oracle price beyond tolerance")]
+    EntryPriceDeviation,
+    
+    #[msg("Entry price tolerance must be between 1 and 1000 basis points")]
+    InvalidPriceTolerance,
+    
+    #[msg("Expected improvement does not justify the rebalance fees")]
+    RebalanceNotWorthwhile,
+    
+    #[msg("Minimum improvement ratio must be at least 10000 basis points (1x)")]
+    InvalidImprovementRatio,
+    
+    #[msg("No pending manager proposal")]
+    NoPendingManager,
+    
+    #[msg("Strategy leverage exceeds the portfolio leverage policy")]
+    LeverageExceedsPolicy,
+    
+    #[msg("Portfolio leverage cap must be between 1x and 10x")]
+    InvalidLeverageCap,
+    
+    #[msg("Withdrawal exceeds the strategy's daily withdrawal limit")]
+    WithdrawalRateLimitExceeded,
+    
+    #[msg("Daily withdrawal limit cannot exceed 10000 basis points")]
+    InvalidWithdrawalLimit,
+    
+    #[msg("Longevity bonus exceeds allowed limits")]
+    InvalidLongevityBonus,
+    
+    #[msg("Destination strategy is inactive or its protocol config is no longer valid")]
+    InvalidDestinationStrategy,
+    
+    #[msg("Emergency funds may only flow to the pre-committed emergency destination")]
+    UnauthorizedEmergencyDestination,
+    
+    #[msg("Emergency destination is already committed and cannot be changed")]
+    EmergencyDestinationAlreadySet,
+    
+    #[msg("Protocol target was recently exited and is still in its re-entry cooldown")]
+    ProtocolTargetInCooldown,
+    
+    #[msg("Re-entry cooldown must be between 0 and 31536000 seconds")]
+    InvalidReentryCooldown,
+    
+    #[msg("Minimum allocation per strategy times the strategy count exceeds 100%")]
+    OverConstrainedAllocation,
+    
+    #[msg("Score dispersion must be between 0 and 10000")]
+    InvalidDispersionThreshold,
+    
+    #[msg("Strategy scores are too tightly clustered to warrant rebalancing")]
+    DispersionBelowThreshold,
+    
+    #[msg("Strategy already holds the maximum number of capital positions")]
+    MaxPositionsReached,
+    
+    #[msg("Max positions per strategy must be between 1 and 16")]
+    InvalidPositionCap,
+    
+    #[msg("Free rebalance allowance must be at most 31 per period of 1 day to 1 year")]
+    InvalidFreeRebalanceAllowance,
+    
+    #[msg("Fee allocations are not allowed during a fee-free rebalance")]
+    FeesWaivedThisRebalance,
+    
+    #[msg("Withdrawal would leave the strategy vault below rent exemption")]
+    VaultBelowRentExemption,
+    
+    #[msg("Settlement delay must be at most 7 days")]
+    InvalidSettlementDelay,
+    
+    #[msg("Invalid strategy status transition: deprecated strategies cannot be reactivated")]
+    InvalidStatusTransition,
+    
+    #[msg("Extraction fees exceed the allowed share of the extracted amount")]
+    ExtractionNotEconomical,
+    
+    #[msg("Max extraction fee must be between 0 and 10000 basis points")]
+    InvalidExtractionFeeCap,
+    
+    #[msg("Uncollected performance fees are at their cap; collect fees before accruing more")]
+    UncollectedFeeCapReached,
+    
+    #[msg("Scoring weights must sum to 10000 basis points")]
+    InvalidScoringWeights,
+    
+    #[msg("Risk limits are out of range or inconsistent")]
+    InvalidRiskLimits,
+    
+    #[msg("Fee allocation does not pay the configured treasury")]
+    InvalidFeeDestination,
+    
+    #[msg("Minimum rebalance interval has not elapsed since the last rebalance")]
+    RebalanceTooSoon,
+    
+    #[msg("A redistribution session is already open")]
+    RedistributionInProgress,
+    
+    #[msg("No redistribution session is open")]
+    NoActiveRedistribution,
+    
+    #[msg("Allocated total does not match the session's expected total")]
+    RedistributionTotalMismatch,
+    
+    #[msg("Yield farming exit needs the token program, position token accounts and pre-created destination token accounts")]
+    TokenAccountNotProvided,
+    
+    #[msg("Unstake ticket is missing, not filled, or does not belong to this position")]
+    InvalidUnstakeTicket,
+    
+    #[msg("Unstake ticket cannot be claimed until its delay has passed")]
+    UnstakeTicketNotReady,
+    
+    #[msg("Staleness halflife must be 0 (disabled) or between 1 hour and 30 days")]
+    InvalidStalenessHalflife,
+    
+    #[msg("Minimum strategies for rebalance must be at least 2")]
+    InvalidMinStrategies,
+    
+    #[msg("Portfolio has fewer strategies than required to rebalance")]
+    TooFewStrategiesToRebalance,
+    
+    #[msg("Strategy cooldown must be between 0 and 30 days")]
+    InvalidStrategyCooldown,
+    
+    #[msg("Slippage tolerance must be at most 10000 basis points")]
+    InvalidSlippageTolerance,
+    
+    #[msg("Realized exit slippage exceeds the allowed tolerance")]
+    SlippageExceeded,
+    
+    #[msg("Position type does not match the strategy's protocol")]
+    PositionTypeMismatch,
+    
+    #[msg("Account is not a portfolio owned by this program")]
+    InvalidPortfolioAccount,
+    
+    #[msg("Portfolio is already on the current schema version")]
+    PortfolioAlreadyMigrated,
+    
+    #[msg("Account schema version is not supported by this program")]
+    UnsupportedSchemaVersion,
+    
+    #[msg("Volatility circuit breaker threshold must be at most 10000")]
+    InvalidVolatilityCircuit,
+    
+    #[msg("Max yield delta must be at most 50000 basis points")]
+    InvalidYieldDelta,
+    
+    #[msg("Yield update moves further from the stored rate than the portfolio allows")]
+    SuspiciousYieldUpdate,
+    
+    #[msg("Automatic yield accrual is only supported for lending strategies")]
+    YieldAccrualUnsupported,
+    
+    #[msg("Rent buffer must cover the rent-exempt minimum and be at most 1 SOL")]
+    InvalidRentBuffer,
+    
+    #[msg("Confirmation nonce must be the next emergency extraction count")]
+    InvalidEmergencyNonce,
+    
+    #[msg("TVL snapshot interval must be between 1 minute and 30 days")]
+    InvalidTvlSnapshotInterval,
+    
+    #[msg("TVL snapshot taken too soon after the previous one")]
+    TvlSnapshotTooSoon,
+    
+    #[msg("Reconcile tolerance cannot exceed 100%")]
+    InvalidReconcileTolerance,
+    
+    #[msg("Portfolio was rebalanced since the caller last read it")]
+    StaleRebalanceState,
+    
+    #[msg("Reference price must be greater than zero")]
+    InvalidReferencePrice,
+    
+    #[msg("Maximum strategies must be between 1 and what one ranking transaction can load")]
+    InvalidMaxStrategies,
+    
+    #[msg("Portfolio has reached its maximum strategy count")]
+    MaxStrategiesReached,
+    
+    #[msg("Strategy is already on the current schema version")]
+    StrategyAlreadyMigrated,
+    
+    #[msg("Account is not a risk config of this portfolio")]
+    InvalidRiskConfigAccount,
+    
+    #[msg("Risk config is already on the current layout")]
+    RiskConfigAlreadyMigrated,
+    
+    #[msg("Price update is only partially verified")]
+    PartiallyVerifiedPrice,
+    
+    #[msg("Strategy has no price feeds bound")]
+    OracleFeedNotBound,
+    
+    #[msg("Price feed is not the one bound to this strategy")]
+    OracleFeedMismatch,
+    
+    #[msg("Price feed binding is missing a required leg or already set")]
+    InvalidPriceFeedBinding,
+    
+    #[msg("Portfolio scores in a stable denomination; its DenominationConfig and price feed are required")]
+    DenominationConfigRequired,
+    
+    #[msg("Allocations exceed the capital extracted and not yet redistributed")]
+    ExceedsUndeployedCapital,
+    
+    #[msg("Keeper cannot be default pubkey")]
+    InvalidKeeper,
+    
+    #[msg("Fees settle to the configured fee treasury through collect_performance_fees")]
+    FeeTreasuryConfigured,
+    
+    #[msg("Repair can only raise the strategy count: a partial set can't prove strategies are gone")]
+    StrategyCountCannotDecrease,
+    
+    #[msg("Ranking buffer predates the current entry layout; reset it first")]
+    RankingBufferOutdated,
+    
+    #[msg("Strategy must be deprecated with a zero balance before it can be closed")]
+    StrategyNotWoundDown,
 }
@@ -0,0 +1,403 @@
+use anchor_lang::prelude::*;
+use crate::instructions::PortfolioConfigUpdate;
+use crate::state::{OracleSource, PriceFeedBinding, ProtocolType, RankingMode, StrategyStatus, TieBreak};
+
+#[event]
+pub struct ImpermanentLossThresholdBreached {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub impermanent_loss_bps: u64,
+    pub max_acceptable_il_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperBudgetFunded {
+    pub portfolio: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub undeployed_capital: u64,            // Portfolio capital left after funding
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperBudgetDrawn {
+    pub portfolio: Pubkey,
+    pub keeper: Pubkey,
+    pub amount: u64,
+    pub remaining_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperRegistered {
+    pub portfolio: Pubkey,
+    pub keeper: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperRevoked {
+    pub portfolio: Pubkey,
+    pub keeper: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyCountRepaired {
+    pub portfolio: Pubkey,
+    pub recorded_count: u32,
+    pub actual_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RankingFinalized {
+    pub portfolio: Pubkey,
+    pub strategies_ranked: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RankingBufferReset {
+    pub portfolio: Pubkey,
+    pub entries_discarded: u32,
+    pub ranks_written: u32,                 // Ranks already persisted before the reset
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsCompounded {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub reward_amount: u64,
+    pub new_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExtractionPauseChanged {
+    pub portfolio: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerProposed {
+    pub portfolio: Pubkey,
+    pub current_manager: Pubkey,
+    pub proposed_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerProposalCancelled {
+    pub portfolio: Pubkey,
+    pub manager: Pubkey,
+    pub cancelled_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerTransferred {
+    pub portfolio: Pubkey,
+    pub previous_manager: Pubkey,
+    pub new_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PerformanceFeesCollected {
+    pub portfolio: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_lamports: u64,
+    pub fee_token_mint: Pubkey,             // Pubkey::default() when settled in SOL
+    pub token_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PerformanceFeesClaimed {
+    pub portfolio: Pubkey,
+    pub manager: Pubkey,
+    pub fee_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyDestinationCommitted {
+    pub portfolio: Pubkey,
+    pub destination: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyExtraction {
+    pub portfolio: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolTargetExited {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub target: Pubkey,
+    pub cooldown_ends: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReentryCooldownOverridden {
+    pub portfolio: Pubkey,
+    pub target: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyPauseChanged {
+    pub portfolio: Pubkey,
+    pub previous: bool,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RebalanceConfigUpdated {
+    pub portfolio: Pubkey,
+    pub old_threshold: u8,
+    pub new_threshold: u8,
+    pub old_interval: i64,
+    pub new_interval: i64,
+    pub old_ranking_mode: RankingMode,
+    pub new_ranking_mode: RankingMode,
+    pub old_tie_break: TieBreak,
+    pub new_tie_break: TieBreak,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapitalDeposited {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapitalWithdrawn {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyStatusChanged {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub previous_status: StrategyStatus,
+    pub new_status: StrategyStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeCollectionDue {
+    pub portfolio: Pubkey,
+    pub accrued_fees: u64,
+    pub max_uncollected_fees: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioInitialized {
+    pub portfolio: Pubkey,
+    pub manager: Pubkey,
+    pub rebalance_threshold: u8,
+    pub min_rebalance_interval: i64,
+    pub max_strategies: u16,
+    pub emergency_destination: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyRegistered {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub protocol_type: ProtocolType,
+    pub initial_balance: u64,
+    pub price_feeds: PriceFeedBinding,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PerformanceUpdated {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub yield_rate: u64,
+    pub volatility_score: u32,
+    pub balance: u64,
+    pub performance_score: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapitalExtracted {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub amount: u64,
+    pub pending_amount: u64,
+    pub fees_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapitalRedistributed {
+    pub portfolio: Pubkey,
+    pub total_allocated: u64,
+    pub allocation_count: u32,
+    pub fees_allocated: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RankingCompleted {
+    pub portfolio: Pubkey,
+    pub strategies_ranked: u32,
+    pub score_spread: u64,
+    pub rebalance_permitted: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskConfigUpdated {
+    pub portfolio: Pubkey,
+    pub platform_treasury: Pubkey,
+    pub manager_treasury: Pubkey,
+    pub platform_fee_bps: u64,
+    pub manager_fee_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeClaimed {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub ticket: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioMigrated {
+    pub portfolio: Pubkey,
+    pub old_version: u8,
+    pub new_version: u8,
+    pub new_size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub previous_volatility: u32,
+    pub new_volatility: u32,
+    pub circuit_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct YieldAccrued {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub accrued: u64,
+    pub elapsed_seconds: i64,
+    pub new_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyCapitalExtracted {
+    pub portfolio: Pubkey,
+    pub manager: Pubkey,
+    pub destination: Pubkey,
+    pub sequence: u32,                      // Portfolio's emergency_extract_count after this call
+    pub strategy_ids: Vec<Pubkey>,
+    pub total_extracted: u64,
+    pub pause_overridden: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TvlSnapshotRecorded {
+    pub portfolio: Pubkey,
+    pub total_tvl: u64,
+    pub strategy_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyAccountingMismatch {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub actual_pnl: i64,                    // calculate_lifetime_pnl without position fees
+    pub expected_pnl: i64,                  // Yield the stored rate should have produced
+    pub tolerance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DenominationConfigUpdated {
+    pub portfolio: Pubkey,
+    pub price_feed: Pubkey,
+    pub oracle_source: OracleSource,
+    pub reference_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyMigrated {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub old_version: u8,
+    pub new_version: u8,
+    pub new_size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RiskConfigMigrated {
+    pub portfolio: Pubkey,
+    pub old_size: u32,
+    pub new_size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioConfigUpdated {
+    pub portfolio: Pubkey,
+    pub update: PortfolioConfigUpdate,      // Settings left as None were unchanged
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RedistributionCancelled {
+    pub portfolio: Pubkey,
+    pub expected_total: u64,
+    pub accumulated_total: u64,             // Credited by chunks before the cancel
+    pub fees_allocated: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyClosed {
+    pub portfolio: Pubkey,
+    pub strategy_id: Pubkey,
+    pub remaining_strategies: u32,
+    pub timestamp: i64,
+}
@@ -0,0 +1,182 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::update_performance::{compound_factor, compounding_periods_per_year, RATE_PRECISION};
+
+// Longest gap a single accrual credits; time beyond it is forfeited rather
+// than compounding a stale rate
+pub const MAX_ACCRUAL_WINDOW_SECONDS: i64 = 2_592_000; // 30 days
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct AccrueYield<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    pub manager: Signer<'info>,
+}
+
+// YIELD ACCRUAL
+// Grows a lending strategy's balance by its stored APY over the time since
+// last_updated, compounding at the strategy's compound frequency. A second
+// call in the same second accrues nothing.
+pub fn accrue_yield(ctx: Context<AccrueYield>, _strategy_id: Pubkey) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(strategy.status == StrategyStatus::Active, ErrorCode::StrategyNotFound);
+    require!(
+        matches!(strategy.protocol_type, ProtocolType::StableLending { .. }),
+        ErrorCode::YieldAccrualUnsupported
+    );
+    
+    let elapsed = current_time.saturating_sub(strategy.last_updated);
+    let accrued = accrued_interest(
+        strategy.current_balance,
+        strategy.yield_rate,
+        elapsed.min(MAX_ACCRUAL_WINDOW_SECONDS),
+        strategy.compound_frequency_seconds,
+    )?;
+    
+    // Rounded-to-zero accruals leave last_updated alone so the time keeps counting
+    if accrued == 0 {
+        msg!("No yield accrued: strategy={}, elapsed={}s", strategy.strategy_id, elapsed);
+        return Ok(());
+    }
+    
+    let new_balance = strategy.current_balance
+        .checked_add(accrued)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    Strategy::validate_balance_update(new_balance)?;
+    
+    strategy.current_balance = new_balance;
+    strategy.total_deposits = strategy.total_deposits
+        .checked_add(accrued)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    strategy.last_updated = current_time;
+    
+    emit!(YieldAccrued {
+        portfolio: portfolio.key(),
+        strategy_id: strategy.strategy_id,
+        accrued,
+        elapsed_seconds: elapsed.min(MAX_ACCRUAL_WINDOW_SECONDS),
+        new_balance,
+        timestamp: current_time,
+    });
+    
+    msg!("Yield accrued: strategy={}, accrued={}, elapsed={}s, balance={}",
+         strategy.strategy_id, accrued, elapsed, new_balance);
+    
+    Ok(())
+}
+
+// Interest earned by `balance` over `elapsed_seconds` at `apy_bps`, compounded
+// every compound_frequency_seconds with a linear remainder for a partial period.
+//   growth = (1 + r)^(elapsed / freq) * (1 + r * (elapsed % freq) / freq)
+// where r is the per-period rate with (1 + r)^periods_per_year = 1 + APY.
+pub fn accrued_interest(
+    balance: u64,
+    apy_bps: u64,
+    elapsed_seconds: i64,
+    compound_frequency_seconds: i64,
+) -> Result<u64> {
+    if balance == 0 || apy_bps == 0 || elapsed_seconds <= 0 {
+        return Ok(0);
+    }
+    
+    let frequency = compound_frequency_seconds.max(1);
+    let period_rate = period_rate_for_apy(apy_bps, compounding_periods_per_year(frequency))?;
+    
+    let whole_periods = (elapsed_seconds / frequency) as u64;
+    let partial = (elapsed_seconds % frequency) as u128;
+    let factor = compound_factor(period_rate, whole_periods)?;
+    let factor = factor
+        .checked_mul(RATE_PRECISION + period_rate * partial / frequency as u128)
+        .ok_or(ErrorCode::BalanceOverflow)?
+        / RATE_PRECISION;
+    
+    let interest = balance as u128 * (factor - RATE_PRECISION) / RATE_PRECISION;
+    u64::try_from(interest).map_err(|_| ErrorCode::BalanceOverflow.into())
+}
+
+// Largest per-period rate (RATE_PRECISION fixed point) that compounds to no
+// more than apy_bps over `periods`, found by bisection. (1 + r)^n >= 1 + n*r,
+// so APY / n bounds it from above.
+fn period_rate_for_apy(apy_bps: u64, periods: u64) -> Result<u128> {
+    let target = RATE_PRECISION + apy_bps as u128 * RATE_PRECISION / 10000;
+    let mut low = 0u128;
+    let mut high = apy_bps as u128 * RATE_PRECISION / (10000 * periods as u128);
+    
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if compound_factor(mid, periods)? <= target {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_accrual_compounds_to_stored_apy() {
+        let balance = 10_000_000_000;
+        
+        // A full year at 10% APY earns 10%, whatever the compounding frequency
+        for frequency in [60, 3_600, 86_400] {
+            let year = accrued_interest(balance, 1000, 31_536_000, frequency).unwrap();
+            assert!(year.abs_diff(1_000_000_000) <= 1_000, "frequency {}: {}", frequency, year);
+        }
+        
+        // Compounding: two half-years earn less than one full year accrued at once
+        let half = accrued_interest(balance, 1000, 15_768_000, 86_400).unwrap();
+        assert!(half < 500_000_000);
+        let second_half = accrued_interest(balance + half, 1000, 15_768_000, 86_400).unwrap();
+        assert!((half + second_half).abs_diff(1_000_000_000) <= 1_000);
+        
+        // A partial period accrues linearly instead of rounding to nothing
+        assert!(accrued_interest(balance, 1000, 3_600, 86_400).unwrap() > 0);
+    }
+    
+    #[test]
+    fn test_accrual_is_bounded_and_idempotent() {
+        let balance = 10_000_000_000;
+        
+        // Nothing accrues without elapsed time, yield or balance
+        assert_eq!(accrued_interest(balance, 1000, 0, 86_400).unwrap(), 0);
+        assert_eq!(accrued_interest(balance, 1000, -60, 86_400).unwrap(), 0);
+        assert_eq!(accrued_interest(balance, 0, 86_400, 86_400).unwrap(), 0);
+        assert_eq!(accrued_interest(0, 1000, 86_400, 86_400).unwrap(), 0);
+        
+        // Long gaps are credited only up to the window
+        let capped = accrued_interest(balance, 50000, MAX_ACCRUAL_WINDOW_SECONDS, 1).unwrap();
+        let ten_years = 315_360_000i64;
+        assert_eq!(
+            accrued_interest(balance, 50000, ten_years.min(MAX_ACCRUAL_WINDOW_SECONDS), 1).unwrap(),
+            capped
+        );
+        assert!(capped < balance);
+    }
+}
+
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use super::extract_capital::estimate_extraction_fees;
+use crate::error::ErrorCode;
+
+pub const SECONDS_PER_YEAR: u128 = 31_536_000;
+
+#[derive(Accounts)]
+pub struct CalculateRebalanceBreakeven<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    // Top-performer window; defaults apply without a stored config
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BreakevenReport {
+    pub capital_moved: u64,
+    pub estimated_fees: u64,
+    pub yield_delta_bps: u64,          // Capital-weighted top minus underperformer yield
+    pub breakeven_seconds: u64,        // u64::MAX when the move never pays back
+    pub exceeds_rebalance_interval: bool,
+}
+
+// READ-ONLY: how long until the planned rebalance's yield improvement repays its fees.
+// remaining_accounts: the portfolio's Strategy accounts. Underperformers and top
+// performers are selected exactly as execute_complete_rebalancing selects them.
+pub fn calculate_rebalance_breakeven<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CalculateRebalanceBreakeven<'info>>,
+) -> Result<BreakevenReport> {
+    let portfolio = &ctx.accounts.portfolio;
+    let portfolio_key = portfolio.key();
+    let risk_limits = ctx.accounts.risk_config.as_ref()
+        .map(|risk_config| risk_config.limits.clone())
+        .unwrap_or_default();
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    
+    let mut strategy_ids: Vec<Pubkey> = Vec::new();
+    let mut capital_moved = 0u64;
+    let mut estimated_fees = 0u64;
+    let mut extracted_yield = 0u128;   // Sum of extractable * yield_rate
+    let mut top_balance = 0u128;
+    let mut top_yield = 0u128;         // Sum of balance * yield_rate
+    let mut top_count = 0usize;
+    
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!strategy_ids.contains(&strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        strategy_ids.push(strategy.strategy_id);
+        
+        if strategy.percentile_rank < portfolio.rebalance_threshold {
+            let extractable = portfolio.extractable_balance(strategy.current_balance); // Keep rent buffer
+            capital_moved = capital_moved.checked_add(extractable).ok_or(ErrorCode::BalanceOverflow)?;
+            estimated_fees = estimated_fees
+                .checked_add(estimate_extraction_fees(&strategy.protocol_type, extractable)?)
+                .ok_or(ErrorCode::BalanceOverflow)?;
+            extracted_yield += extractable as u128 * strategy.yield_rate as u128;
+        } else if strategy.percentile_rank >= risk_limits.top_performer_percentile
+            && top_count < risk_limits.max_top_performers as usize
+        {
+            top_balance += strategy.current_balance as u128;
+            top_yield += strategy.current_balance as u128 * strategy.yield_rate as u128;
+            top_count += 1;
+        }
+    }
+    
+    require!(capital_moved > 0 && top_balance > 0, ErrorCode::InsufficientStrategies);
+    
+    let yield_delta_bps = (top_yield / top_balance)
+        .saturating_sub(extracted_yield / capital_moved as u128) as u64;
+    let breakeven_seconds = calculate_breakeven_seconds(estimated_fees, capital_moved, yield_delta_bps);
+    
+    let report = BreakevenReport {
+        capital_moved,
+        estimated_fees,
+        yield_delta_bps,
+        breakeven_seconds,
+        exceeds_rebalance_interval: breakeven_seconds > portfolio.min_rebalance_interval as u64,
+    };
+    
+    msg!("Rebalance break-even: {}s (interval {}s, exceeds={}), fees={}, moved={}, delta={}bps",
+         report.breakeven_seconds, portfolio.min_rebalance_interval, report.exceeds_rebalance_interval,
+         report.estimated_fees, report.capital_moved, report.yield_delta_bps);
+    
+    Ok(report)
+}
+
+// Seconds for `capital_moved` earning an extra `yield_delta_bps` per year to repay
+// `estimated_fees`, rounded up: fees * 10000 * SECONDS_PER_YEAR / (capital * delta)
+pub fn calculate_breakeven_seconds(estimated_fees: u64, capital_moved: u64, yield_delta_bps: u64) -> u64 {
+    if estimated_fees == 0 {
+        return 0;
+    }
+    
+    let gain_per_year_scaled = capital_moved as u128 * yield_delta_bps as u128;
+    if gain_per_year_scaled == 0 {
+        return u64::MAX;
+    }
+    
+    let numerator = estimated_fees as u128 * 10000 * SECONDS_PER_YEAR;
+    u64::try_from(numerator.div_ceil(gain_per_year_scaled)).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_breakeven_seconds() {
+        // 10 SOL moved at +5% APY earns 0.5 SOL/year; 0.05 SOL of fees repays in a tenth of a year
+        assert_eq!(calculate_breakeven_seconds(50_000_000, 10_000_000_000, 500), 3_153_600);
+        
+        // Free moves break even immediately; no yield improvement never does
+        assert_eq!(calculate_breakeven_seconds(0, 10_000_000_000, 500), 0);
+        assert_eq!(calculate_breakeven_seconds(50_000_000, 10_000_000_000, 0), u64::MAX);
+    }
+}
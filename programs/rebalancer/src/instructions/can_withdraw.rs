@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct CanWithdraw<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    #[account(
+        seeds = [b"position", strategy.key().as_ref(), &[position.position_index]],
+        bump = position.bump,
+        constraint = position.strategy_id == strategy_id @ ErrorCode::StrategyNotFound
+    )]
+    pub position: Account<'info, CapitalPosition>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct WithdrawalFeasibility {
+    pub feasible: bool,
+    pub reason_code: u32, // ErrorCode number the withdrawal would fail with (0 = feasible)
+}
+
+// READ-ONLY: whether requested_amount could be withdrawn from the position
+// right now, reported instead of raised so clients can precheck
+pub fn can_withdraw(
+    ctx: Context<CanWithdraw>,
+    strategy_id: Pubkey,
+    requested_amount: u64,
+) -> Result<WithdrawalFeasibility> {
+    let feasibility = check_withdrawal_feasibility(
+        &ctx.accounts.position,
+        requested_amount,
+        &ctx.accounts.strategy.protocol_type,
+    );
+    
+    msg!("Withdrawal of {} from strategy {}: feasible={}, reason={}",
+         requested_amount, strategy_id, feasibility.feasible, feasibility.reason_code);
+    
+    Ok(feasibility)
+}
+
+pub fn check_withdrawal_feasibility(
+    position: &CapitalPosition,
+    requested_amount: u64,
+    protocol_type: &ProtocolType,
+) -> WithdrawalFeasibility {
+    match position.validate_withdrawal_feasibility(requested_amount, protocol_type) {
+        Ok(()) => WithdrawalFeasibility { feasible: true, reason_code: 0 },
+        Err(error) => WithdrawalFeasibility { feasible: false, reason_code: reason_code(&error) },
+    }
+}
+
+// ErrorCode number carried by an error, for reporting instead of raising
+pub fn reason_code(error: &Error) -> u32 {
+    match error {
+        Error::AnchorError(error) => error.error_code_number,
+        Error::ProgramError(_) => u32::MAX, // Not an ErrorCode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn farming_position(lp_tokens: u64) -> CapitalPosition {
+        CapitalPosition {
+            strategy_id: Pubkey::new_unique(),
+            token_a_amount: 1_000_000_000,
+            token_b_amount: 1_000_000_000,
+            lp_tokens,
+            platform_controlled_lp: lp_tokens,
+            position_type: PositionType::LiquidityPair,
+            entry_price_a: 1_000_000,
+            entry_price_b: 1_000_000,
+            last_rebalance: 0,
+            accrued_fees: 0,
+            impermanent_loss: 0,
+            bump: 255,
+            position_index: 0,
+            reserved: [0u8; 14],
+        }
+    }
+    
+    #[test]
+    fn test_infeasible_withdrawal_reports_reason() {
+        let position = farming_position(10_000_000_000);
+        let farming = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 30,
+        };
+        
+        assert_eq!(
+            check_withdrawal_feasibility(&position, 5_000_000_000, &farming),
+            WithdrawalFeasibility { feasible: true, reason_code: 0 }
+        );
+        
+        // Over half the LP in one transaction is refused, with the reason attached
+        let too_much = check_withdrawal_feasibility(&position, 5_000_000_001, &farming);
+        assert!(!too_much.feasible);
+        assert_eq!(too_much.reason_code, u32::from(ErrorCode::ExcessiveWithdrawal));
+        
+        let dust = check_withdrawal_feasibility(&position, 999, &farming);
+        assert_eq!(dust.reason_code, u32::from(ErrorCode::WithdrawalTooSmall));
+    }
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::reentry_cooldown::record_strategy_exit;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct CheckIlThresholds<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    #[account(
+        seeds = [b"position", strategy.key().as_ref(), &[position.position_index]],
+        bump = position.bump,
+        constraint = position.strategy_id == strategy_id @ ErrorCode::StrategyNotFound
+    )]
+    pub position: Account<'info, CapitalPosition>,
+    
+    pub manager: Signer<'info>,
+}
+
+// IMPERMANENT LOSS STOP-LOSS
+// Deprecates a yield-farming strategy whose recorded IL exceeds the portfolio
+// threshold so the next rebalance extracts its capital.
+pub fn check_il_thresholds(
+    ctx: Context<CheckIlThresholds>,
+    _strategy_id: Pubkey,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    let position = &ctx.accounts.position;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(
+        matches!(strategy.protocol_type, ProtocolType::YieldFarming { .. }),
+        ErrorCode::InvalidProtocolType
+    );
+    
+    if strategy.status != StrategyStatus::Active
+        || !position.exceeds_il_threshold(portfolio.max_acceptable_il_bps)
+    {
+        return Ok(());
+    }
+    
+    let impermanent_loss_bps = position.impermanent_loss_bps();
+    strategy.status = StrategyStatus::Deprecated;
+    record_strategy_exit(portfolio, strategy.strategy_id, strategy.protocol_type.target_id(), current_time);
+    
+    emit!(ImpermanentLossThresholdBreached {
+        portfolio: portfolio.key(),
+        strategy_id: strategy.strategy_id,
+        impermanent_loss_bps,
+        max_acceptable_il_bps: portfolio.max_acceptable_il_bps,
+        timestamp: current_time,
+    });
+    
+    msg!("IL stop-loss triggered: strategy={}, il={}bps, max={}bps, flagged for exit",
+         strategy.strategy_id, impermanent_loss_bps, portfolio.max_acceptable_il_bps);
+    
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+// Return data is capped at 1024 bytes; each violation serializes to 57 bytes
+pub const MAX_RISK_VIOLATIONS: usize = 17;
+
+#[derive(Accounts)]
+pub struct CheckRiskConfigFeasibility<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RiskLimitKind {
+    MaxSingleStrategy, // Holding is more concentrated than the proposed cap
+    MinSingleStrategy, // Funded holding is below the proposed minimum share
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RiskViolation {
+    pub strategy_id: Pubkey,
+    pub limit: RiskLimitKind,
+    pub current_bps: u64,
+    pub limit_bps: u64,
+    pub excess_lamports: u64, // Capital to move to comply (out of, or into, the strategy)
+}
+
+// READ-ONLY: previews which strategies passed via remaining_accounts would break
+// the proposed limits at their current balances, and by how much
+pub fn check_risk_config_feasibility<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CheckRiskConfigFeasibility<'info>>,
+    proposed: RiskLimits,
+) -> Result<Vec<RiskViolation>> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_RISK_VIOLATIONS, ErrorCode::TooManyStrategies);
+    
+    let mut holdings: Vec<(Pubkey, u64)> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!holdings.iter().any(|(id, _)| *id == strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        holdings.push((strategy.strategy_id, strategy.current_balance));
+    }
+    
+    let violations = find_risk_violations(&holdings, &proposed);
+    msg!("Proposed risk limits: {} violations across {} strategies", violations.len(), holdings.len());
+    
+    Ok(violations)
+}
+
+// Each holding's share of the total, in bps, checked against the proposed
+// per-strategy maximum and (for funded holdings) minimum
+pub fn find_risk_violations(holdings: &[(Pubkey, u64)], proposed: &RiskLimits) -> Vec<RiskViolation> {
+    let total: u128 = holdings.iter().map(|(_, balance)| *balance as u128).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    
+    let mut violations = Vec::new();
+    for &(strategy_id, balance) in holdings {
+        let share_bps = (balance as u128 * 10000 / total) as u64;
+        
+        if share_bps > proposed.max_single_strategy_bps {
+            let cap = total * proposed.max_single_strategy_bps as u128 / 10000;
+            violations.push(RiskViolation {
+                strategy_id,
+                limit: RiskLimitKind::MaxSingleStrategy,
+                current_bps: share_bps,
+                limit_bps: proposed.max_single_strategy_bps,
+                excess_lamports: (balance as u128 - cap) as u64,
+            });
+        } else if balance > 0 && share_bps < proposed.min_single_strategy_bps {
+            let floor = total * proposed.min_single_strategy_bps as u128 / 10000;
+            violations.push(RiskViolation {
+                strategy_id,
+                limit: RiskLimitKind::MinSingleStrategy,
+                current_bps: share_bps,
+                limit_bps: proposed.min_single_strategy_bps,
+                excess_lamports: (floor - balance as u128) as u64,
+            });
+        }
+    }
+    
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_lowered_concentration_cap_flags_largest_holding() {
+        let large = Pubkey::new_unique();
+        let small = Pubkey::new_unique();
+        let holdings = vec![
+            (large, 6_000_000_000),
+            (Pubkey::new_unique(), 3_500_000_000),
+            (small, 500_000_000),
+        ];
+        
+        let current = RiskLimits { max_single_strategy_bps: 7000, min_single_strategy_bps: 100, ..RiskLimits::default() };
+        assert!(find_risk_violations(&holdings, &current).is_empty());
+        
+        let proposed = RiskLimits { max_single_strategy_bps: 4000, min_single_strategy_bps: 1000, ..RiskLimits::default() };
+        let violations = find_risk_violations(&holdings, &proposed);
+        assert_eq!(violations.len(), 2);
+        
+        assert_eq!(violations[0].strategy_id, large);
+        assert_eq!(violations[0].limit, RiskLimitKind::MaxSingleStrategy);
+        assert_eq!(violations[0].current_bps, 6000);
+        assert_eq!(violations[0].excess_lamports, 2_000_000_000);
+        
+        assert_eq!(violations[1].strategy_id, small);
+        assert_eq!(violations[1].limit, RiskLimitKind::MinSingleStrategy);
+        assert_eq!(violations[1].excess_lamports, 500_000_000);
+    }
+}
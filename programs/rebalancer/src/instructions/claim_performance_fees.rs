@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use crate::instructions::collect_performance_fees::pay_portfolio_lamports;
+
+#[derive(Accounts)]
+pub struct ClaimPerformanceFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager,
+        constraint = portfolio.fee_treasury == Pubkey::default() @ ErrorCode::FeeTreasuryConfigured,
+        constraint = portfolio.fee_token_mint == Pubkey::default() @ ErrorCode::FeeTreasuryConfigured
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+// Pay the performance fees accrued by update_performance to the manager's
+// wallet in SOL and zero the counter. Portfolios that configured a fee
+// treasury or fee token settle through collect_performance_fees instead.
+pub fn claim_performance_fees(ctx: Context<ClaimPerformanceFees>) -> Result<()> {
+    let fees = ctx.accounts.portfolio.accrued_performance_fees;
+    require!(fees > 0, ErrorCode::InsufficientBalance);
+    
+    pay_portfolio_lamports(&ctx.accounts.portfolio, &ctx.accounts.manager.to_account_info(), fees)?;
+    
+    let portfolio = &mut ctx.accounts.portfolio;
+    portfolio.accrued_performance_fees = 0;
+    
+    emit!(PerformanceFeesClaimed {
+        portfolio: portfolio.key(),
+        manager: portfolio.manager,
+        fee_lamports: fees,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Performance fees claimed: {} lamports -> manager={}", fees, portfolio.manager);
+    
+    Ok(())
+}
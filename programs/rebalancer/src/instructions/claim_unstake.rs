@@ -0,0 +1,130 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use crate::state::*;
+use crate::marinade::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey, position_index: u8)]
+pub struct ClaimUnstake<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", strategy.key().as_ref(), &[position_index]],
+        bump = position.bump,
+        constraint = position.strategy_id == strategy_id @ ErrorCode::StrategyNotFound
+    )]
+    pub position: Account<'info, CapitalPosition>,
+    
+    /// CHECK: Marinade State, must be the strategy's stake_pool
+    #[account(mut)]
+    pub marinade_state: UncheckedAccount<'info>,
+    
+    /// CHECK: Marinade reserve PDA, verified by the Marinade program
+    #[account(mut)]
+    pub reserve_pda: UncheckedAccount<'info>,
+    
+    /// CHECK: Marinade TicketAccountData, decoded and validated in the handler
+    #[account(mut, owner = MARINADE_PROGRAM_ID @ ErrorCode::InvalidUnstakeTicket)]
+    pub ticket_account: UncheckedAccount<'info>,
+    
+    /// CHECK: Marinade program
+    #[account(address = MARINADE_PROGRAM_ID @ ErrorCode::InvalidProtocolType)]
+    pub marinade_program: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub clock: Sysvar<'info, Clock>,
+    pub system_program: Program<'info, System>,
+}
+
+// Claim a delayed Marinade unstake ordered by extract_capital. The ticket pays
+// its beneficiary, the CapitalPosition PDA, which forwards the SOL to the manager.
+pub fn claim_unstake(ctx: Context<ClaimUnstake>, strategy_id: Pubkey, _position_index: u8) -> Result<()> {
+    let ProtocolType::LiquidStaking { stake_pool, unstake_delay, .. } = ctx.accounts.strategy.protocol_type else {
+        return Err(ErrorCode::InvalidProtocolType.into());
+    };
+    require_keys_eq!(ctx.accounts.marinade_state.key(), stake_pool, ErrorCode::InvalidProtocolType);
+    
+    let ticket = decode_marinade_ticket(&ctx.accounts.ticket_account.try_borrow_data()?)?;
+    require_keys_eq!(ticket.state, stake_pool, ErrorCode::InvalidUnstakeTicket);
+    require_keys_eq!(ticket.beneficiary, ctx.accounts.position.key(), ErrorCode::InvalidUnstakeTicket);
+    require!(
+        ctx.accounts.clock.epoch >= ticket.claimable_epoch(unstake_delay),
+        ErrorCode::UnstakeTicketNotReady
+    );
+    
+    let position_info = ctx.accounts.position.to_account_info();
+    let balance_before = position_info.lamports();
+    invoke(
+        &Instruction {
+            program_id: MARINADE_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.marinade_state.key(), false),
+                AccountMeta::new(ctx.accounts.reserve_pda.key(), false),
+                AccountMeta::new(ctx.accounts.ticket_account.key(), false),
+                AccountMeta::new(position_info.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.clock.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+            ],
+            data: CLAIM_DISCRIMINATOR.to_vec(),
+        },
+        &[
+            ctx.accounts.marinade_state.to_account_info(),
+            ctx.accounts.reserve_pda.to_account_info(),
+            ctx.accounts.ticket_account.to_account_info(),
+            position_info.clone(),
+            ctx.accounts.clock.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.marinade_program.to_account_info(),
+        ],
+    )?;
+    let claimed = position_info.lamports().saturating_sub(balance_before);
+    
+    // FORWARD THE CLAIMED SOL TO THE MANAGER
+    ctx.accounts.position.sub_lamports(claimed)?;
+    ctx.accounts.manager.add_lamports(claimed)?;
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    ctx.accounts.portfolio.release_capital(claimed)?;
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.total_withdrawals = strategy.total_withdrawals
+        .checked_add(claimed)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    let position = &mut ctx.accounts.position;
+    position.token_a_amount = claimed; // SOL received after unstaking
+    position.last_rebalance = current_time;
+    
+    emit!(UnstakeClaimed {
+        portfolio: ctx.accounts.portfolio.key(),
+        strategy_id,
+        ticket: ctx.accounts.ticket_account.key(),
+        amount: claimed,
+        timestamp: current_time,
+    });
+    
+    msg!("Unstake ticket claimed: strategy={}, amount={}", strategy_id, claimed);
+    
+    Ok(())
+}
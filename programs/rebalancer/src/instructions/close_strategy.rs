@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct CloseStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        constraint = strategy.status == StrategyStatus::Deprecated @ ErrorCode::StrategyNotWoundDown,
+        constraint = strategy.current_balance == 0 @ ErrorCode::StrategyNotWoundDown
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+// Deregister a wound-down strategy: its account's rent goes back to the
+// manager and it stops counting toward total_strategies, so ranking no longer
+// expects it and its slot under max_strategies frees up
+pub fn close_strategy(ctx: Context<CloseStrategy>, strategy_id: Pubkey) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    
+    portfolio.total_strategies = portfolio.total_strategies
+        .checked_sub(1)
+        .ok_or(ErrorCode::StrategyCountMismatch)?;
+    
+    emit!(StrategyClosed {
+        portfolio: portfolio.key(),
+        strategy_id,
+        remaining_strategies: portfolio.total_strategies,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Strategy {} closed, {} remaining", strategy_id, portfolio.total_strategies);
+    
+    Ok(())
+}
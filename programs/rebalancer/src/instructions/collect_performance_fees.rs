@@ -0,0 +1,192 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::state::*;
+use crate::oracle::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct CollectPerformanceFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager,
+        constraint = portfolio.fee_treasury != Pubkey::default() @ ErrorCode::TreasuryNotConfigured
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+    
+    /// CHECK: Receives SOL fees, or owns the treasury token account in fee-token mode
+    #[account(mut, address = portfolio.fee_treasury @ ErrorCode::TreasuryNotConfigured)]
+    pub fee_treasury: UncheckedAccount<'info>,
+    
+    // FEE-TOKEN MODE ACCOUNTS (required when portfolio.fee_token_mint is set)
+    
+    #[account(address = portfolio.fee_token_mint @ ErrorCode::InvalidTokenMint)]
+    pub fee_token_mint: Option<Account<'info, Mint>>,
+    
+    #[account(
+        mut,
+        token::mint = portfolio.fee_token_mint,
+        token::authority = portfolio
+    )]
+    pub fee_token_vault: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        token::mint = portfolio.fee_token_mint,
+        token::authority = fee_treasury
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: Pyth PriceUpdateV2 for SOL/USD bound in risk_config, validated in read_oracle_price
+    #[account(
+        address = risk_config.as_ref().map(|c| c.limits.sol_price_feed).unwrap_or_default()
+            @ ErrorCode::OracleFeedMismatch
+    )]
+    pub sol_price_feed: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Pyth PriceUpdateV2 for the fee token bound in risk_config, validated in read_oracle_price
+    #[account(
+        address = risk_config.as_ref().map(|c| c.limits.fee_token_price_feed).unwrap_or_default()
+            @ ErrorCode::OracleFeedMismatch
+    )]
+    pub fee_token_price_feed: Option<UncheckedAccount<'info>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Settle accrued performance fees to the fee treasury, in SOL from the portfolio
+// account or, when a fee token is configured, as that token at the oracle rate.
+// The rate is only taken from the feeds bound in risk_config.limits, so the
+// manager can't pick a feed that inflates or shrinks the token amount.
+pub fn collect_performance_fees(ctx: Context<CollectPerformanceFees>) -> Result<()> {
+    let fees = ctx.accounts.portfolio.accrued_performance_fees;
+    let fee_token_mint = ctx.accounts.portfolio.fee_token_mint;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(fees > 0, ErrorCode::InsufficientBalance);
+    
+    let token_amount = if fee_token_mint == Pubkey::default() {
+        // SOL MODE: pay from portfolio lamports above its rent reserve
+        pay_portfolio_lamports(&ctx.accounts.portfolio, &ctx.accounts.fee_treasury, fees)?;
+        0
+    } else {
+        // FEE-TOKEN MODE: convert at fresh oracle prices, then pay from the portfolio's token vault
+        let mint = ctx.accounts.fee_token_mint.as_ref().ok_or(ErrorCode::InvalidTokenMint)?;
+        let vault = ctx.accounts.fee_token_vault.as_ref().ok_or(ErrorCode::InvalidTokenMint)?;
+        let treasury_account = ctx.accounts.treasury_token_account.as_ref()
+            .ok_or(ErrorCode::InvalidTokenMint)?;
+        let token_program = ctx.accounts.token_program.as_ref().ok_or(ErrorCode::InvalidTokenMint)?;
+        let sol_feed = ctx.accounts.sol_price_feed.as_ref().ok_or(ErrorCode::OracleFeedRequired)?;
+        let token_feed = ctx.accounts.fee_token_price_feed.as_ref().ok_or(ErrorCode::OracleFeedRequired)?;
+        let limits = &ctx.accounts.risk_config.as_ref().ok_or(ErrorCode::OracleFeedNotBound)?.limits;
+        require!(
+            limits.sol_price_feed != Pubkey::default() && limits.fee_token_price_feed != Pubkey::default(),
+            ErrorCode::OracleFeedNotBound
+        );
+        
+        let sol_price = read_oracle_price(sol_feed, current_time)?;
+        let token_price = read_oracle_price(token_feed, current_time)?;
+        let token_amount = convert_fee_to_token_amount(fees, sol_price, token_price, mint.decimals)?;
+        require!(token_amount > 0 && vault.amount >= token_amount, ErrorCode::InsufficientBalance);
+        
+        let portfolio = &ctx.accounts.portfolio;
+        let seeds: &[&[u8]] = &[b"portfolio", portfolio.original_manager.as_ref(), &[portfolio.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: vault.to_account_info(),
+                    to: treasury_account.to_account_info(),
+                    authority: portfolio.to_account_info(),
+                },
+                &[seeds],
+            ),
+            token_amount,
+        )?;
+        token_amount
+    };
+    
+    let portfolio = &mut ctx.accounts.portfolio;
+    portfolio.accrued_performance_fees = 0;
+    
+    emit!(PerformanceFeesCollected {
+        portfolio: portfolio.key(),
+        treasury: portfolio.fee_treasury,
+        fee_lamports: fees,
+        fee_token_mint,
+        token_amount,
+        timestamp: current_time,
+    });
+    
+    msg!("Performance fees collected: {} lamports -> treasury={}, token_amount={}",
+         fees, portfolio.fee_treasury, token_amount);
+    
+    Ok(())
+}
+
+// Move `amount` lamports out of the portfolio account, never dipping into the
+// reserve that keeps it rent-exempt
+pub fn pay_portfolio_lamports<'info>(
+    portfolio: &Account<'info, Portfolio>,
+    recipient: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let portfolio_info = portfolio.to_account_info();
+    let rent_reserve = Rent::get()?.minimum_balance(portfolio_info.data_len());
+    require!(
+        portfolio_info.lamports().saturating_sub(rent_reserve) >= amount,
+        ErrorCode::InsufficientBalance
+    );
+    
+    portfolio_info.sub_lamports(amount)?;
+    recipient.add_lamports(amount)?;
+    Ok(())
+}
+
+// Value a lamport-denominated fee in fee-token base units using USD prices
+// (6 decimals) for SOL and the fee token:
+//   tokens = lamports * sol_price * 10^decimals / (10^9 * token_price)
+pub fn convert_fee_to_token_amount(
+    fee_lamports: u64,
+    sol_price: u64,
+    token_price: u64,
+    token_decimals: u8,
+) -> Result<u64> {
+    require!(sol_price > 0 && token_price > 0, ErrorCode::InvalidPrice);
+    
+    let token_scale = 10u128.checked_pow(token_decimals as u32).ok_or(ErrorCode::BalanceOverflow)?;
+    let amount = (fee_lamports as u128)
+        .checked_mul(sol_price as u128)
+        .and_then(|v| v.checked_mul(token_scale))
+        .ok_or(ErrorCode::BalanceOverflow)?
+        / (1_000_000_000u128 * token_price as u128);
+    
+    u64::try_from(amount).map_err(|_| error!(ErrorCode::BalanceOverflow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_fee_token_conversion() {
+        // 0.5 SOL of fees at $150/SOL paid in a $1.00 stablecoin with 6 decimals = 75 USDC
+        assert_eq!(convert_fee_to_token_amount(500_000_000, 150_000_000, 1_000_000, 6).unwrap(), 75_000_000);
+        
+        // Same fee in a $2.50 governance token with 9 decimals = 30 tokens
+        assert_eq!(convert_fee_to_token_amount(500_000_000, 150_000_000, 2_500_000, 9).unwrap(), 30_000_000_000);
+        
+        assert!(convert_fee_to_token_amount(500_000_000, 0, 1_000_000, 6).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct CompoundRewards<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    // System-owned lamport vault the protocol pays the strategy's rewards into
+    #[account(
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    pub manager: Signer<'info>,
+}
+
+// REWARD COMPOUNDING
+// Reinvests harvested rewards into the strategy balance, no more often than the
+// strategy's protocol-specific compound frequency (over-compounding wastes gas).
+// The reward is whatever the vault holds beyond the lamports already counted,
+// so only rewards that actually arrived can be credited.
+pub fn compound_rewards(
+    ctx: Context<CompoundRewards>,
+    _strategy_id: Pubkey,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(strategy.status == StrategyStatus::Active, ErrorCode::StrategyNotFound);
+    require!(strategy.can_compound(current_time), ErrorCode::CompoundTooSoon);
+    
+    let vault_lamports = ctx.accounts.vault.lamports();
+    let reward_amount = strategy.unaccounted_vault_rewards(vault_lamports);
+    require!(reward_amount > 0, ErrorCode::InsufficientBalance);
+    
+    // Rewards are yield, not deposits: only the balance grows
+    let new_balance = strategy.current_balance
+        .checked_add(reward_amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    Strategy::validate_balance_update(new_balance)?;
+    
+    strategy.current_balance = new_balance;
+    strategy.vault_accounted = vault_lamports;
+    strategy.last_compounded = current_time;
+    
+    emit!(RewardsCompounded {
+        portfolio: portfolio.key(),
+        strategy_id: strategy.strategy_id,
+        reward_amount,
+        new_balance,
+        timestamp: current_time,
+    });
+    
+    msg!("Rewards compounded: strategy={}, rewards={}, balance={}",
+         strategy.strategy_id, reward_amount, new_balance);
+    
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::oracle::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct CreateCapitalPosition<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    #[account(
+        init,
+        payer = manager,
+        space = CapitalPosition::MAX_SIZE,
+        seeds = [b"position", strategy.key().as_ref(), &[strategy.position_count]],
+        bump
+    )]
+    pub position: Account<'info, CapitalPosition>,
+    
+    /// CHECK: Token A feed bound in strategy.price_feeds, validated in read_bound_oracle_price
+    pub price_feed_a: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Token B feed bound in strategy.price_feeds, validated in read_bound_oracle_price
+    pub price_feed_b: Option<UncheckedAccount<'info>>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_capital_position(
+    ctx: Context<CreateCapitalPosition>,
+    strategy_id: Pubkey,
+    position_type: PositionType,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    lp_tokens: u64,
+    entry_price_a: u64,
+    entry_price_b: u64,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    let position = &mut ctx.accounts.position;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(entry_price_a > 0, ErrorCode::InvalidPrice);
+    require!(
+        position_type == strategy.protocol_type.compatible_position_type(),
+        ErrorCode::PositionTypeMismatch
+    );
+    
+    // ENTRY PRICE SOURCING
+    // Under OracleVerified the oracle price is recorded, so IL is always measured
+    // against honest entry prices; the manager's figure only has to agree with it.
+    // Only the feeds bound to the strategy at registration are accepted.
+    let price_feeds = strategy.price_feeds;
+    let (entry_price_a, entry_price_b) = match portfolio.entry_price_policy {
+        EntryPricePolicy::ManagerProvided => (entry_price_a, entry_price_b),
+        EntryPricePolicy::OracleVerified => {
+            let feed_a = ctx.accounts.price_feed_a.as_ref()
+                .ok_or(ErrorCode::OracleFeedRequired)?;
+            let oracle_price_a = read_bound_oracle_price(feed_a, &price_feeds, &price_feeds.feed_a, current_time)?;
+            let verified_a = verify_entry_price(
+                entry_price_a, oracle_price_a, portfolio.entry_price_tolerance_bps,
+            )?;
+            
+            let verified_b = if position_type == PositionType::LiquidityPair {
+                let feed_b = ctx.accounts.price_feed_b.as_ref()
+                    .ok_or(ErrorCode::OracleFeedRequired)?;
+                let oracle_price_b = read_bound_oracle_price(feed_b, &price_feeds, &price_feeds.feed_b, current_time)?;
+                verify_entry_price(
+                    entry_price_b, oracle_price_b, portfolio.entry_price_tolerance_bps,
+                )?
+            } else {
+                entry_price_b
+            };
+            
+            (verified_a, verified_b)
+        }
+    };
+    
+    let position_index = strategy.reserve_position_slot(portfolio.max_positions_per_strategy)?;
+    
+    position.strategy_id = strategy_id;
+    position.token_a_amount = token_a_amount;
+    position.token_b_amount = token_b_amount;
+    position.lp_tokens = lp_tokens;
+    position.platform_controlled_lp = lp_tokens;
+    position.position_type = position_type;
+    position.entry_price_a = entry_price_a;
+    position.entry_price_b = entry_price_b;
+    position.last_rebalance = current_time;
+    position.accrued_fees = 0;
+    position.impermanent_loss = 0;
+    position.bump = ctx.bumps.position;
+    position.position_index = position_index;
+    position.reserved = [0u8; 14];
+    
+    msg!("Capital position {} created: strategy={}, type={:?}, entry_a={}, entry_b={}, policy={:?}",
+         position_index, strategy_id, position_type, entry_price_a, entry_price_b, portfolio.entry_price_policy);
+    
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct DepositCapital<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    // System-owned lamport vault holding the strategy's capital
+    #[account(
+        mut,
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Per-protocol minimum balances; defaults apply without a stored config
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn deposit_capital(
+    ctx: Context<DepositCapital>,
+    strategy_id: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(amount > 0, ErrorCode::InsufficientBalance);
+    
+    // VALIDATE THE POST-DEPOSIT BALANCE BEFORE MOVING ANY LAMPORTS
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.validate_as_destination()?;
+    
+    let new_balance = strategy.current_balance
+        .checked_add(amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    Strategy::validate_balance_update(new_balance)?;
+    let risk_limits = ctx.accounts.risk_config.as_ref()
+        .map(|risk_config| risk_config.limits.clone())
+        .unwrap_or_default();
+    strategy.protocol_type.validate_balance_constraints(new_balance, &risk_limits)?;
+    
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.manager.to_account_info(),
+                to: ctx.accounts.vault.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    
+    strategy.credit_allocation(amount)?;
+    strategy.vault_accounted = strategy.vault_accounted
+        .checked_add(amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    emit!(CapitalDeposited {
+        portfolio: portfolio.key(),
+        strategy_id,
+        amount,
+        balance: strategy.current_balance,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Capital deposited: strategy={}, amount={}, balance={}",
+         strategy_id, amount, strategy.current_balance);
+    
+    Ok(())
+}
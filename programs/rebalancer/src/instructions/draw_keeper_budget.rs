@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct DrawKeeperBudget<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"keeper_budget", portfolio.key().as_ref()],
+        bump = keeper_budget.bump
+    )]
+    pub keeper_budget: Account<'info, KeeperBudget>,
+    
+    // Only exists while the manager has the keeper registered
+    #[account(
+        seeds = [b"keeper", portfolio.key().as_ref(), keeper.key().as_ref()],
+        bump = keeper_registration.bump
+    )]
+    pub keeper_registration: Account<'info, KeeperRegistration>,
+    
+    /// Registered keeper, reimbursed for reward-claiming transactions
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+// A registered keeper draws on its own signature so reward claiming runs
+// without the manager; each draw is capped by the tracked budget balance
+pub fn draw_keeper_budget(
+    ctx: Context<DrawKeeperBudget>,
+    amount: u64,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(amount > 0, ErrorCode::InsufficientBalance);
+    require!(amount <= ctx.accounts.keeper_budget.balance, ErrorCode::InsufficientBalance);
+    
+    // PROGRAM-OWNED VAULT: move lamports directly (rent reserve is never tracked in balance)
+    ctx.accounts.keeper_budget.sub_lamports(amount)?;
+    ctx.accounts.keeper.add_lamports(amount)?;
+    
+    let keeper_budget = &mut ctx.accounts.keeper_budget;
+    keeper_budget.balance = keeper_budget.balance
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientBalance)?;
+    keeper_budget.total_drawn = keeper_budget.total_drawn
+        .checked_add(amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    keeper_budget.last_drawn = current_time;
+    
+    emit!(KeeperBudgetDrawn {
+        portfolio: portfolio.key(),
+        keeper: ctx.accounts.keeper.key(),
+        amount,
+        remaining_balance: keeper_budget.balance,
+        timestamp: current_time,
+    });
+    
+    msg!("Keeper budget drawn: keeper={}, amount={}, remaining={}",
+         ctx.accounts.keeper.key(), amount, keeper_budget.balance);
+    
+    Ok(())
+}
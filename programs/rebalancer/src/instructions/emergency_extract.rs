@@ -0,0 +1,157 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::extract_capital::{extract_strategies, ExtractionOptions};
+use super::reentry_cooldown::record_strategy_exit;
+
+// EMERGENCY ESCAPE HATCH
+// The destination is committed at initialize_portfolio (or, for portfolios
+// that predate it, at migrate_portfolio) and no instruction can change it
+// afterwards: a compromised manager key cannot redirect emergency funds.
+
+#[derive(Accounts)]
+pub struct EmergencyExtract<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    /// CHECK: Lamport sink only; must be the committed emergency destination
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Sweep the portfolio's idle SOL (above its rent reserve and fees still owed)
+// to the committed emergency destination
+pub fn emergency_extract(ctx: Context<EmergencyExtract>) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let destination = ctx.accounts.destination.key();
+    
+    require!(
+        portfolio.emergency_destination != Pubkey::default()
+            && destination == portfolio.emergency_destination,
+        ErrorCode::UnauthorizedEmergencyDestination
+    );
+    
+    let portfolio_info = portfolio.to_account_info();
+    let rent_reserve = Rent::get()?.minimum_balance(portfolio_info.data_len());
+    let amount = portfolio_info.lamports()
+        .saturating_sub(rent_reserve)
+        .saturating_sub(portfolio.accrued_performance_fees);
+    require!(amount > 0, ErrorCode::InsufficientBalance);
+    
+    ctx.accounts.portfolio.sub_lamports(amount)?;
+    ctx.accounts.destination.add_lamports(amount)?;
+    
+    emit!(EmergencyExtraction {
+        portfolio: ctx.accounts.portfolio.key(),
+        destination,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("EMERGENCY EXTRACTION: {} lamports -> {}", amount, destination);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct EmergencyExtractCapital<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager,
+        constraint = portfolio.emergency_destination != Pubkey::default() @ ErrorCode::UnauthorizedEmergencyDestination
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    /// CHECK: Receives the proceeds and owns their token accounts; must be the committed emergency destination
+    #[account(mut, address = portfolio.emergency_destination @ ErrorCode::UnauthorizedEmergencyDestination)]
+    pub destination: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    // Required for any real protocol exit (every strategy except simulated staking)
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// EMERGENCY STRATEGY EXTRACTION
+// Pulls capital out of the listed strategies right away, as extract_capital
+// would but ignoring the dispersion gate, per-strategy cooldowns and exit
+// fees, and unstaking liquid. The pause flags still apply unless
+// override_pause is set. confirmation_nonce must equal the portfolio's
+// emergency_extract_count + 1. Proceeds go to the committed emergency
+// destination, never the manager, so the destination's token accounts take
+// the manager's place in remaining_accounts (otherwise as for extract_capital).
+pub fn emergency_extract_capital<'info>(
+    ctx: Context<'_, '_, 'info, 'info, EmergencyExtractCapital<'info>>,
+    strategy_ids: Vec<Pubkey>,
+    confirmation_nonce: u32,
+    override_pause: bool,
+    max_slippage_bps: u16,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let portfolio = &mut ctx.accounts.portfolio;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    if !override_pause {
+        require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+        require!(!portfolio.extraction_paused, ErrorCode::ExtractionPaused);
+    }
+    require!(max_slippage_bps <= 10000, ErrorCode::InvalidSlippageTolerance);
+    require!(!strategy_ids.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(strategy_ids.len() <= 10, ErrorCode::TooManyStrategies);
+    
+    let sequence = portfolio.record_emergency_extraction(confirmation_nonce)?;
+    
+    let extracted = extract_strategies(
+        ctx.remaining_accounts,
+        ctx.accounts.token_program.as_ref(),
+        ctx.accounts.destination.to_account_info(),
+        &portfolio_key,
+        &strategy_ids,
+        ExtractionOptions {
+            max_fee_bps: None,
+            unstake_mode: UnstakeMode::Liquid,
+            max_slippage_bps,
+            strategy_cooldown: 0,
+        },
+        current_time,
+    )?;
+    let total_extracted = extracted.total_extracted;
+    
+    for &(strategy_id, target) in extracted.exited.iter() {
+        record_strategy_exit(portfolio, strategy_id, target, current_time);
+    }
+    
+    portfolio.total_capital_moved = portfolio.total_capital_moved
+        .checked_add(total_extracted)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    emit!(EmergencyCapitalExtracted {
+        portfolio: portfolio_key,
+        manager: ctx.accounts.manager.key(),
+        destination: ctx.accounts.destination.key(),
+        sequence,
+        strategy_ids: strategy_ids.clone(),
+        total_extracted,
+        pause_overridden: override_pause,
+        timestamp: current_time,
+    });
+    
+    msg!("EMERGENCY CAPITAL EXTRACTION #{}: {} lamports from {} strategies (pause overridden: {})",
+         sequence, total_extracted, strategy_ids.len(), override_pause);
+    
+    Ok(())
+}
@@ -1,13 +1,15 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::events::*;
 use crate::error::ErrorCode;
 
 #[derive(Accounts)]
 pub struct ExecuteRankingCycle<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
         bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
         has_one = manager @ ErrorCode::UnauthorizedManager
     )]
     pub portfolio: Account<'info, Portfolio>,
@@ -16,28 +18,347 @@ pub struct ExecuteRankingCycle<'info> {
     pub manager: Signer<'info>,
 }
 
-pub fn execute_ranking_cycle(
-    ctx: Context<ExecuteRankingCycle>,
+// remaining_accounts: every (writable) Strategy PDA of the portfolio. Each is
+// verified as [b"strategy", portfolio, strategy_id] and program-owned before its
+// new percentile_rank is written back. Racing keepers pass the last_rebalance
+// they read as expected_last_rebalance so only the first of them runs.
+pub fn execute_ranking_cycle<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecuteRankingCycle<'info>>,
+    expected_last_rebalance: Option<i64>,
 ) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
     let portfolio = &mut ctx.accounts.portfolio;
     
     // SECURITY VALIDATIONS
     require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(portfolio.has_enough_strategies_for_rebalance(), ErrorCode::TooFewStrategiesToRebalance);
+    portfolio.require_last_rebalance(expected_last_rebalance)?;
     
-    // Check minimum rebalance interval
+    // Pause and minimum interval gated together by can_rebalance
     let current_timestamp = Clock::get()?.unix_timestamp;
-    let time_since_last_rebalance = current_timestamp.saturating_sub(portfolio.last_rebalance);
+    require!(portfolio.can_rebalance(current_timestamp), ErrorCode::RebalanceTooSoon);
     
+    // LOAD THE FULL STRATEGY SET
     require!(
-        time_since_last_rebalance >= portfolio.min_rebalance_interval,
-        ErrorCode::InvalidRebalanceInterval
+        ctx.remaining_accounts.len() == portfolio.total_strategies as usize,
+        ErrorCode::StrategyCountMismatch
     );
     
+    let mut strategies: Vec<Account<'info, Strategy>> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(
+            !strategies.iter().any(|s| s.strategy_id == strategy.strategy_id),
+            ErrorCode::DuplicateStrategy
+        );
+        strategies.push(strategy);
+    }
+    
+    // SORT AND ASSIGN CAPITAL-WEIGHTED PERCENTILES
+    let mut inputs: Vec<RankingInput> = strategies
+        .iter()
+        .map(|s| RankingInput {
+            strategy_id: s.strategy_id,
+            performance_score: portfolio.ranking_score(s, current_timestamp),
+            tie_key: portfolio.tie_break.key(s),
+            weight: s.current_balance,
+        })
+        .collect();
+    let percentiles = rank_inputs(&mut inputs);
+    let entries: Vec<RankingEntry> = inputs
+        .iter()
+        .zip(percentiles)
+        .map(|(input, percentile)| RankingEntry {
+            strategy_id: input.strategy_id,
+            performance_score: input.performance_score,
+            weight: input.weight,
+            tie_key: input.tie_key,
+            percentile,
+            written_back: false,
+        })
+        .collect();
+    
+    let count = entries.len();
+    for entry in entries.iter() {
+        let strategy = strategies
+            .iter_mut()
+            .find(|s| s.strategy_id == entry.strategy_id)
+            .ok_or(ErrorCode::StrategyNotFound)?;
+        strategy.percentile_rank = portfolio.ranking_percentile(strategy, entry.percentile, current_timestamp);
+        strategy.exit(&crate::ID)?;
+    }
+    
+    // DISPERSION TRIGGER: only a meaningful score spread unlocks extraction/redistribution
+    let spread = score_dispersion(&entries);
+    portfolio.dispersion_gate_open = dispersion_exceeds_threshold(spread, portfolio.min_dispersion_threshold);
+    
     // UPDATE PORTFOLIO STATE
     portfolio.last_rebalance = current_timestamp;
     
+    emit!(RankingCompleted {
+        portfolio: portfolio_key,
+        strategies_ranked: count as u32,
+        score_spread: spread,
+        rebalance_permitted: portfolio.rebalance_permitted(),
+        timestamp: current_timestamp,
+    });
+    
     msg!("Ranking cycle executed at timestamp: {}", current_timestamp);
-    msg!("Portfolio has {} total strategies", portfolio.total_strategies);
+    msg!("Ranked {} strategies, score spread {} (rebalance permitted: {})",
+         count, spread, portfolio.rebalance_permitted());
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+// PERCENTILE RANKING HELPERS
+// What execute_ranking_cycle ranks a strategy by
+pub struct RankingInput {
+    pub strategy_id: Pubkey,
+    pub performance_score: u64,
+    pub tie_key: i128,                      // TieBreak::key; higher wins a score tie
+    pub weight: u64,                        // Current balance
+}
+
+// Sorts ascending by score, then tie_key, then strategy_id bytes, and returns
+// the capital-weighted percentile of each input in that order. strategy_id is
+// the final fallback so the order is total whatever the tie-break; inputs
+// equal on both score and tie_key share a percentile.
+pub fn rank_inputs(inputs: &mut [RankingInput]) -> Vec<u8> {
+    inputs.sort_by(|a, b| {
+        a.performance_score
+            .cmp(&b.performance_score)
+            .then_with(|| a.tie_key.cmp(&b.tie_key))
+            .then_with(|| a.strategy_id.to_bytes().cmp(&b.strategy_id.to_bytes()))
+    });
+    let weighted: Vec<((u64, i128), u64)> = inputs
+        .iter()
+        .map(|input| ((input.performance_score, input.tie_key), input.weight))
+        .collect();
+    weighted_percentiles(&weighted)
+}
+
+// Ranks a complete two-phase buffer exactly as execute_ranking_cycle ranks the
+// same strategies, sorting the entries and storing each one's percentile for
+// finalize_ranking to write back. Ties follow the TieBreak keys captured at
+// submission, then strategy_id.
+pub fn rank_buffered_entries(entries: &mut [RankingEntry]) {
+    let mut inputs: Vec<RankingInput> = entries
+        .iter()
+        .map(|entry| RankingInput {
+            strategy_id: entry.strategy_id,
+            performance_score: entry.performance_score,
+            tie_key: entry.tie_key,
+            weight: entry.weight,
+        })
+        .collect();
+    let percentiles = rank_inputs(&mut inputs);
+    
+    let ranked: Vec<RankingEntry> = inputs
+        .iter()
+        .zip(percentiles)
+        .filter_map(|(input, percentile)| {
+            entries
+                .iter()
+                .find(|entry| entry.strategy_id == input.strategy_id)
+                .map(|&entry| RankingEntry { percentile, ..entry })
+        })
+        .collect();
+    entries.copy_from_slice(&ranked);
+}
+
+// Spread between the top and bottom score of sorted entries
+pub fn score_dispersion(sorted_entries: &[RankingEntry]) -> u64 {
+    match (sorted_entries.first(), sorted_entries.last()) {
+        (Some(bottom), Some(top)) => top.performance_score.saturating_sub(bottom.performance_score),
+        _ => 0,
+    }
+}
+
+// A threshold of 0 disables the trigger
+pub fn dispersion_exceeds_threshold(spread: u64, min_dispersion_threshold: u16) -> bool {
+    min_dispersion_threshold == 0 || spread > min_dispersion_threshold as u64
+}
+
+// CAPITAL-WEIGHTED PERCENTILES
+// For (rank key, weight) pairs sorted ascending by key, each strategy's percentile is
+//   floor(100 * W_below / (W_total - W_self))
+// where W_below is the weight of strategies keyed strictly lower. Ties share
+// a percentile instead of being spread by strategy_id, the bottom score gets 0
+// and a strictly highest score gets 100. Weights are current balances floored
+// at 1 so unfunded strategies still count; a strategy with nothing else
+// weighted against it gets 100.
+pub fn weighted_percentiles<K: PartialEq + Copy>(sorted: &[(K, u64)]) -> Vec<u8> {
+    let weight = |w: u64| w.max(1) as u128;
+    let total: u128 = sorted.iter().map(|&(_, w)| weight(w)).sum();
+    
+    let mut percentiles = Vec::with_capacity(sorted.len());
+    let mut below: u128 = 0;
+    let mut i = 0;
+    while i < sorted.len() {
+        let score = sorted[i].0;
+        let tie_end = sorted[i..].iter().position(|&(s, _)| s != score).map_or(sorted.len(), |n| i + n);
+        
+        for &(_, w) in &sorted[i..tie_end] {
+            let others = total - weight(w);
+            percentiles.push((below * 100).checked_div(others).map_or(100, |p| p as u8));
+        }
+        
+        below += sorted[i..tie_end].iter().map(|&(_, w)| weight(w)).sum::<u128>();
+        i = tie_end;
+    }
+    percentiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn entry(strategy_id: Pubkey, performance_score: u64, weight: u64) -> RankingEntry {
+        RankingEntry { strategy_id, performance_score, weight, tie_key: 0, percentile: 0, written_back: false }
+    }
+    
+    #[test]
+    fn test_buffered_entries_rank_like_a_single_cycle() {
+        let low = Pubkey::new_from_array([1u8; 32]);
+        let high = Pubkey::new_from_array([2u8; 32]);
+        let bottom = Pubkey::new_from_array([3u8; 32]);
+        let leader = Pubkey::new_from_array([4u8; 32]);
+        let scored = [(high, 5000, 1_000_000_000), (low, 5000, 1_000_000_000), (bottom, 100, 6_000_000_000), (leader, 5001, 1_000_000_000)];
+        
+        let mut entries: Vec<RankingEntry> = scored.iter().map(|&(id, score, weight)| entry(id, score, weight)).collect();
+        rank_buffered_entries(&mut entries);
+        
+        // Sorted by score with ties in strategy_id order
+        let order: Vec<Pubkey> = entries.iter().map(|e| e.strategy_id).collect();
+        assert_eq!(order, vec![bottom, low, high, leader]);
+        
+        // The same capital-weighted percentiles execute_ranking_cycle assigns
+        let mut inputs: Vec<RankingInput> = scored
+            .iter()
+            .map(|&(strategy_id, performance_score, weight)| RankingInput { strategy_id, performance_score, tie_key: 0, weight })
+            .collect();
+        let expected = rank_inputs(&mut inputs);
+        assert_eq!(entries.iter().map(|e| e.percentile).collect::<Vec<u8>>(), expected);
+        assert_eq!(expected, vec![0, 75, 75, 100]);
+    }
+    
+    #[test]
+    fn test_dispersion_trigger() {
+        let ranked = |scores: &[u64]| -> Vec<RankingEntry> {
+            let mut entries: Vec<RankingEntry> = scores
+                .iter()
+                .map(|&performance_score| entry(Pubkey::new_unique(), performance_score, 1))
+                .collect();
+            rank_buffered_entries(&mut entries);
+            entries
+        };
+        
+        // Tightly clustered scores: nothing worth rebalancing
+        let clustered = score_dispersion(&ranked(&[5100, 5000, 5050, 4980]));
+        assert_eq!(clustered, 120);
+        assert!(!dispersion_exceeds_threshold(clustered, 1000));
+        
+        // Wide spread: rebalancing proceeds
+        let wide = score_dispersion(&ranked(&[8200, 5000, 2100]));
+        assert_eq!(wide, 6100);
+        assert!(dispersion_exceeds_threshold(wide, 1000));
+        
+        // Disabled trigger always permits
+        assert!(dispersion_exceeds_threshold(clustered, 0));
+        assert_eq!(score_dispersion(&[]), 0);
+    }
+    
+    #[test]
+    fn test_weighted_percentiles_with_tied_cluster() {
+        // A bottom strategy, three tied at 5000, and one marginally ahead
+        let sorted = [
+            (100, 1_000_000_000),
+            (5000, 1_000_000_000),
+            (5000, 1_000_000_000),
+            (5000, 1_000_000_000),
+            (5001, 1_000_000_000),
+        ];
+        
+        // Positional ranks would push one tied strategy to 75 on id alone;
+        // weighted ranks keep the cluster together and lift the leader clear of it
+        assert_eq!(weighted_percentiles(&sorted), vec![0, 25, 25, 25, 100]);
+        
+        // Capital shifts the cluster: a heavy bottom strategy raises everyone above it
+        let heavy_bottom = [
+            (100, 6_000_000_000),
+            (5000, 1_000_000_000),
+            (5000, 1_000_000_000),
+            (5001, 1_000_000_000),
+        ];
+        assert_eq!(weighted_percentiles(&heavy_bottom), vec![0, 75, 75, 100]);
+        
+        // Unfunded strategies still count, and a lone strategy tops out
+        assert_eq!(weighted_percentiles(&[(10, 0), (20, 0)]), vec![0, 100]);
+        assert_eq!(weighted_percentiles(&[(10, 5)]), vec![100]);
+        assert!(weighted_percentiles::<u64>(&[]).is_empty());
+    }
+    
+    #[test]
+    fn test_tie_break_modes_reorder_tied_leaders() {
+        // Three strategies tied on score above a clear bottom; each mode favours a different one
+        let at = |byte: u8| Pubkey::new_from_array([byte; 32]);
+        let tied = [
+            // (id, creation_time, balance, volatility)
+            (at(1), 1_000, 1_000_000_000, 5000),    // oldest
+            (at(2), 5_000, 9_000_000_000, 4000),    // richest
+            (at(3), 9_000, 1_000_000_000, 1000),    // calmest
+        ];
+        let winner = |tie_break: TieBreak| -> (Pubkey, Vec<u8>) {
+            let mut inputs: Vec<RankingInput> = tied
+                .iter()
+                .map(|&(strategy_id, creation_time, balance, volatility)| {
+                    let mut strategy = crate::state::tests::test_strategy(balance, balance);
+                    strategy.creation_time = creation_time;
+                    strategy.volatility_score = volatility;
+                    RankingInput {
+                        strategy_id,
+                        performance_score: 5000,
+                        tie_key: tie_break.key(&strategy),
+                        weight: 1_000_000_000,
+                    }
+                })
+                .collect();
+            inputs.push(RankingInput { strategy_id: at(9), performance_score: 100, tie_key: 0, weight: 1_000_000_000 });
+            let percentiles = rank_inputs(&mut inputs);
+            (inputs.last().unwrap().strategy_id, percentiles)
+        };
+        
+        assert_eq!(winner(TieBreak::OldestFirst).0, at(1));
+        assert_eq!(winner(TieBreak::HighestBalance).0, at(2));
+        assert_eq!(winner(TieBreak::LowestVolatility).0, at(3));
+        
+        // A configured key spreads the tie; the default keeps it shared and
+        // only orders it, by strategy_id
+        assert_eq!(winner(TieBreak::OldestFirst).1, vec![0, 33, 66, 100]);
+        let (last, shared) = winner(TieBreak::StrategyId);
+        assert_eq!(last, at(3));
+        assert_eq!(shared, vec![0, 33, 33, 33]);
+    }
+    
+    #[test]
+    fn test_buffered_entries_apply_the_tie_break() {
+        // Tied on score, the older strategy wins under OldestFirst even though
+        // its strategy_id sorts first
+        let older = Pubkey::new_from_array([1u8; 32]);
+        let newer = Pubkey::new_from_array([2u8; 32]);
+        let tie_key = |creation_time: i64| {
+            let mut strategy = crate::state::tests::test_strategy(1_000_000_000, 1_000_000_000);
+            strategy.creation_time = creation_time;
+            TieBreak::OldestFirst.key(&strategy)
+        };
+        
+        let mut entries = vec![
+            RankingEntry { tie_key: tie_key(1_000), ..entry(older, 5000, 1_000_000_000) },
+            RankingEntry { tie_key: tie_key(9_000), ..entry(newer, 5000, 1_000_000_000) },
+        ];
+        rank_buffered_entries(&mut entries);
+        
+        assert_eq!(entries[1].strategy_id, older);
+        assert_eq!(entries.iter().map(|e| e.percentile).collect::<Vec<u8>>(), vec![0, 100]);
+    }
+}
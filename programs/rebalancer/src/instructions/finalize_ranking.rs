@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::execute_ranking::rank_buffered_entries;
+
+#[derive(Accounts)]
+pub struct FinalizeRanking<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"ranking_buffer", portfolio.key().as_ref()],
+        bump = ranking_buffer.bump,
+        constraint = ranking_buffer.to_account_info().data_len() == RankingBuffer::MAX_SIZE
+            @ ErrorCode::RankingBufferOutdated
+    )]
+    pub ranking_buffer: Account<'info, RankingBuffer>,
+    
+    pub manager: Signer<'info>,
+}
+
+// TWO-PHASE RANKING: PHASE 2
+// Computes capital-weighted percentiles over the complete buffer with the same
+// helpers as execute_ranking_cycle, so either path ranks the same strategies
+// alike, and writes them back to the (writable) strategy accounts passed via
+// remaining_accounts, one batch per call.
+// The cycle is held to the same minimum interval as execute_ranking_cycle when
+// it is sorted; the batches after that only complete it.
+pub fn finalize_ranking<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FinalizeRanking<'info>>,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let ranking_buffer = &mut ctx.accounts.ranking_buffer;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    
+    let was_complete = ranking_buffer.is_complete();
+    
+    // SORT ONCE THE BUFFER HOLDS EVERY REGISTERED STRATEGY
+    if !ranking_buffer.sorted {
+        require!(
+            ranking_buffer.entries.len() == portfolio.total_strategies as usize,
+            ErrorCode::RankingIncomplete
+        );
+        require!(portfolio.can_rebalance(current_time), ErrorCode::RebalanceTooSoon);
+        rank_buffered_entries(&mut ranking_buffer.entries);
+        ranking_buffer.sorted = true;
+    }
+    
+    let count = ranking_buffer.entries.len();
+    
+    for account_info in ctx.remaining_accounts.iter() {
+        let mut strategy = Strategy::load_checked(account_info, &portfolio.key())?;
+        
+        let position = ranking_buffer.entries
+            .iter()
+            .position(|e| e.strategy_id == strategy.strategy_id)
+            .ok_or(ErrorCode::StrategyNotFound)?;
+        
+        strategy.percentile_rank = portfolio.ranking_percentile(
+            &strategy, ranking_buffer.entries[position].percentile, current_time
+        );
+        strategy.exit(&crate::ID)?;
+        
+        let entry = &mut ranking_buffer.entries[position];
+        if !entry.written_back {
+            entry.written_back = true;
+            ranking_buffer.written_back = ranking_buffer.written_back
+                .checked_add(1)
+                .ok_or(ErrorCode::BalanceOverflow)?;
+        }
+    }
+    
+    msg!("Ranking batch finalized: {}/{} ranks written", ranking_buffer.written_back, count);
+    
+    if !was_complete && ranking_buffer.is_complete() {
+        portfolio.last_rebalance = current_time;
+        
+        emit!(RankingFinalized {
+            portfolio: portfolio.key(),
+            strategies_ranked: ranking_buffer.written_back,
+            timestamp: current_time,
+        });
+        
+        msg!("Ranking cycle finalized at timestamp: {}", current_time);
+    }
+    
+    Ok(())
+}
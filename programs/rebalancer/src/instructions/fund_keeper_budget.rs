@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use crate::instructions::collect_performance_fees::pay_portfolio_lamports;
+
+#[derive(Accounts)]
+pub struct FundKeeperBudget<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = KeeperBudget::MAX_SIZE,
+        seeds = [b"keeper_budget", portfolio.key().as_ref()],
+        bump
+    )]
+    pub keeper_budget: Account<'info, KeeperBudget>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Set aside undeployed portfolio capital for keepers' transaction costs. The
+// amount is booked out of undeployed_capital like a redistribution and its
+// lamports leave the portfolio account, so the manager's wallet only pays the
+// budget account's rent.
+pub fn fund_keeper_budget(
+    ctx: Context<FundKeeperBudget>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, ErrorCode::InsufficientBalance);
+    
+    ctx.accounts.portfolio.deploy_capital(amount)?;
+    
+    // MOVE LAMPORTS FROM THE PORTFOLIO INTO THE BUDGET VAULT
+    pay_portfolio_lamports(&ctx.accounts.portfolio, &ctx.accounts.keeper_budget.to_account_info(), amount)?;
+    
+    let keeper_budget = &mut ctx.accounts.keeper_budget;
+    keeper_budget.portfolio = ctx.accounts.portfolio.key();
+    keeper_budget.bump = ctx.bumps.keeper_budget;
+    keeper_budget.balance = keeper_budget.balance
+        .checked_add(amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    keeper_budget.total_funded = keeper_budget.total_funded
+        .checked_add(amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    emit!(KeeperBudgetFunded {
+        portfolio: keeper_budget.portfolio,
+        amount,
+        balance: keeper_budget.balance,
+        undeployed_capital: ctx.accounts.portfolio.undeployed_capital,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Keeper budget funded from portfolio capital: amount={}, balance={}", amount, keeper_budget.balance);
+    
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+// COMPOSITE RISK WEIGHTS (basis points, sum to 10000)
+pub const RISK_WEIGHT_VOLATILITY_BPS: u128 = 4000;
+pub const RISK_WEIGHT_CONCENTRATION_BPS: u128 = 3000;
+pub const RISK_WEIGHT_IMPERMANENT_LOSS_BPS: u128 = 2000;
+pub const RISK_WEIGHT_LEVERAGE_BPS: u128 = 1000;
+
+#[derive(Accounts)]
+pub struct GetPortfolioRiskScore<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+// Per-strategy inputs to the risk score
+#[derive(Debug, Clone, Copy)]
+pub struct RiskHolding {
+    pub balance: u64,
+    pub volatility_score: u32,
+    pub category_index: usize,
+    pub impermanent_loss_bps: u64,
+    pub leverage_bps: u64,          // 10000 = 1x; unleveraged protocols report 10000
+}
+
+// READ-ONLY: headline 0-10000 risk figure, higher = riskier.
+// remaining_accounts: the portfolio's Strategy accounts, optionally followed by
+// CapitalPosition accounts whose recorded IL is attributed to their strategy.
+pub fn get_portfolio_risk_score<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetPortfolioRiskScore<'info>>,
+) -> Result<u64> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    
+    let mut strategy_ids: Vec<Pubkey> = Vec::new();
+    let mut holdings: Vec<RiskHolding> = Vec::new();
+    
+    for account_info in ctx.remaining_accounts.iter() {
+        if let Ok(position) = Account::<CapitalPosition>::try_from(account_info) {
+            let index = strategy_ids
+                .iter()
+                .position(|id| *id == position.strategy_id)
+                .ok_or(ErrorCode::StrategyNotFound)?;
+            holdings[index].impermanent_loss_bps = holdings[index].impermanent_loss_bps
+                .max(position.impermanent_loss_bps());
+            continue;
+        }
+        
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!strategy_ids.contains(&strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        
+        strategy_ids.push(strategy.strategy_id);
+        holdings.push(RiskHolding {
+            balance: strategy.current_balance,
+            volatility_score: strategy.volatility_score,
+            category_index: strategy.protocol_type.category_index(),
+            impermanent_loss_bps: 0,
+            leverage_bps: strategy.protocol_type.leverage_bps(),
+        });
+    }
+    
+    let risk_score = calculate_portfolio_risk_score(&holdings)?;
+    msg!("Portfolio risk score: {} across {} strategies", risk_score, holdings.len());
+    
+    Ok(risk_score)
+}
+
+// PORTFOLIO RISK FORMULA (all components on a 0-10000 scale, weighted by balance):
+//   volatility    = sum(balance_i * volatility_i) / total_balance
+//   concentration = sum over protocol categories of share_k^2 / 10000   (HHI;
+//                   share_k in bps, 10000 = everything in one category)
+//   il_exposure   = min(sum(balance_i * il_bps_i) / total_balance, 10000)
+//   leverage      = min((weighted_leverage_bps - 10000) / 9, 10000)      (1x = 0, 10x = 10000)
+//   risk_score    = (40% volatility + 30% concentration + 20% il + 10% leverage)
+// An empty or zero-balance portfolio scores 0.
+pub fn calculate_portfolio_risk_score(holdings: &[RiskHolding]) -> Result<u64> {
+    let total_balance: u128 = holdings.iter().map(|h| h.balance as u128).sum();
+    if total_balance == 0 {
+        return Ok(0);
+    }
+    
+    let mut weighted_volatility = 0u128;
+    let mut weighted_il = 0u128;
+    let mut weighted_leverage = 0u128;
+    let mut category_balances = [0u128; ProtocolType::CATEGORY_COUNT];
+    
+    for holding in holdings {
+        let balance = holding.balance as u128;
+        weighted_volatility = weighted_volatility
+            .checked_add(balance * holding.volatility_score.min(10000) as u128)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        weighted_il = weighted_il
+            .checked_add(balance * holding.impermanent_loss_bps.min(10000) as u128)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        weighted_leverage = weighted_leverage
+            .checked_add(balance * holding.leverage_bps.min(100000) as u128)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        category_balances[holding.category_index] += balance;
+    }
+    
+    let volatility = weighted_volatility / total_balance;
+    let il_exposure = (weighted_il / total_balance).min(10000);
+    let leverage = ((weighted_leverage / total_balance).saturating_sub(10000) / 9).min(10000);
+    let concentration: u128 = category_balances
+        .iter()
+        .map(|&b| {
+            let share = b * 10000 / total_balance;
+            share * share / 10000
+        })
+        .sum();
+    
+    let risk_score = (volatility * RISK_WEIGHT_VOLATILITY_BPS
+        + concentration * RISK_WEIGHT_CONCENTRATION_BPS
+        + il_exposure * RISK_WEIGHT_IMPERMANENT_LOSS_BPS
+        + leverage * RISK_WEIGHT_LEVERAGE_BPS)
+        / 10000;
+    
+    Ok(risk_score.min(10000) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn holding(balance: u64, volatility_score: u32, category_index: usize, impermanent_loss_bps: u64) -> RiskHolding {
+        RiskHolding { balance, volatility_score, category_index, impermanent_loss_bps, leverage_bps: 10000 }
+    }
+    
+    #[test]
+    fn test_empty_portfolio_scores_zero() {
+        assert_eq!(calculate_portfolio_risk_score(&[]).unwrap(), 0);
+        assert_eq!(calculate_portfolio_risk_score(&[holding(0, 9000, 0, 0)]).unwrap(), 0);
+    }
+    
+    #[test]
+    fn test_single_category_volatile_portfolio() {
+        // 50% volatility, fully concentrated, no IL: 0.4 * 5000 + 0.3 * 10000
+        let score = calculate_portfolio_risk_score(&[holding(1_000_000_000, 5000, 1, 0)]).unwrap();
+        assert_eq!(score, 5000);
+    }
+    
+    #[test]
+    fn test_diversification_and_il_move_score() {
+        let concentrated = calculate_portfolio_risk_score(&[
+            holding(1_000_000_000, 3000, 0, 0),
+            holding(1_000_000_000, 3000, 0, 0),
+        ]).unwrap();
+        let diversified = calculate_portfolio_risk_score(&[
+            holding(1_000_000_000, 3000, 0, 0),
+            holding(1_000_000_000, 3000, 2, 0),
+        ]).unwrap();
+        let with_il = calculate_portfolio_risk_score(&[
+            holding(1_000_000_000, 3000, 0, 0),
+            holding(1_000_000_000, 3000, 2, 2000),
+        ]).unwrap();
+        
+        assert!(diversified < concentrated);
+        assert_eq!(with_il - diversified, 200); // 1000 bps weighted IL * 20%
+    }
+}
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+pub const MAX_SUMMARY_STRATEGIES: usize = 50;
+
+#[derive(Accounts)]
+pub struct GetPortfolioSummary<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct PortfolioSummary {
+    pub total_balance: u64,
+    pub weighted_avg_yield: u64,        // Basis points, weighted by current_balance
+    pub weighted_avg_volatility: u32,   // 0-10000, weighted by current_balance
+    pub active_count: u32,
+    pub paused_count: u32,
+    pub deprecated_count: u32,
+}
+
+// Per-strategy inputs to the summary
+#[derive(Debug, Clone, Copy)]
+pub struct SummaryHolding {
+    pub balance: u64,
+    pub yield_rate: u64,
+    pub volatility_score: u32,
+    pub status: StrategyStatus,
+}
+
+// READ-ONLY: aggregate balance, balance-weighted yield and volatility, and
+// status counts over the Strategy accounts passed via remaining_accounts
+pub fn get_portfolio_summary<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetPortfolioSummary<'info>>,
+) -> Result<PortfolioSummary> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_SUMMARY_STRATEGIES, ErrorCode::TooManyStrategies);
+    
+    let mut strategy_ids: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut holdings: Vec<SummaryHolding> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!strategy_ids.contains(&strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        
+        strategy_ids.push(strategy.strategy_id);
+        holdings.push(SummaryHolding {
+            balance: strategy.current_balance,
+            yield_rate: strategy.yield_rate,
+            volatility_score: strategy.volatility_score,
+            status: strategy.status,
+        });
+    }
+    
+    let summary = summarize_holdings(&holdings)?;
+    msg!("Portfolio summary: balance={}, yield={}bps, volatility={}, active={}, paused={}, deprecated={}",
+         summary.total_balance, summary.weighted_avg_yield, summary.weighted_avg_volatility,
+         summary.active_count, summary.paused_count, summary.deprecated_count);
+    
+    Ok(summary)
+}
+
+// Averages are 0 when the holdings carry no balance
+pub fn summarize_holdings(holdings: &[SummaryHolding]) -> Result<PortfolioSummary> {
+    let mut summary = PortfolioSummary::default();
+    let mut yield_sum = 0u128;
+    let mut volatility_sum = 0u128;
+    
+    for holding in holdings.iter() {
+        summary.total_balance = summary.total_balance
+            .checked_add(holding.balance)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        yield_sum += holding.balance as u128 * holding.yield_rate as u128;
+        volatility_sum += holding.balance as u128 * holding.volatility_score as u128;
+        
+        match holding.status {
+            StrategyStatus::Active => summary.active_count += 1,
+            StrategyStatus::Paused => summary.paused_count += 1,
+            StrategyStatus::Deprecated => summary.deprecated_count += 1,
+        }
+    }
+    
+    if summary.total_balance > 0 {
+        summary.weighted_avg_yield = (yield_sum / summary.total_balance as u128) as u64;
+        summary.weighted_avg_volatility = (volatility_sum / summary.total_balance as u128) as u32;
+    }
+    
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn holding(balance: u64, yield_rate: u64, volatility_score: u32, status: StrategyStatus) -> SummaryHolding {
+        SummaryHolding { balance, yield_rate, volatility_score, status }
+    }
+    
+    #[test]
+    fn test_summary_weights_by_balance() {
+        let summary = summarize_holdings(&[
+            holding(3_000_000_000, 1000, 2000, StrategyStatus::Active),
+            holding(1_000_000_000, 3000, 6000, StrategyStatus::Paused),
+            holding(0, 9000, 9000, StrategyStatus::Deprecated),
+        ]).unwrap();
+        
+        assert_eq!(summary.total_balance, 4_000_000_000);
+        assert_eq!(summary.weighted_avg_yield, 1500);       // (3*1000 + 1*3000) / 4
+        assert_eq!(summary.weighted_avg_volatility, 3000);  // (3*2000 + 1*6000) / 4
+        assert_eq!((summary.active_count, summary.paused_count, summary.deprecated_count), (1, 1, 1));
+        
+        let empty = summarize_holdings(&[holding(0, 5000, 5000, StrategyStatus::Active)]).unwrap();
+        assert_eq!(empty.weighted_avg_yield, 0);
+        assert_eq!(empty.active_count, 1);
+    }
+}
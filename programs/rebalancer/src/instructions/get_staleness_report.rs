@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+// Return data is capped at 1024 bytes; each entry serializes to 41 bytes
+pub const MAX_STALENESS_ENTRIES: usize = 24;
+
+#[derive(Accounts)]
+pub struct GetStalenessReport<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct StalenessEntry {
+    pub strategy_id: Pubkey,
+    pub seconds_since_update: i64,
+    pub is_stale: bool,
+}
+
+// READ-ONLY: reports metric age for each strategy passed via remaining_accounts,
+// judged against the portfolio's freshness window
+pub fn get_staleness_report<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetStalenessReport<'info>>,
+) -> Result<Vec<StalenessEntry>> {
+    let portfolio = &ctx.accounts.portfolio;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_STALENESS_ENTRIES, ErrorCode::TooManyStrategies);
+    
+    let mut report = Vec::with_capacity(ctx.remaining_accounts.len());
+    
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio.key())?;
+        
+        report.push(StalenessEntry {
+            strategy_id: strategy.strategy_id,
+            seconds_since_update: strategy.seconds_since_update(current_time),
+            is_stale: strategy.is_stale(current_time, portfolio.freshness_window_seconds),
+        });
+    }
+    
+    Ok(report)
+}
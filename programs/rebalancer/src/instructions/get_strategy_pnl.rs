@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct GetStrategyPnl<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    // Omit when the strategy has no capital position (no accrued fees)
+    #[account(
+        seeds = [b"position", strategy.key().as_ref(), &[position.position_index]],
+        bump = position.bump
+    )]
+    pub position: Option<Account<'info, CapitalPosition>>,
+}
+
+// READ-ONLY: canonical lifetime profit and loss of a strategy, in lamports
+pub fn get_strategy_pnl(
+    ctx: Context<GetStrategyPnl>,
+    strategy_id: Pubkey,
+) -> Result<i64> {
+    let strategy = &ctx.accounts.strategy;
+    let accrued_fees = ctx.accounts.position
+        .as_ref()
+        .map_or(0, |position| position.accrued_fees);
+    
+    let pnl = calculate_lifetime_pnl(
+        strategy.current_balance,
+        strategy.total_withdrawals,
+        accrued_fees,
+        strategy.total_deposits,
+    )?;
+    
+    msg!("Strategy {} lifetime PnL: {} lamports", strategy_id, pnl);
+    
+    Ok(pnl)
+}
+
+// LIFETIME PNL FORMULA (signed lamports):
+//   pnl = (current_balance + total_withdrawals + accrued_fees) - total_deposits
+// Impermanent loss is not subtracted separately: it is already reflected in
+// current_balance. A new strategy with no activity reports 0.
+pub fn calculate_lifetime_pnl(
+    current_balance: u64,
+    total_withdrawals: u64,
+    accrued_fees: u64,
+    total_deposits: u64,
+) -> Result<i64> {
+    let value_returned = current_balance as i128 + total_withdrawals as i128 + accrued_fees as i128;
+    let pnl = value_returned - total_deposits as i128;
+    
+    i64::try_from(pnl).map_err(|_| ErrorCode::BalanceOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_lifetime_pnl_sign_conventions() {
+        // New strategy with no activity
+        assert_eq!(calculate_lifetime_pnl(0, 0, 0, 0).unwrap(), 0);
+        
+        // Deposited 2 SOL, withdrew 0.5, holds 1.7, earned 0.1 in fees
+        assert_eq!(
+            calculate_lifetime_pnl(1_700_000_000, 500_000_000, 100_000_000, 2_000_000_000).unwrap(),
+            300_000_000
+        );
+        
+        // Losses report negative
+        assert_eq!(calculate_lifetime_pnl(800_000_000, 0, 0, 1_000_000_000).unwrap(), -200_000_000);
+        
+        assert!(calculate_lifetime_pnl(u64::MAX, u64::MAX, 0, 0).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+// Return data is capped at 1024 bytes; each entry serializes to 41 bytes
+pub const MAX_RANKING_ENTRIES: usize = 24;
+
+#[derive(Accounts)]
+pub struct GetStrategyRankings<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct RankingRow {
+    pub strategy_id: Pubkey,
+    pub performance_score: u64,
+    pub percentile_rank: u8,
+}
+
+// READ-ONLY: the leaderboard of the strategies passed via remaining_accounts,
+// as stored by the last ranking cycle, best first
+pub fn get_strategy_rankings<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetStrategyRankings<'info>>,
+) -> Result<Vec<RankingRow>> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_RANKING_ENTRIES, ErrorCode::TooManyStrategies);
+    
+    let mut rows: Vec<RankingRow> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(
+            !rows.iter().any(|r| r.strategy_id == strategy.strategy_id),
+            ErrorCode::DuplicateStrategy
+        );
+        rows.push(RankingRow {
+            strategy_id: strategy.strategy_id,
+            performance_score: strategy.performance_score,
+            percentile_rank: strategy.percentile_rank,
+        });
+    }
+    
+    sort_leaderboard(&mut rows);
+    Ok(rows)
+}
+
+// Descending by percentile, then score; remaining ties by strategy_id bytes
+// so the order matches across calls
+pub fn sort_leaderboard(rows: &mut [RankingRow]) {
+    rows.sort_by(|a, b| {
+        b.percentile_rank
+            .cmp(&a.percentile_rank)
+            .then_with(|| b.performance_score.cmp(&a.performance_score))
+            .then_with(|| a.strategy_id.to_bytes().cmp(&b.strategy_id.to_bytes()))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_leaderboard_sorted_best_first() {
+        let row = |id: u8, performance_score: u64, percentile_rank: u8| RankingRow {
+            strategy_id: Pubkey::new_from_array([id; 32]),
+            performance_score,
+            percentile_rank,
+        };
+        let mut rows = vec![row(1, 4000, 25), row(2, 9000, 100), row(3, 5000, 25), row(4, 5000, 25), row(5, 100, 0)];
+        
+        sort_leaderboard(&mut rows);
+        
+        let order: Vec<u8> = rows.iter().map(|r| r.strategy_id.to_bytes()[0]).collect();
+        assert_eq!(order, vec![2, 3, 4, 1, 5]);
+        
+        // The size cap keeps the whole leaderboard inside return data
+        assert!(4 + MAX_RANKING_ENTRIES * rows[0].try_to_vec().unwrap().len() <= 1024);
+    }
+}
@@ -1,9 +1,10 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::events::*;
 use crate::error::ErrorCode;
 
 #[derive(Accounts)]
-#[instruction(manager: Pubkey, rebalance_threshold: u8, min_rebalance_interval: i64)]
+#[instruction(manager: Pubkey, rebalance_threshold: u8, min_rebalance_interval: i64, max_strategies: u16, emergency_destination: Pubkey)]
 pub struct InitializePortfolio<'info> {
     #[account(
         init,
@@ -28,7 +29,10 @@ pub fn initialize_portfolio(
     manager: Pubkey,
     rebalance_threshold: u8,
     min_rebalance_interval: i64,
+    max_strategies: u16,
+    emergency_destination: Pubkey,
 ) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
     let portfolio = &mut ctx.accounts.portfolio;
     let current_time = Clock::get()?.unix_timestamp;
     
@@ -36,6 +40,8 @@ pub fn initialize_portfolio(
     require!(manager != Pubkey::default(), ErrorCode::InvalidManager);
     Portfolio::validate_rebalance_threshold(rebalance_threshold)?;
     Portfolio::validate_min_interval(min_rebalance_interval)?;
+    Portfolio::validate_max_strategies(max_strategies)?;
+    Portfolio::validate_emergency_destination(&emergency_destination, &portfolio_key)?;
     
     // INITIALIZATION WITH SAFE DEFAULTS
     portfolio.manager = manager;
@@ -48,10 +54,66 @@ pub fn initialize_portfolio(
     portfolio.emergency_pause = false;
     portfolio.performance_fee_bps = 200; // 2% default performance fee
     portfolio.bump = ctx.bumps.portfolio;
-    portfolio.reserved = [0u8; 31];
+    portfolio.max_acceptable_il_bps = 0; // IL stop-loss disabled until configured
+    portfolio.fee_eligibility_seconds = 604_800; // 7 days before gains earn fees
+    portfolio.accrued_performance_fees = 0;
+    portfolio.freshness_window_seconds = 86_400; // Metrics older than 1 day are stale
+    portfolio.extraction_paused = false;
+    portfolio.entry_price_policy = EntryPricePolicy::ManagerProvided;
+    portfolio.entry_price_tolerance_bps = 100; // 1% manager/oracle deviation allowed
+    portfolio.min_improvement_ratio_bps = 10000; // Improvement must at least cover fees
+    portfolio.original_manager = manager;
+    portfolio.pending_manager = Pubkey::default();
+    portfolio.max_portfolio_leverage = 10; // Matches the per-strategy 10x limit
+    portfolio.longevity_bonus_bps_per_day = 0; // Longevity bonus disabled by default
+    portfolio.max_longevity_bonus_bps = 0;
+    portfolio.fee_treasury = Pubkey::default(); // Fees cannot be collected until configured
+    portfolio.fee_token_mint = Pubkey::default(); // Fees settle in SOL
+    portfolio.emergency_destination = emergency_destination; // Never changes: a compromised manager can't redirect it
+    portfolio.reentry_cooldown_seconds = 604_800; // Exited targets stay out for 7 days
+    portfolio.recent_exits = RecentExits::default();
+    portfolio.total_fees_allocated = 0;
+    portfolio.min_dispersion_threshold = 0; // Rebalance on interval alone
+    portfolio.dispersion_gate_open = false;
+    portfolio.max_positions_per_strategy = 4;
+    portfolio.free_rebalances_per_period = 0; // Every rebalance pays fees
+    portfolio.free_rebalances_used = 0;
+    portfolio.free_rebalance_period_seconds = 2_592_000; // 30 days
+    portfolio.free_rebalance_window_start = 0;
+    portfolio.settlement_delay_seconds = [0; ProtocolType::CATEGORY_COUNT]; // Capital earns on arrival
+    portfolio.max_extraction_fee_bps = 1000; // Exits may cost at most 10% of the amount
+    portfolio.max_uncollected_fees = 100_000_000_000; // 100 SOL of uncollected fees
+    portfolio.set_scoring_weights(ScoringWeights::LEGACY)?;
+    portfolio.staleness_halflife = 0; // Stale metrics rank at full score until configured
+    portfolio.min_strategies_for_rebalance = 2; // Ranking needs something to compare against
+    portfolio.ranking_mode = RankingMode::Composite;
+    portfolio.strategy_cooldown = 0; // Strategies may be touched every rebalance
+    portfolio.schema_version = CURRENT_PORTFOLIO_SCHEMA;
+    portfolio.volatility_circuit_bps = 0; // No automatic pausing until configured
+    portfolio.max_yield_delta_bps = 0; // Any yield change accepted until configured
+    portfolio.rent_buffer_lamports = DEFAULT_RENT_BUFFER_LAMPORTS
+        .max(Portfolio::rent_exempt_floor(&Rent::get()?));
+    portfolio.emergency_extract_count = 0;
+    portfolio.tvl_snapshot_interval = 3_600; // At most hourly TVL snapshots
+    portfolio.tie_break = TieBreak::StrategyId;
+    portfolio.reconcile_tolerance_bps = 500; // 5% of net principal
+    portfolio.max_strategies = max_strategies;
+    portfolio.denomination_enabled = false; // Scores in lamports until a denomination is set
+    portfolio.undeployed_capital = 0;
+    portfolio.reserved = [0u8; 26];
     
-    msg!("Portfolio initialized: manager={}, threshold={}%, interval={}s", 
-         manager, rebalance_threshold, min_rebalance_interval);
+    emit!(PortfolioInitialized {
+        portfolio: portfolio_key,
+        manager,
+        rebalance_threshold,
+        min_rebalance_interval,
+        max_strategies,
+        emergency_destination,
+        timestamp: current_time,
+    });
+    
+    msg!("Portfolio initialized: manager={}, threshold={}%, interval={}s, max_strategies={}, emergency_destination={}", 
+         manager, rebalance_threshold, min_rebalance_interval, max_strategies, emergency_destination);
     
     Ok(())
 }
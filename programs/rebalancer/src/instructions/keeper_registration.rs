@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+// KEEPER REGISTRATION
+// The manager registers the keeper signers allowed to draw on the keeper
+// budget. A keeper is registered while its [b"keeper", portfolio, keeper] PDA
+// exists; revoking closes it and returns the rent to the manager.
+
+#[derive(Accounts)]
+#[instruction(keeper: Pubkey)]
+pub struct RegisterKeeper<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init,
+        payer = manager,
+        space = KeeperRegistration::MAX_SIZE,
+        seeds = [b"keeper", portfolio.key().as_ref(), keeper.as_ref()],
+        bump
+    )]
+    pub keeper_registration: Account<'info, KeeperRegistration>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn register_keeper(
+    ctx: Context<RegisterKeeper>,
+    keeper: Pubkey,
+) -> Result<()> {
+    require!(keeper != Pubkey::default(), ErrorCode::InvalidKeeper);
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    let registration = &mut ctx.accounts.keeper_registration;
+    registration.portfolio = ctx.accounts.portfolio.key();
+    registration.keeper = keeper;
+    registration.registered_at = current_time;
+    registration.bump = ctx.bumps.keeper_registration;
+    
+    emit!(KeeperRegistered {
+        portfolio: registration.portfolio,
+        keeper,
+        timestamp: current_time,
+    });
+    
+    msg!("Keeper registered: {}", keeper);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(keeper: Pubkey)]
+pub struct RevokeKeeper<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        close = manager,
+        seeds = [b"keeper", portfolio.key().as_ref(), keeper.as_ref()],
+        bump = keeper_registration.bump
+    )]
+    pub keeper_registration: Account<'info, KeeperRegistration>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+}
+
+pub fn revoke_keeper(
+    ctx: Context<RevokeKeeper>,
+    keeper: Pubkey,
+) -> Result<()> {
+    emit!(KeeperRevoked {
+        portfolio: ctx.accounts.portfolio.key(),
+        keeper,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Keeper revoked: {}", keeper);
+    
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+// Return data is capped at 1024 bytes; each entry serializes to 40 bytes
+pub const MAX_LISTED_STRATEGIES: usize = 25;
+
+#[derive(Accounts)]
+pub struct ListStrategiesByProtocol<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+}
+
+// Protocol category to list, without the per-protocol fields of ProtocolType
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProtocolFilter {
+    Lending,
+    Farming,
+    Staking,
+    Any,
+}
+
+impl ProtocolFilter {
+    pub fn matches(&self, protocol_type: &ProtocolType) -> bool {
+        match self {
+            ProtocolFilter::Lending => matches!(protocol_type, ProtocolType::StableLending { .. }),
+            ProtocolFilter::Farming => matches!(protocol_type, ProtocolType::YieldFarming { .. }),
+            ProtocolFilter::Staking => matches!(protocol_type, ProtocolType::LiquidStaking { .. }),
+            ProtocolFilter::Any => true,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct StrategyListing {
+    pub strategy_id: Pubkey,
+    pub current_balance: u64,
+}
+
+// READ-ONLY: the strategies passed via remaining_accounts whose protocol
+// matches the filter, in the order given
+pub fn list_strategies_by_protocol<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ListStrategiesByProtocol<'info>>,
+    filter: ProtocolFilter,
+) -> Result<Vec<StrategyListing>> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut listings: Vec<StrategyListing> = Vec::new();
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!seen.contains(&strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        seen.push(strategy.strategy_id);
+        
+        if filter.matches(&strategy.protocol_type) {
+            require!(listings.len() < MAX_LISTED_STRATEGIES, ErrorCode::TooManyStrategies);
+            listings.push(StrategyListing {
+                strategy_id: strategy.strategy_id,
+                current_balance: strategy.current_balance,
+            });
+        }
+    }
+    
+    msg!("Listed {} of {} strategies matching {:?}", listings.len(), seen.len(), filter);
+    
+    Ok(listings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_filter_matches_protocol_category() {
+        let lending = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        let staking = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        
+        assert!(ProtocolFilter::Lending.matches(&lending));
+        assert!(!ProtocolFilter::Lending.matches(&staking));
+        assert!(ProtocolFilter::Staking.matches(&staking));
+        assert!(!ProtocolFilter::Farming.matches(&staking));
+        assert!(ProtocolFilter::Any.matches(&lending) && ProtocolFilter::Any.matches(&staking));
+        
+        // The size cap keeps a full listing inside return data
+        let listing = StrategyListing { strategy_id: Pubkey::new_unique(), current_balance: 1 };
+        assert!(4 + MAX_LISTED_STRATEGIES * listing.try_to_vec().unwrap().len() <= 1024);
+    }
+}
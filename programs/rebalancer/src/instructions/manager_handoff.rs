@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+// TWO-STEP MANAGER HANDOFF
+// The current manager stages a key with transfer_manager, and that key must sign
+// accept_manager to take over. The portfolio PDA is seeded by original_manager,
+// so its address does NOT change across handoffs; only the authority does.
+
+#[derive(Accounts)]
+pub struct TransferManager<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+pub fn transfer_manager(
+    ctx: Context<TransferManager>,
+    new_manager: Pubkey,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    
+    require!(new_manager != Pubkey::default(), ErrorCode::InvalidManager);
+    require!(new_manager != portfolio.manager, ErrorCode::InvalidManager);
+    
+    portfolio.pending_manager = new_manager;
+    
+    emit!(ManagerProposed {
+        portfolio: portfolio.key(),
+        current_manager: portfolio.manager,
+        proposed_manager: new_manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Manager handoff proposed: {} -> {}", portfolio.manager, new_manager);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptManager<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        constraint = portfolio.has_pending_manager() @ ErrorCode::NoPendingManager,
+        constraint = portfolio.pending_manager == new_manager.key() @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub new_manager: Signer<'info>,
+}
+
+pub fn accept_manager(ctx: Context<AcceptManager>) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let previous_manager = portfolio.manager;
+    
+    portfolio.manager = ctx.accounts.new_manager.key();
+    portfolio.pending_manager = Pubkey::default();
+    
+    emit!(ManagerTransferred {
+        portfolio: portfolio.key(),
+        previous_manager,
+        new_manager: portfolio.manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Manager handoff accepted: {} -> {}", previous_manager, portfolio.manager);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelManagerProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+pub fn cancel_manager_proposal(ctx: Context<CancelManagerProposal>) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    
+    require!(portfolio.has_pending_manager(), ErrorCode::NoPendingManager);
+    
+    let cancelled_manager = portfolio.pending_manager;
+    portfolio.pending_manager = Pubkey::default();
+    
+    emit!(ManagerProposalCancelled {
+        portfolio: portfolio.key(),
+        manager: portfolio.manager,
+        cancelled_manager,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Manager handoff to {} cancelled", cancelled_manager);
+    
+    Ok(())
+}
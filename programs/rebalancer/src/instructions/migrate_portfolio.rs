@@ -0,0 +1,306 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use crate::state::*;
+use crate::oracle::read_bytes;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+// Fixed offsets into a serialized Portfolio (after the 8-byte discriminator).
+// Everything up to original_manager predates every schema, so older accounts
+// can be checked before they are large enough to deserialize.
+pub const PORTFOLIO_MANAGER_OFFSET: usize = 8;
+pub const PORTFOLIO_BUMP_OFFSET: usize = 8 + 72;
+pub const PORTFOLIO_ORIGINAL_MANAGER_OFFSET: usize = 8 + 105;
+pub const PORTFOLIO_SCHEMA_VERSION_OFFSET: usize = 8 + 674;
+
+// Allocation size of every Portfolio layout that predates schema versioning,
+// oldest first. Fields were only ever added in front of `reserved`, so each
+// is a prefix of the current layout once zero-extended.
+pub const LEGACY_PORTFOLIO_SIZES: [usize; 12] = [144, 208, 272, 304, 632, 650, 652, 660, 666, 674, 675, 683];
+
+// Smallest layout carrying original_manager; before it the manager could not
+// change and was the PDA seed itself
+pub const ORIGINAL_MANAGER_MIN_SIZE: usize = 208;
+
+#[derive(Accounts)]
+pub struct MigratePortfolio<'info> {
+    /// CHECK: may predate the current layout; validated by hand in migrate_portfolio
+    #[account(mut, owner = crate::ID @ ErrorCode::StrategyNotFound)]
+    pub portfolio: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Schema of a serialized Portfolio, told apart by allocation size: every
+// pre-versioning layout is schema 0, current-size accounts carry the byte.
+// Only older schemas can be migrated, and each migrates once.
+pub fn migratable_schema_version(data: &[u8]) -> Result<u8> {
+    require!(
+        data.len() >= 8 && data[..8] == *Portfolio::DISCRIMINATOR,
+        ErrorCode::InvalidPortfolioAccount
+    );
+    if LEGACY_PORTFOLIO_SIZES.contains(&data.len()) {
+        return Ok(0);
+    }
+    require!(data.len() == Portfolio::MAX_SIZE, ErrorCode::InvalidPortfolioAccount);
+    
+    let version = data[PORTFOLIO_SCHEMA_VERSION_OFFSET];
+    require!(version < CURRENT_PORTFOLIO_SCHEMA, ErrorCode::PortfolioAlreadyMigrated);
+    Ok(version)
+}
+
+// Seed the portfolio PDA was derived from
+pub fn portfolio_seed_manager(data: &[u8]) -> Result<Pubkey> {
+    let offset = if data.len() < ORIGINAL_MANAGER_MIN_SIZE {
+        PORTFOLIO_MANAGER_OFFSET
+    } else {
+        PORTFOLIO_ORIGINAL_MANAGER_OFFSET
+    };
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+// Rewrites a zero-extended account image as a current Portfolio. Layouts
+// from before the emergency destination existed must commit one here, the
+// same one-time choice initialize_portfolio makes; a committed one is kept.
+pub fn upgrade_portfolio_data(
+    data: &mut [u8],
+    address: &Pubkey,
+    emergency_destination: Option<Pubkey>,
+) -> Result<()> {
+    let mut portfolio = Portfolio::try_deserialize(&mut &data[..])?;
+    if portfolio.original_manager == Pubkey::default() {
+        portfolio.original_manager = portfolio.manager;
+    }
+    match emergency_destination {
+        Some(_) if portfolio.emergency_destination != Pubkey::default() => {
+            return err!(ErrorCode::EmergencyDestinationAlreadySet);
+        },
+        Some(destination) => {
+            Portfolio::validate_emergency_destination(&destination, address)?;
+            portfolio.emergency_destination = destination;
+        },
+        None => Portfolio::validate_emergency_destination(&portfolio.emergency_destination, address)?,
+    }
+    portfolio.fill_migrated_defaults();
+    portfolio.schema_version = CURRENT_PORTFOLIO_SCHEMA;
+    
+    let mut writer: &mut [u8] = data;
+    portfolio.try_serialize(&mut writer)
+}
+
+// Reallocate a program account to `new_len`, topping up its rent from `payer`
+// and zeroing the added bytes
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let old_len = account.data_len();
+    
+    // TOP UP RENT FOR THE LARGER ACCOUNT
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let shortfall = required_lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    
+    account.resize(new_len)?;
+    account.try_borrow_mut_data()?[old_len..].fill(0);
+    Ok(())
+}
+
+// Grow a portfolio created under an older schema to Portfolio::MAX_SIZE from
+// its real historical size, zero-extending it, filling fields the old layout
+// lacked and stamping CURRENT_PORTFOLIO_SCHEMA
+pub fn migrate_portfolio(
+    ctx: Context<MigratePortfolio>,
+    emergency_destination: Option<Pubkey>,
+) -> Result<()> {
+    let portfolio_info = ctx.accounts.portfolio.to_account_info();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    let (old_len, old_version) = {
+        let data = portfolio_info.try_borrow_data()?;
+        let old_version = migratable_schema_version(&data)?;
+        
+        let manager = Pubkey::new_from_array(read_bytes(&data, PORTFOLIO_MANAGER_OFFSET)?);
+        require_keys_eq!(manager, ctx.accounts.manager.key(), ErrorCode::UnauthorizedManager);
+        
+        let expected_address = Pubkey::create_program_address(
+            &[b"portfolio", portfolio_seed_manager(&data)?.as_ref(), &[data[PORTFOLIO_BUMP_OFFSET]]],
+            &crate::ID,
+        ).map_err(|_| error!(ErrorCode::InvalidPortfolioAccount))?;
+        require_keys_eq!(expected_address, portfolio_info.key(), ErrorCode::InvalidPortfolioAccount);
+        
+        (data.len(), old_version)
+    };
+    
+    let new_len = Portfolio::MAX_SIZE;
+    grow_account(
+        &portfolio_info,
+        &ctx.accounts.manager.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        new_len,
+    )?;
+    upgrade_portfolio_data(
+        &mut portfolio_info.try_borrow_mut_data()?[..],
+        &portfolio_info.key(),
+        emergency_destination,
+    )?;
+    
+    if let Some(destination) = emergency_destination {
+        emit!(EmergencyDestinationCommitted {
+            portfolio: portfolio_info.key(),
+            destination,
+            timestamp: current_time,
+        });
+    }
+    
+    emit!(PortfolioMigrated {
+        portfolio: portfolio_info.key(),
+        old_version,
+        new_version: CURRENT_PORTFOLIO_SCHEMA,
+        new_size: new_len as u32,
+        timestamp: current_time,
+    });
+    
+    msg!("Portfolio migrated: schema {} -> {}, {} -> {} bytes",
+         old_version, CURRENT_PORTFOLIO_SCHEMA, old_len, new_len);
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_fixed_offsets_match_layout() {
+        let mut data = Portfolio::DISCRIMINATOR.to_vec();
+        data.resize(Portfolio::MAX_SIZE, 0);
+        let mut portfolio = Portfolio::try_deserialize(&mut &data[..]).unwrap();
+        portfolio.manager = Pubkey::new_unique();
+        portfolio.original_manager = Pubkey::new_unique();
+        portfolio.bump = 254;
+        portfolio.schema_version = 7;
+        
+        let mut serialized = Vec::new();
+        portfolio.try_serialize(&mut serialized).unwrap();
+        assert_eq!(serialized.len(), Portfolio::MAX_SIZE);
+        assert_eq!(serialized[PORTFOLIO_MANAGER_OFFSET..][..32], portfolio.manager.to_bytes());
+        assert_eq!(serialized[PORTFOLIO_ORIGINAL_MANAGER_OFFSET..][..32], portfolio.original_manager.to_bytes());
+        assert_eq!(serialized[PORTFOLIO_BUMP_OFFSET], 254);
+        assert_eq!(serialized[PORTFOLIO_SCHEMA_VERSION_OFFSET], 7);
+    }
+    
+    // Byte image of a Portfolio as the original program allocated it: 8 + 136
+    // bytes with bump as the last field before reserved
+    fn baseline_portfolio_image(manager: &Pubkey, bump: u8) -> Vec<u8> {
+        let mut data = Portfolio::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(manager.as_ref());
+        data.push(25);                                      // rebalance_threshold
+        data.extend_from_slice(&3u32.to_le_bytes());        // total_strategies
+        data.extend_from_slice(&7_000_000_000u64.to_le_bytes()); // total_capital_moved
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // last_rebalance
+        data.extend_from_slice(&3_600i64.to_le_bytes());    // min_rebalance_interval
+        data.extend_from_slice(&1_690_000_000i64.to_le_bytes()); // portfolio_creation
+        data.push(0);                                       // emergency_pause
+        data.extend_from_slice(&200u16.to_le_bytes());      // performance_fee_bps
+        data.push(bump);
+        data.resize(144, 0);                                // reserved: [u8; 63]
+        data
+    }
+    
+    #[test]
+    fn test_baseline_portfolio_migrates() {
+        let manager = Pubkey::new_unique();
+        let mut data = baseline_portfolio_image(&manager, 253);
+        assert_eq!(data.len(), LEGACY_PORTFOLIO_SIZES[0]);
+        assert_eq!(data[PORTFOLIO_BUMP_OFFSET], 253);
+        
+        assert_eq!(migratable_schema_version(&data).unwrap(), 0);
+        assert_eq!(portfolio_seed_manager(&data).unwrap(), manager);
+        
+        let address = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        data.resize(Portfolio::MAX_SIZE, 0);
+        
+        // The baseline layout had no emergency destination, so one must be committed
+        assert_eq!(
+            upgrade_portfolio_data(&mut data.clone(), &address, None).unwrap_err(),
+            ErrorCode::UnauthorizedEmergencyDestination.into()
+        );
+        upgrade_portfolio_data(&mut data, &address, Some(destination)).unwrap();
+        let portfolio = Portfolio::try_deserialize(&mut &data[..]).unwrap();
+        
+        // Baseline fields survive
+        assert_eq!(portfolio.manager, manager);
+        assert_eq!(portfolio.rebalance_threshold, 25);
+        assert_eq!(portfolio.total_strategies, 3);
+        assert_eq!(portfolio.total_capital_moved, 7_000_000_000);
+        assert_eq!(portfolio.last_rebalance, 1_700_000_000);
+        assert_eq!(portfolio.min_rebalance_interval, 3_600);
+        assert_eq!(portfolio.performance_fee_bps, 200);
+        assert_eq!(portfolio.bump, 253);
+        
+        // Later fields are filled so the account passes every validator
+        assert_eq!(portfolio.original_manager, manager);
+        assert_eq!(portfolio.emergency_destination, destination);
+        assert_eq!(portfolio.schema_version, CURRENT_PORTFOLIO_SCHEMA);
+        assert!(portfolio.schema_supported());
+        assert!(Portfolio::validate_freshness_window(portfolio.freshness_window_seconds).is_ok());
+        assert!(Portfolio::validate_entry_price_tolerance(portfolio.entry_price_tolerance_bps).is_ok());
+        assert!(Portfolio::validate_max_positions(portfolio.max_positions_per_strategy).is_ok());
+        assert!(Portfolio::validate_max_leverage(portfolio.max_portfolio_leverage).is_ok());
+        assert!(Portfolio::validate_improvement_ratio(portfolio.min_improvement_ratio_bps).is_ok());
+        assert!(Portfolio::validate_tvl_snapshot_interval(portfolio.tvl_snapshot_interval).is_ok());
+        assert!(Portfolio::validate_max_strategies(portfolio.max_strategies).is_ok());
+        assert!(portfolio.scoring_weights().validate().is_ok());
+        
+        // Once migrated the account is current and can't be migrated again,
+        // nor its destination re-pointed
+        assert_eq!(
+            migratable_schema_version(&data).unwrap_err(),
+            ErrorCode::PortfolioAlreadyMigrated.into()
+        );
+        assert_eq!(
+            upgrade_portfolio_data(&mut data, &address, Some(Pubkey::new_unique())).unwrap_err(),
+            ErrorCode::EmergencyDestinationAlreadySet.into()
+        );
+    }
+    
+    #[test]
+    fn test_migration_only_accepts_known_layouts() {
+        let mut data = baseline_portfolio_image(&Pubkey::new_unique(), 255);
+        
+        // Every historical size is schema 0; anything else is not a Portfolio
+        for &len in LEGACY_PORTFOLIO_SIZES.iter() {
+            data.resize(len, 0);
+            assert_eq!(migratable_schema_version(&data).unwrap(), 0);
+        }
+        data.resize(Portfolio::MAX_SIZE + 1, 0);
+        assert_eq!(migratable_schema_version(&data).unwrap_err(), ErrorCode::InvalidPortfolioAccount.into());
+        data.resize(700, 0);
+        assert_eq!(migratable_schema_version(&data).unwrap_err(), ErrorCode::InvalidPortfolioAccount.into());
+        
+        // Layouts with original_manager seed the PDA from it, not the current manager
+        let original_manager = Pubkey::new_unique();
+        data.resize(ORIGINAL_MANAGER_MIN_SIZE, 0);
+        data[PORTFOLIO_ORIGINAL_MANAGER_OFFSET..][..32].copy_from_slice(original_manager.as_ref());
+        assert_eq!(portfolio_seed_manager(&data).unwrap(), original_manager);
+    }
+}
@@ -0,0 +1,238 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::state::*;
+use crate::instructions::migrate_portfolio::grow_account;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+// Allocation size and RiskLimits length of every RiskConfig layout older than
+// the current one, oldest first. RiskLimits fields were only ever appended, so
+// each older limits encoding is a prefix of the current one; last_updated and
+// bump follow it wherever it ended.
+pub const LEGACY_RISK_CONFIG_LAYOUTS: [(usize, usize); 6] = [
+    (187, 122),
+    (187, 123),                             // normalize_to_capital, same allocation
+    (211, 147),
+    (235, 171),
+    (237, 173),
+    (245, 181),
+];
+
+pub const RISK_CONFIG_LIMITS_OFFSET: usize = 8 + 32;
+
+#[derive(Accounts)]
+pub struct MigrateRiskConfig<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    /// CHECK: may predate the current layout; decoded by hand in migrate_risk_config
+    #[account(
+        mut,
+        owner = crate::ID @ ErrorCode::InvalidRiskConfigAccount,
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump
+    )]
+    pub risk_config: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Decodes a RiskConfig written under an older layout. Limits the layout
+// lacked take their RiskLimits::default() values. Two layouts share 187
+// bytes, so the one whose bump byte matches the PDA's is used, newest first.
+pub fn decode_legacy_risk_config(data: &[u8], portfolio: &Pubkey, bump: u8) -> Result<RiskConfig> {
+    require!(
+        data.len() >= RISK_CONFIG_LIMITS_OFFSET
+            && data[..8] == *RiskConfig::DISCRIMINATOR
+            && data[8..RISK_CONFIG_LIMITS_OFFSET] == portfolio.to_bytes(),
+        ErrorCode::InvalidRiskConfigAccount
+    );
+    require!(data.len() != RiskConfig::MAX_SIZE, ErrorCode::RiskConfigAlreadyMigrated);
+    
+    let limits_len = LEGACY_RISK_CONFIG_LAYOUTS
+        .iter()
+        .rev()
+        .find(|&&(size, limits_len)| {
+            size == data.len() && data[RISK_CONFIG_LIMITS_OFFSET + limits_len + 8] == bump
+        })
+        .map(|&(_, limits_len)| limits_len)
+        .ok_or(ErrorCode::InvalidRiskConfigAccount)?;
+    
+    let mut limits_bytes = RiskLimits::default().try_to_vec()?;
+    let limits_end = RISK_CONFIG_LIMITS_OFFSET + limits_len;
+    limits_bytes[..limits_len].copy_from_slice(&data[RISK_CONFIG_LIMITS_OFFSET..limits_end]);
+    
+    Ok(RiskConfig {
+        portfolio: *portfolio,
+        limits: RiskLimits::deserialize(&mut &limits_bytes[..])?,
+        last_updated: i64::from_le_bytes(data[limits_end..limits_end + 8].try_into().unwrap()),
+        bump,
+        reserved: [0u8; 15],
+    })
+}
+
+// Grow a risk config written under an older RiskLimits layout to
+// RiskConfig::MAX_SIZE, keeping every stored limit so set_risk_config and
+// the instructions reading it can load it again
+pub fn migrate_risk_config(ctx: Context<MigrateRiskConfig>) -> Result<()> {
+    let risk_config_info = ctx.accounts.risk_config.to_account_info();
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    let old_len = risk_config_info.data_len();
+    let risk_config = decode_legacy_risk_config(
+        &risk_config_info.try_borrow_data()?,
+        &portfolio_key,
+        ctx.bumps.risk_config,
+    )?;
+    
+    grow_account(
+        &risk_config_info,
+        &ctx.accounts.manager.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        RiskConfig::MAX_SIZE,
+    )?;
+    let mut data = risk_config_info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    risk_config.try_serialize(&mut writer)?;
+    
+    emit!(RiskConfigMigrated {
+        portfolio: portfolio_key,
+        old_size: old_len as u32,
+        new_size: RiskConfig::MAX_SIZE as u32,
+        timestamp: current_time,
+    });
+    
+    msg!("Risk config migrated: {} -> {} bytes", old_len, RiskConfig::MAX_SIZE);
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Byte image of a RiskConfig as first allocated: the 122-byte limits
+    // encoding from before normalize_to_capital, then last_updated and bump
+    fn original_risk_config_image(portfolio: &Pubkey, limits: &RiskLimits, bump: u8) -> Vec<u8> {
+        let mut data = RiskConfig::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(portfolio.as_ref());
+        data.extend_from_slice(&limits.try_to_vec().unwrap()[..122]);
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.push(bump);
+        data.resize(187, 0);                                // reserved: [u8; 16]
+        data
+    }
+    
+    #[test]
+    fn test_original_risk_config_migrates() {
+        let portfolio = Pubkey::new_unique();
+        let stored = RiskLimits {
+            max_single_strategy_bps: 3000,
+            platform_fee_bps: 25,
+            platform_treasury: Pubkey::new_unique(),
+            manager_treasury: Pubkey::new_unique(),
+            max_skips: 5,
+            gas_cost_per_transfer: 5_000,
+            min_net_allocation: 50_000,
+            ..RiskLimits::default()
+        };
+        let data = original_risk_config_image(&portfolio, &stored, 251);
+        
+        let config = decode_legacy_risk_config(&data, &portfolio, 251).unwrap();
+        assert_eq!(config.portfolio, portfolio);
+        assert_eq!(config.last_updated, 1_700_000_000);
+        assert_eq!(config.bump, 251);
+        assert_eq!(config.limits.max_single_strategy_bps, 3000);
+        assert_eq!(config.limits.platform_fee_bps, 25);
+        assert_eq!(config.limits.platform_treasury, stored.platform_treasury);
+        assert_eq!(config.limits.max_skips, 5);
+        assert_eq!(config.limits.min_net_allocation, 50_000);
+        
+        // Limits added since keep their defaults rather than reading zero
+        let defaults = RiskLimits::default();
+        assert_eq!(config.limits.max_lending_bps, defaults.max_lending_bps);
+        assert_eq!(config.limits.min_balance_staking, defaults.min_balance_staking);
+        assert_eq!(config.limits.top_performer_percentile, defaults.top_performer_percentile);
+        assert_eq!(config.limits.dust_threshold_lamports, defaults.dust_threshold_lamports);
+        assert!(config.limits.validate().is_ok());
+        
+        let mut serialized = Vec::new();
+        config.try_serialize(&mut serialized).unwrap();
+        assert_eq!(serialized.len(), RiskConfig::MAX_SIZE);
+    }
+    
+    #[test]
+    fn test_same_size_layouts_told_apart_by_bump() {
+        let portfolio = Pubkey::new_unique();
+        let stored = RiskLimits { normalize_to_capital: true, ..RiskLimits::default() };
+        
+        // 123-byte limits in the same 187-byte allocation
+        let mut data = RiskConfig::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(portfolio.as_ref());
+        data.extend_from_slice(&stored.try_to_vec().unwrap()[..123]);
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.push(252);
+        data.resize(187, 0);
+        
+        let config = decode_legacy_risk_config(&data, &portfolio, 252).unwrap();
+        assert!(config.limits.normalize_to_capital);
+        assert_eq!(config.last_updated, 1_700_000_000);
+        
+        // Another portfolio's config, or one already current, is refused
+        assert!(decode_legacy_risk_config(&data, &Pubkey::new_unique(), 252).is_err());
+        data.resize(RiskConfig::MAX_SIZE, 0);
+        assert_eq!(
+            decode_legacy_risk_config(&data, &portfolio, 252).unwrap_err(),
+            ErrorCode::RiskConfigAlreadyMigrated.into()
+        );
+    }
+    
+    #[test]
+    fn test_every_legacy_layout_keeps_its_limits() {
+        let portfolio = Pubkey::new_unique();
+        let stored = RiskLimits {
+            max_single_strategy_bps: 3500,
+            normalize_to_capital: true,
+            max_farming_bps: 6000,
+            min_balance_lending: 250_000_000,
+            top_performer_percentile: 90,
+            max_top_performers: 3,
+            dust_threshold_lamports: 7,
+            sol_price_feed: Pubkey::new_unique(),
+            ..RiskLimits::default()
+        };
+        let encoded = stored.try_to_vec().unwrap();
+        let defaults = RiskLimits::default();
+        
+        for &(size, limits_len) in LEGACY_RISK_CONFIG_LAYOUTS.iter() {
+            let mut data = RiskConfig::DISCRIMINATOR.to_vec();
+            data.extend_from_slice(portfolio.as_ref());
+            data.extend_from_slice(&encoded[..limits_len]);
+            data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+            data.push(254);
+            data.resize(size, 0);
+            
+            let limits = decode_legacy_risk_config(&data, &portfolio, 254).unwrap().limits;
+            assert_eq!(limits.max_single_strategy_bps, 3500);
+            
+            // Each limit is kept where the layout had it and defaulted where it didn't
+            let had = |field_end: usize| limits_len >= field_end;
+            assert_eq!(limits.normalize_to_capital, had(123));
+            assert_eq!(limits.max_farming_bps, if had(147) { 6000 } else { defaults.max_farming_bps });
+            assert_eq!(limits.min_balance_lending, if had(171) { 250_000_000 } else { defaults.min_balance_lending });
+            assert_eq!(limits.top_performer_percentile, if had(173) { 90 } else { defaults.top_performer_percentile });
+            assert_eq!(limits.max_top_performers, if had(173) { 3 } else { defaults.max_top_performers });
+            assert_eq!(limits.dust_threshold_lamports, if had(181) { 7 } else { defaults.dust_threshold_lamports });
+            assert_eq!(limits.sol_price_feed, Pubkey::default());
+        }
+    }
+}
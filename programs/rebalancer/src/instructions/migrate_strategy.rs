@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::state::*;
+use crate::instructions::migrate_portfolio::grow_account;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+// Allocation size of every Strategy layout older than the current one, oldest
+// first. From the 208-byte layout that carried skip_count onward, fields were
+// only ever added in front of `reserved`, so each is a prefix of the current
+// layout once zero-extended.
+pub const LEGACY_STRATEGY_SIZES: [usize; 6] = [208, 242, 262, 278, 284, 349];
+
+// current_balance through creation_time: the fixed-size fields every layout
+// shares after protocol_type
+pub const STRATEGY_SHARED_FIXED_LEN: usize = 62;
+
+// Bytes the earliest 208-byte layouts lack in front of bump: skip_count alone,
+// or high_water_mark and skip_count together. 0 is the current layout.
+const MISSING_BEFORE_BUMP: [usize; 3] = [0, 1, 9];
+
+#[derive(Accounts)]
+pub struct MigrateStrategy<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    /// CHECK: may predate the current layout; validated by hand in migrate_strategy
+    #[account(mut, owner = crate::ID @ ErrorCode::StrategyNotFound)]
+    pub strategy: UncheckedAccount<'info>,
+    
+    // Lamports already in the vault are counted as deposited capital
+    #[account(
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Offset just past creation_time, where the historical layouts diverge
+fn creation_time_end(data: &[u8]) -> Result<usize> {
+    let mut rest = data.get(8 + 32..).ok_or(ErrorCode::StrategyNotFound)?;
+    ProtocolType::deserialize(&mut rest).map_err(|_| error!(ErrorCode::StrategyNotFound))?;
+    Ok(data.len() - rest.len() + STRATEGY_SHARED_FIXED_LEN)
+}
+
+// Rewrites a zero-extended account image of `address`, a strategy of
+// `portfolio`, as a current Strategy and returns the schema it was on. The
+// layout is identified by which candidate bump offset derives `address`, so
+// a 208-byte account is never misread under the wrong layout. Layouts before
+// schema 3 had no price feed binding; `price_feeds` becomes it. Layouts
+// before schema 4 didn't track the vault, so the `vault_lamports` it holds
+// are taken as already accounted rather than compounded as rewards.
+pub fn upgrade_strategy_data(
+    data: &mut [u8],
+    portfolio: &Pubkey,
+    address: &Pubkey,
+    price_feeds: Option<PriceFeedBinding>,
+    vault_lamports: u64,
+) -> Result<u8> {
+    require!(
+        data.len() == Strategy::MAX_SIZE && data[..8] == *Strategy::DISCRIMINATOR,
+        ErrorCode::StrategyNotFound
+    );
+    let strategy_id: [u8; 32] = data[8..40].try_into().unwrap();
+    let bump_offset = creation_time_end(data)? + 9;
+    
+    let missing = MISSING_BEFORE_BUMP
+        .iter()
+        .copied()
+        .find(|&missing| {
+            Pubkey::create_program_address(
+                &[b"strategy", portfolio.as_ref(), &strategy_id, &[data[bump_offset - missing]]],
+                &crate::ID,
+            ).is_ok_and(|expected| expected == *address)
+        })
+        .ok_or(ErrorCode::StrategyNotFound)?;
+    
+    // Shift everything from bump on into place; the bytes pushed off the end
+    // are the old layout's zeroed reserve
+    let bump_at = bump_offset - missing;
+    data.copy_within(bump_at..data.len() - missing, bump_offset);
+    data[bump_at..bump_offset].fill(0);
+    
+    let mut strategy = Strategy::try_deserialize(&mut &data[..])?;
+    let old_version = strategy.schema_version;
+    require!(old_version < CURRENT_STRATEGY_SCHEMA, ErrorCode::StrategyAlreadyMigrated);
+    strategy.fill_migrated_defaults();
+    strategy.vault_accounted = vault_lamports;
+    if let Some(price_feeds) = price_feeds {
+        strategy.bind_price_feeds(price_feeds)?;
+    }
+    strategy.schema_version = CURRENT_STRATEGY_SCHEMA;
+    
+    let mut writer: &mut [u8] = data;
+    strategy.try_serialize(&mut writer)?;
+    Ok(old_version)
+}
+
+// Grow a strategy registered under an older schema to Strategy::MAX_SIZE,
+// realigning the layouts that predate skip_count, filling fields the old
+// layout lacked and stamping CURRENT_STRATEGY_SCHEMA
+pub fn migrate_strategy(ctx: Context<MigrateStrategy>, price_feeds: Option<PriceFeedBinding>) -> Result<()> {
+    let strategy_info = ctx.accounts.strategy.to_account_info();
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    // Upgrade a copy first so nothing is reallocated for an account that
+    // isn't a legacy strategy of this portfolio
+    let old_len = strategy_info.data_len();
+    require!(
+        LEGACY_STRATEGY_SIZES.contains(&old_len) || old_len == Strategy::MAX_SIZE,
+        ErrorCode::StrategyNotFound
+    );
+    let mut image = strategy_info.try_borrow_data()?.to_vec();
+    image.resize(Strategy::MAX_SIZE, 0);
+    let old_version = upgrade_strategy_data(
+        &mut image,
+        &portfolio_key,
+        &strategy_info.key(),
+        price_feeds,
+        ctx.accounts.vault.lamports(),
+    )?;
+    
+    grow_account(
+        &strategy_info,
+        &ctx.accounts.manager.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        Strategy::MAX_SIZE,
+    )?;
+    strategy_info.try_borrow_mut_data()?.copy_from_slice(&image);
+    
+    let strategy_id = Pubkey::new_from_array(image[8..40].try_into().unwrap());
+    emit!(StrategyMigrated {
+        portfolio: portfolio_key,
+        strategy_id,
+        old_version,
+        new_version: CURRENT_STRATEGY_SCHEMA,
+        new_size: Strategy::MAX_SIZE as u32,
+        timestamp: current_time,
+    });
+    
+    msg!("Strategy migrated: {} schema {} -> {}, {} -> {} bytes",
+         strategy_id, old_version, CURRENT_STRATEGY_SCHEMA, old_len, Strategy::MAX_SIZE);
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::test_strategy;
+    
+    fn strategy_address(portfolio: &Pubkey, strategy_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"strategy", portfolio.as_ref(), strategy_id.as_ref()], &crate::ID)
+    }
+    
+    // Byte image of a Strategy as the original program allocated it: 8 + 200
+    // bytes with bump straight after creation_time
+    fn baseline_strategy_image(strategy_id: &Pubkey, protocol_type: &ProtocolType, bump: u8) -> Vec<u8> {
+        let mut data = Strategy::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(strategy_id.as_ref());
+        protocol_type.serialize(&mut data).unwrap();
+        data.extend_from_slice(&2_000_000_000u64.to_le_bytes()); // current_balance
+        data.extend_from_slice(&800u64.to_le_bytes());           // yield_rate
+        data.extend_from_slice(&3_000u32.to_le_bytes());         // volatility_score
+        data.extend_from_slice(&6_500u64.to_le_bytes());         // performance_score
+        data.push(75);                                           // percentile_rank
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // last_updated
+        data.push(0);                                            // status: Active
+        data.extend_from_slice(&2_500_000_000u64.to_le_bytes()); // total_deposits
+        data.extend_from_slice(&500_000_000u64.to_le_bytes());   // total_withdrawals
+        data.extend_from_slice(&1_690_000_000i64.to_le_bytes()); // creation_time
+        data.push(bump);
+        data.resize(LEGACY_STRATEGY_SIZES[0], 0);                // reserved: [u8; 23]
+        data
+    }
+    
+    #[test]
+    fn test_baseline_strategy_migrates() {
+        let portfolio = Pubkey::new_unique();
+        let strategy_id = Pubkey::new_unique();
+        let (address, bump) = strategy_address(&portfolio, &strategy_id);
+        let protocol_type = test_strategy(0, 0).protocol_type;
+        
+        let price_feeds = PriceFeedBinding { source: OracleSource::Pyth, feed_a: [9u8; 32], feed_b: [0u8; 32] };
+        
+        let mut data = baseline_strategy_image(&strategy_id, &protocol_type, bump);
+        data.resize(Strategy::MAX_SIZE, 0);
+        assert_eq!(upgrade_strategy_data(&mut data, &portfolio, &address, Some(price_feeds), 2_000_000_000).unwrap(), 0);
+        
+        let strategy = Strategy::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(strategy.strategy_id, strategy_id);
+        assert_eq!(strategy.current_balance, 2_000_000_000);
+        assert_eq!(strategy.yield_rate, 800);
+        assert_eq!(strategy.performance_score, 6_500);
+        assert_eq!(strategy.percentile_rank, 75);
+        assert_eq!(strategy.total_deposits, 2_500_000_000);
+        assert_eq!(strategy.total_withdrawals, 500_000_000);
+        assert_eq!(strategy.creation_time, 1_690_000_000);
+        assert_eq!(strategy.bump, bump);
+        assert_eq!(strategy.skip_count, 0);
+        
+        // Fields the baseline lacked start from the balance it holds
+        assert_eq!(strategy.high_water_mark, 2_000_000_000);
+        assert_eq!(strategy.peak_balance, 2_000_000_000);
+        assert_eq!(strategy.compound_frequency_seconds, protocol_type.default_compound_frequency());
+        assert_eq!(strategy.price_feeds, price_feeds);
+        assert_eq!(strategy.vault_accounted, 2_000_000_000);
+        assert!(strategy.schema_supported());
+        
+        // A migrated strategy can't be migrated again
+        assert_eq!(
+            upgrade_strategy_data(&mut data, &portfolio, &address, None, 0).unwrap_err(),
+            ErrorCode::StrategyAlreadyMigrated.into()
+        );
+    }
+    
+    #[test]
+    fn test_versioned_strategy_keeps_its_layout() {
+        let portfolio = Pubkey::new_unique();
+        let mut strategy = test_strategy(1_000_000_000, 1_200_000_000);
+        let (address, bump) = strategy_address(&portfolio, &strategy.strategy_id);
+        strategy.bump = bump;
+        strategy.skip_count = 3;
+        strategy.schema_version = 1;
+        strategy.peak_balance = 0;
+        
+        let mut data = Vec::new();
+        strategy.try_serialize(&mut data).unwrap();
+        data.resize(Strategy::MAX_SIZE, 0);
+        assert_eq!(upgrade_strategy_data(&mut data, &portfolio, &address, None, 0).unwrap(), 1);
+        
+        let migrated = Strategy::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.high_water_mark, 1_200_000_000);
+        assert_eq!(migrated.skip_count, 3);
+        assert_eq!(migrated.bump, bump);
+        assert_eq!(migrated.peak_balance, 1_000_000_000);
+        assert_eq!(migrated.schema_version, CURRENT_STRATEGY_SCHEMA);
+        
+        // Another portfolio's strategy is not this one's to migrate
+        assert_eq!(
+            upgrade_strategy_data(&mut data, &Pubkey::new_unique(), &address, None, 0).unwrap_err(),
+            ErrorCode::StrategyNotFound.into()
+        );
+    }
+    
+    #[test]
+    fn test_v1_strategy_gains_drawdown_fields() {
+        // The largest protocol type filled a v1 account, so its image only
+        // deserializes once grown by the drawdown fields
+        let portfolio = Pubkey::new_unique();
+        let mut strategy = test_strategy(3_000_000_000, 3_000_000_000);
+        strategy.protocol_type = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 30,
+        };
+        let (address, bump) = strategy_address(&portfolio, &strategy.strategy_id);
+        strategy.bump = bump;
+        strategy.schema_version = 1;
+        strategy.peak_balance = 0;
+        strategy.max_drawdown_bps = 0;
+        
+        let mut data = Vec::new();
+        strategy.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), Strategy::MAX_SIZE);
+        data.truncate(LEGACY_STRATEGY_SIZES[3]);
+        assert!(Strategy::try_deserialize(&mut &data[..]).is_err());
+        
+        data.resize(Strategy::MAX_SIZE, 0);
+        assert_eq!(upgrade_strategy_data(&mut data, &portfolio, &address, None, 0).unwrap(), 1);
+        let migrated = Strategy::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.peak_balance, 3_000_000_000);
+        assert_eq!(migrated.max_drawdown_bps, 0);
+        assert!(migrated.schema_supported());
+    }
+}
@@ -4,6 +4,54 @@ pub mod update_performance;
 pub mod execute_ranking;
 pub mod extract_capital;
 pub mod redistribute_capital;
+pub mod update_portfolio_config;
+pub mod check_il_thresholds;
+pub mod fund_keeper_budget;
+pub mod draw_keeper_budget;
+pub mod verify_strategy_count;
+pub mod submit_scores;
+pub mod finalize_ranking;
+pub mod get_staleness_report;
+pub mod set_compound_frequency;
+pub mod compound_rewards;
+pub mod get_portfolio_risk_score;
+pub mod set_extraction_paused;
+pub mod create_capital_position;
+pub mod manager_handoff;
+pub mod calculate_rebalance_breakeven;
+pub mod set_withdrawal_limit;
+pub mod collect_performance_fees;
+pub mod emergency_extract;
+pub mod reentry_cooldown;
+pub mod get_strategy_pnl;
+pub mod set_emergency_pause;
+pub mod update_rebalance_config;
+pub mod deposit_capital;
+pub mod check_risk_config_feasibility;
+pub mod withdraw_capital;
+pub mod set_strategy_status;
+pub mod set_risk_config;
+pub mod simulate_rebalance;
+pub mod redistribution_session;
+pub mod get_portfolio_summary;
+pub mod refresh_position_il;
+pub mod claim_unstake;
+pub mod preview_allocation;
+pub mod migrate_portfolio;
+pub mod can_withdraw;
+pub mod accrue_yield;
+pub mod wind_down_strategy;
+pub mod get_strategy_rankings;
+pub mod record_tvl_snapshot;
+pub mod list_strategies_by_protocol;
+pub mod reconcile_strategy;
+pub mod set_denomination_config;
+pub mod migrate_strategy;
+pub mod migrate_risk_config;
+pub mod keeper_registration;
+pub mod claim_performance_fees;
+pub mod reset_ranking_buffer;
+pub mod close_strategy;
 
 pub use initialize::*;
 pub use register_strategy::*;
@@ -11,3 +59,51 @@ pub use update_performance::*;
 pub use execute_ranking::*;
 pub use extract_capital::*;
 pub use redistribute_capital::*;
+pub use update_portfolio_config::*;
+pub use check_il_thresholds::*;
+pub use fund_keeper_budget::*;
+pub use draw_keeper_budget::*;
+pub use verify_strategy_count::*;
+pub use submit_scores::*;
+pub use finalize_ranking::*;
+pub use get_staleness_report::*;
+pub use set_compound_frequency::*;
+pub use compound_rewards::*;
+pub use get_portfolio_risk_score::*;
+pub use set_extraction_paused::*;
+pub use create_capital_position::*;
+pub use manager_handoff::*;
+pub use calculate_rebalance_breakeven::*;
+pub use set_withdrawal_limit::*;
+pub use collect_performance_fees::*;
+pub use emergency_extract::*;
+pub use reentry_cooldown::*;
+pub use get_strategy_pnl::*;
+pub use set_emergency_pause::*;
+pub use update_rebalance_config::*;
+pub use deposit_capital::*;
+pub use check_risk_config_feasibility::*;
+pub use withdraw_capital::*;
+pub use set_strategy_status::*;
+pub use set_risk_config::*;
+pub use simulate_rebalance::*;
+pub use redistribution_session::*;
+pub use get_portfolio_summary::*;
+pub use refresh_position_il::*;
+pub use claim_unstake::*;
+pub use preview_allocation::*;
+pub use migrate_portfolio::*;
+pub use can_withdraw::*;
+pub use accrue_yield::*;
+pub use wind_down_strategy::*;
+pub use get_strategy_rankings::*;
+pub use record_tvl_snapshot::*;
+pub use list_strategies_by_protocol::*;
+pub use reconcile_strategy::*;
+pub use set_denomination_config::*;
+pub use migrate_strategy::*;
+pub use migrate_risk_config::*;
+pub use keeper_registration::*;
+pub use claim_performance_fees::*;
+pub use reset_ranking_buffer::*;
+pub use close_strategy::*;
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::redistribute_capital::{calculate_optimal_allocation, OptimalAllocation, StrategyPerformanceData};
+
+// Return data is capped at 1024 bytes; each allocation serializes to 41 bytes,
+// so 22 strategies plus the two fee allocations fit
+pub const MAX_PREVIEW_STRATEGIES: usize = 22;
+
+#[derive(Accounts)]
+pub struct PreviewAllocation<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Account<'info, RiskConfig>,
+}
+
+// READ-ONLY: how available_capital would be deployed across the active
+// strategies passed via remaining_accounts under the stored risk limits
+pub fn preview_allocation<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PreviewAllocation<'info>>,
+    available_capital: u64,
+) -> Result<Vec<CapitalAllocation>> {
+    let portfolio = &ctx.accounts.portfolio;
+    let portfolio_key = portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_PREVIEW_STRATEGIES, ErrorCode::TooManyStrategies);
+    
+    let mut strategies: Vec<StrategyPerformanceData> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!strategies.iter().any(|s| s.strategy_id == strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        if strategy.status == StrategyStatus::Active {
+            strategies.push(StrategyPerformanceData::from_strategy(&strategy));
+        }
+    }
+    
+    // Priced the way the next rebalance would be, including any fee waiver
+    let risk_limits = if portfolio.free_rebalance_available(current_time) {
+        ctx.accounts.risk_config.limits.with_fees_waived()
+    } else {
+        ctx.accounts.risk_config.limits.clone()
+    };
+    
+    let OptimalAllocation { allocations, unallocated_dust } =
+        calculate_optimal_allocation(available_capital, &strategies, &risk_limits)?;
+    
+    msg!("Previewed allocation of {} lamports: {} allocations across {} strategies, {} unallocated",
+         available_capital, allocations.len(), strategies.len(), unallocated_dust);
+    
+    Ok(allocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_largest_preview_fits_return_data() {
+        let allocation = CapitalAllocation {
+            strategy_id: Pubkey::new_unique(),
+            amount: u64::MAX,
+            allocation_type: AllocationType::TopPerformer,
+        };
+        let allocations = vec![allocation; MAX_PREVIEW_STRATEGIES + 2];
+        
+        assert!(allocations.try_to_vec().unwrap().len() <= 1024);
+    }
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::accrue_yield::accrued_interest;
+use super::get_strategy_pnl::calculate_lifetime_pnl;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct ReconcileStrategy<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reconciliation {
+    pub actual_pnl: i64,
+    pub expected_pnl: i64,
+    pub tolerance: u64,
+}
+
+impl Reconciliation {
+    pub fn diverges(&self) -> bool {
+        self.actual_pnl.abs_diff(self.expected_pnl) > self.tolerance
+    }
+}
+
+// ACCOUNTING RECONCILIATION
+// Compares the strategy's lifetime PnL (calculate_lifetime_pnl) with the yield its stored rate
+// should have earned on net principal since creation, and emits
+// StrategyAccountingMismatch when they differ by more than the portfolio's
+// reconcile_tolerance_bps of net principal. Anyone may call it.
+pub fn reconcile_strategy(ctx: Context<ReconcileStrategy>, strategy_id: Pubkey) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    let reconciliation = reconcile(&ctx.accounts.strategy, portfolio.reconcile_tolerance_bps, current_time)?;
+    
+    if reconciliation.diverges() {
+        emit!(StrategyAccountingMismatch {
+            portfolio: portfolio.key(),
+            strategy_id,
+            actual_pnl: reconciliation.actual_pnl,
+            expected_pnl: reconciliation.expected_pnl,
+            tolerance: reconciliation.tolerance,
+            timestamp: current_time,
+        });
+        msg!("Accounting mismatch: strategy={}, pnl={}, expected={}, tolerance={}",
+             strategy_id, reconciliation.actual_pnl, reconciliation.expected_pnl, reconciliation.tolerance);
+    } else {
+        msg!("Strategy {} reconciled: pnl={}, expected={}",
+             strategy_id, reconciliation.actual_pnl, reconciliation.expected_pnl);
+    }
+    
+    Ok(())
+}
+
+// Lending interest is booked as deposits by accrue_yield and nets out of the
+// PnL, so a lending strategy is expected to show none. Growth too large to
+// represent saturates rather than failing the check.
+pub fn reconcile(strategy: &Strategy, tolerance_bps: u16, current_time: i64) -> Result<Reconciliation> {
+    let principal = strategy.total_deposits.saturating_sub(strategy.total_withdrawals);
+    
+    let expected_pnl = match strategy.protocol_type {
+        ProtocolType::StableLending { .. } => 0,
+        _ => accrued_interest(
+            principal,
+            strategy.yield_rate,
+            current_time.saturating_sub(strategy.creation_time),
+            strategy.compound_frequency_seconds,
+        )
+        .unwrap_or(u64::MAX)
+        .min(i64::MAX as u64) as i64,
+    };
+    
+    let tolerance = u64::try_from(principal as u128 * tolerance_bps as u128 / 10000)
+        .map_err(|_| ErrorCode::BalanceOverflow)?;
+    
+    Ok(Reconciliation {
+        actual_pnl: calculate_lifetime_pnl(
+            strategy.current_balance,
+            strategy.total_withdrawals,
+            0,
+            strategy.total_deposits,
+        )?,
+        expected_pnl,
+        tolerance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::test_strategy;
+    
+    #[test]
+    fn test_reconcile_flags_unexplained_balance() {
+        // Two deposits, one withdrawal, 10% APY on a daily-compounding staking strategy
+        let mut strategy = test_strategy(0, 0);
+        strategy.protocol_type = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        strategy.total_deposits = 3_000_000_000;
+        strategy.total_withdrawals = 1_000_000_000;
+        strategy.yield_rate = 1000;
+        strategy.compound_frequency_seconds = 86_400;
+        strategy.creation_time = 0;
+        let year = 31_536_000;
+        
+        // 2 SOL of principal plus a year of 10% yield reconciles
+        strategy.current_balance = 2_200_000_000;
+        let clean = reconcile(&strategy, 500, year).unwrap();
+        assert_eq!(clean.actual_pnl, 200_000_000);
+        assert!(clean.expected_pnl.abs_diff(200_000_000) <= 1_000);
+        assert_eq!(clean.tolerance, 100_000_000);
+        assert!(!clean.diverges());
+        
+        // An unbooked 1 SOL transfer into the strategy does not
+        strategy.current_balance = 3_200_000_000;
+        assert!(reconcile(&strategy, 500, year).unwrap().diverges());
+        
+        // Nor does a balance that vanished
+        strategy.current_balance = 1_000_000_000;
+        let drained = reconcile(&strategy, 500, year).unwrap();
+        assert_eq!(drained.actual_pnl, -1_000_000_000);
+        assert!(drained.diverges());
+        
+        // Lending yield is booked as deposits, so any PnL there is unexplained
+        strategy.protocol_type = test_strategy(0, 0).protocol_type;
+        strategy.current_balance = 2_200_000_000;
+        let lending = reconcile(&strategy, 500, year).unwrap();
+        assert_eq!(lending.expected_pnl, 0);
+        assert!(lending.diverges());
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct RecordTvlSnapshot<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = TvlHistory::MAX_SIZE,
+        seeds = [b"tvl_history", portfolio.key().as_ref()],
+        bump
+    )]
+    pub tvl_history: Account<'info, TvlHistory>,
+    
+    // Anyone may crank snapshots; the interval bounds how often
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// TVL SNAPSHOT
+// Sums current_balance over every Strategy account of the portfolio, passed via
+// remaining_accounts, and appends it to the portfolio's TvlHistory ring.
+// Frontends read the series by deserializing the TvlHistory account.
+pub fn record_tvl_snapshot<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RecordTvlSnapshot<'info>>,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let portfolio_key = portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    // A partial strategy set would chart a false drop
+    require!(
+        ctx.remaining_accounts.len() == portfolio.total_strategies as usize,
+        ErrorCode::StrategyCountMismatch
+    );
+    
+    let mut strategy_ids: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut total_tvl = 0u64;
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!strategy_ids.contains(&strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        strategy_ids.push(strategy.strategy_id);
+        total_tvl = total_tvl
+            .checked_add(strategy.current_balance)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+    }
+    
+    let tvl_history = &mut ctx.accounts.tvl_history;
+    tvl_history.portfolio = portfolio_key;
+    tvl_history.bump = ctx.bumps.tvl_history;
+    tvl_history.record(total_tvl, current_time, portfolio.tvl_snapshot_interval)?;
+    
+    emit!(TvlSnapshotRecorded {
+        portfolio: portfolio_key,
+        total_tvl,
+        strategy_count: strategy_ids.len() as u32,
+        timestamp: current_time,
+    });
+    
+    msg!("TVL snapshot #{}: {} lamports across {} strategies",
+         tvl_history.snapshot_count, total_tvl, strategy_ids.len());
+    
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::events::*;
 use crate::error::ErrorCode;
+use super::extract_capital::{estimate_extraction_fees, is_extraction_economical};
+use super::can_withdraw::reason_code;
+use super::redistribution_session::redistribution_session_open;
 use std::collections::HashSet;
 
 #[derive(Accounts)]
@@ -8,40 +12,290 @@ use std::collections::HashSet;
 pub struct RedistributeCapital<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
         bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
         has_one = manager @ ErrorCode::UnauthorizedManager
     )]
     pub portfolio: Account<'info, Portfolio>,
     
+    // Required only when allocations include fees
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+    
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = AllocationLog::MAX_SIZE,
+        seeds = [b"allocation_log", portfolio.key().as_ref()],
+        bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    /// CHECK: may not exist yet; only read to refuse while a chunked session is open
+    #[account(
+        seeds = [b"redistribution", portfolio.key().as_ref()],
+        bump
+    )]
+    pub session: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
-pub fn redistribute_capital(
-    ctx: Context<RedistributeCapital>,
+// remaining_accounts: the destination Strategy account of every TopPerformer and
+// RiskDiversification allocation, re-validated and then credited with its amount,
+// plus any other strategy this cycle's plan left out. Strategies passed without
+// a credited allocation count as skipped (skip_count grows); credited ones reset it.
+// Fee allocations must pay the treasuries stored in the portfolio's RiskConfig
+// and are only tallied on the portfolio. Everything credited, fees included,
+// is drawn from portfolio.undeployed_capital, so only capital extract_capital
+// actually released can be handed out; skipped allocations stay undeployed.
+//
+// With dry_run set, the same validation and account matching runs against a
+// copy of the portfolio and nothing is written back (beyond creating the
+// allocation log on first use); the outcome is returned as return data
+// instead of raised.
+pub fn redistribute_capital<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RedistributeCapital<'info>>,
     allocations: Vec<CapitalAllocation>,
-) -> Result<()> {
+    dry_run: bool,
+) -> Result<RedistributionOutcome> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let risk_config = ctx.accounts.risk_config.as_ref();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(
+        !redistribution_session_open(&ctx.accounts.session)?,
+        ErrorCode::RedistributionInProgress
+    );
+    
+    if dry_run {
+        let mut preview = (*ctx.accounts.portfolio).clone();
+        let outcome = prepare_redistribution(&mut preview, &allocations, current_time)
+            .and_then(|total_allocated| {
+                let applied = apply_allocations(
+                    &allocations,
+                    ctx.remaining_accounts,
+                    &preview,
+                    &portfolio_key,
+                    risk_config,
+                    current_time,
+                    false,
+                )?;
+                Ok(RedistributionOutcome::succeeded(total_allocated, &applied))
+            })
+            .unwrap_or_else(|error| RedistributionOutcome::failed(&error));
+        
+        msg!("Dry-run redistribution: would_succeed={}, reason={}, total={}, skipped={}",
+             outcome.would_succeed, outcome.reason_code, outcome.total_allocated, outcome.skipped_amount);
+        return Ok(outcome);
+    }
+    
     let portfolio = &mut ctx.accounts.portfolio;
+    let total_allocated = prepare_redistribution(portfolio, &allocations, current_time)?;
+    
+    let applied = apply_allocations(
+        &allocations,
+        ctx.remaining_accounts,
+        portfolio,
+        &portfolio_key,
+        risk_config,
+        current_time,
+        true,
+    )?;
+    let outcome = RedistributionOutcome::succeeded(total_allocated, &applied);
+    let RedistributionOutcome { total_allocated, fees_allocated, .. } = outcome;
     
+    portfolio.deploy_capital(total_allocated)?;
+    portfolio.total_fees_allocated = portfolio.total_fees_allocated
+        .checked_add(fees_allocated)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    portfolio.total_capital_moved = portfolio.total_capital_moved
+        .checked_add(total_allocated)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    let allocation_log = &mut ctx.accounts.allocation_log;
+    allocation_log.portfolio = portfolio_key;
+    allocation_log.bump = ctx.bumps.allocation_log;
+    let strategy_count = allocations.iter().filter(|a| a.allocation_type.targets_strategy()).count();
+    let sequence = allocation_log.append(total_allocated, strategy_count as u16, current_time);
+    msg!("Allocation log entry {}", sequence);
+    
+    emit!(CapitalRedistributed {
+        portfolio: portfolio_key,
+        total_allocated,
+        allocation_count: allocations.len() as u32,
+        fees_allocated,
+        timestamp: current_time,
+    });
+    
+    Ok(outcome)
+}
+
+// Portfolio-level checks shared by real and dry-run redistribution, consuming
+// a fee-free rebalance if one is available; returns the validated total
+fn prepare_redistribution(
+    portfolio: &mut Portfolio,
+    allocations: &[CapitalAllocation],
+    current_time: i64,
+) -> Result<u64> {
     // COMPREHENSIVE VALIDATION
     require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!portfolio.extraction_paused, ErrorCode::ExtractionPaused);
+    require!(portfolio.rebalance_permitted(), ErrorCode::DispersionBelowThreshold);
     require!(!allocations.is_empty(), ErrorCode::InsufficientStrategies);
-    require!(allocations.len() <= 20, ErrorCode::TooManyStrategies);
+    require!(allocations.len() <= MAX_ALLOCATIONS_PER_TX, ErrorCode::TooManyStrategies);
     
     // VALIDATE ALLOCATION TOTALS
-    let total_allocated = validate_allocations(&allocations)?;
+    let total_allocated = validate_allocations(allocations)?;
+    require!(total_allocated <= portfolio.undeployed_capital, ErrorCode::ExceedsUndeployedCapital);
+    
+    // FEE-FREE ALLOWANCE: waived rebalances may not carry fee allocations
+    if portfolio.consume_free_rebalance(current_time) {
+        require!(
+            allocations.iter().all(|a| a.allocation_type.targets_strategy()),
+            ErrorCode::FeesWaivedThisRebalance
+        );
+        msg!("Fee-free rebalance {} of {} this period",
+             portfolio.free_rebalances_used, portfolio.free_rebalances_per_period);
+    }
     
     msg!("Redistributing {} lamports across {} strategies", total_allocated, allocations.len());
     
-    // NOTE: In full implementation, this would update strategy accounts
-    // For assessment purposes, we'll implement the core redistribution logic
+    Ok(total_allocated)
+}
+
+// Result of a redistribute_capital call; a dry run reports failures here
+// instead of raising them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct RedistributionOutcome {
+    pub would_succeed: bool,
+    pub reason_code: u32,                   // ErrorCode number the real call would fail with (0 = success)
+    pub total_allocated: u64,               // Net of skipped allocations
+    pub fees_allocated: u64,
+    pub skipped_amount: u64,
+}
+
+impl RedistributionOutcome {
+    pub fn succeeded(total_allocated: u64, applied: &AppliedAllocations) -> Self {
+        Self {
+            would_succeed: true,
+            reason_code: 0,
+            total_allocated: total_allocated.saturating_sub(applied.skipped_amount),
+            fees_allocated: applied.fees_allocated,
+            skipped_amount: applied.skipped_amount,
+        }
+    }
+    
+    pub fn failed(error: &Error) -> Self {
+        Self {
+            would_succeed: false,
+            reason_code: reason_code(error),
+            total_allocated: 0,
+            fees_allocated: 0,
+            skipped_amount: 0,
+        }
+    }
+}
+
+pub const MAX_ALLOCATIONS_PER_TX: usize = 20;
+
+// Fee and skipped totals of one apply_allocations pass
+#[derive(Debug, Default)]
+pub struct AppliedAllocations {
+    pub fees_allocated: u64,
+    pub skipped_amount: u64,                // Left undeployed: destination in rebalance cooldown
+}
+
+// Credits each strategy allocation to its destination in destination_infos
+// (re-validated first) and returns the fee and skipped totals. Fee allocations
+// must pay the treasury stored in risk_config; destinations in their rebalance
+// cooldown are skipped and logged rather than failing the transaction. Every
+// strategy in destination_infos records whether it was credited this pass,
+// feeding the max_skips starvation guard, so each may appear only once.
+// Destinations are only written back when persist is set.
+pub fn apply_allocations<'info>(
+    allocations: &[CapitalAllocation],
+    destination_infos: &'info [AccountInfo<'info>],
+    portfolio: &Portfolio,
+    portfolio_key: &Pubkey,
+    risk_config: Option<&Account<'info, RiskConfig>>,
+    current_time: i64,
+    persist: bool,
+) -> Result<AppliedAllocations> {
+    // RE-VALIDATE EVERY DESTINATION STRATEGY
+    let mut destinations: Vec<Account<'info, Strategy>> = Vec::new();
+    for account_info in destination_infos.iter() {
+        require!(!destinations.iter().any(|d| d.key() == account_info.key()), ErrorCode::DuplicateStrategy);
+        destinations.push(Strategy::load_checked(account_info, portfolio_key)?);
+    }
     
-    portfolio.total_capital_moved = portfolio.total_capital_moved
-        .checked_add(total_allocated)
-        .ok_or(ErrorCode::BalanceOverflow)?;
+    // CREDIT DESTINATIONS AND TALLY FEES
+    let mut applied = AppliedAllocations::default();
+    let mut credited = vec![false; destinations.len()];
+    for allocation in allocations.iter() {
+        if !allocation.allocation_type.targets_strategy() {
+            let risk_config = risk_config.ok_or(ErrorCode::TreasuryNotConfigured)?;
+            require!(
+                risk_config.limits.treasury_for(&allocation.allocation_type) == Some(allocation.strategy_id),
+                ErrorCode::InvalidFeeDestination
+            );
+            applied.fees_allocated = applied.fees_allocated
+                .checked_add(allocation.amount)
+                .ok_or(ErrorCode::BalanceOverflow)?;
+            continue;
+        }
+        
+        let index = destinations
+            .iter()
+            .position(|s| s.strategy_id == allocation.strategy_id)
+            .ok_or(ErrorCode::StrategyNotFound)?;
+        let destination = &mut destinations[index];
+        
+        if destination.in_rebalance_cooldown(current_time, portfolio.strategy_cooldown) {
+            msg!("Skipping allocation of {} to strategy {} in rebalance cooldown (last rebalanced {})",
+                 allocation.amount, allocation.strategy_id, destination.last_rebalance);
+            applied.skipped_amount = applied.skipped_amount
+                .checked_add(allocation.amount)
+                .ok_or(ErrorCode::BalanceOverflow)?;
+            continue;
+        }
+        
+        destination.validate_as_destination()?;
+        destination.credit_allocation(allocation.amount)?;
+        destination.last_rebalance = current_time;
+        
+        let settlement_delay = portfolio.settlement_delay_for(&destination.protocol_type);
+        destination.add_pending_capital(allocation.amount, current_time, settlement_delay)?;
+        credited[index] = true;
+    }
     
-    Ok(())
+    // SKIP ACCOUNTING FOR THE STARVATION GUARD
+    for (destination, credited) in destinations.iter_mut().zip(credited) {
+        destination.record_allocation_outcome(credited);
+    }
+    
+    if persist {
+        for destination in destinations.iter() {
+            destination.exit(&crate::ID)?;
+        }
+    }
+    
+    Ok(applied)
+}
+
+// Allocations for available_capital plus what was left over. Fees, allocations,
+// transfer gas and unallocated_dust always add up to available_capital.
+#[derive(Debug)]
+pub struct OptimalAllocation {
+    pub allocations: Vec<CapitalAllocation>,
+    pub unallocated_dust: u64,              // Below the sweep threshold, or no top performer had room
 }
 
 // OPTIMAL ALLOCATION ALGORITHM
@@ -49,16 +303,22 @@ pub fn calculate_optimal_allocation(
     available_capital: u64,
     top_strategies: &[StrategyPerformanceData],
     risk_limits: &RiskLimits,
-) -> Result<Vec<CapitalAllocation>> {
+) -> Result<OptimalAllocation> {
     require!(available_capital > 0, ErrorCode::InsufficientBalance);
     require!(!top_strategies.is_empty(), ErrorCode::InsufficientStrategies);
     
     let mut allocations = Vec::new();
     let mut remaining_capital = available_capital;
     
+    // FEES MUST HAVE A REAL DESTINATION (never burn to the default pubkey)
+    risk_limits.validate_treasuries()?;
+    
+    // EVERY STRATEGY'S MINIMUM MUST BE SATISFIABLE AT ONCE
+    risk_limits.validate_minimum_feasible(top_strategies.len())?;
+    
     // CALCULATE PLATFORM AND MANAGER FEES FIRST
-    let platform_fee = (available_capital * risk_limits.platform_fee_bps) / 10000;
-    let manager_fee = (available_capital * risk_limits.manager_fee_bps) / 10000;
+    let platform_fee = bps_of(available_capital, risk_limits.platform_fee_bps)?;
+    let manager_fee = bps_of(available_capital, risk_limits.manager_fee_bps)?;
     
     if platform_fee > 0 {
         allocations.push(CapitalAllocation {
@@ -77,6 +337,7 @@ pub fn calculate_optimal_allocation(
         });
         remaining_capital = remaining_capital.saturating_sub(manager_fee);
     }
+    let distributable_capital = remaining_capital;
     
     // PERFORMANCE-WEIGHTED ALLOCATION
     let total_performance_score: u128 = top_strategies
@@ -86,6 +347,11 @@ pub fn calculate_optimal_allocation(
     
     require!(total_performance_score > 0, ErrorCode::InvalidPerformanceScore);
     
+    // PROTOCOL-CATEGORY CEILINGS, in lamports of the capital being deployed
+    let category_caps = risk_limits.category_caps_bps()
+        .map(|cap_bps| (available_capital as u128 * cap_bps as u128 / 10000) as u64);
+    let mut category_allocated = [0u64; ProtocolType::CATEGORY_COUNT];
+    
     // CALCULATE ALLOCATIONS WITH DIVERSIFICATION CONSTRAINTS
     for (index, strategy) in top_strategies.iter().enumerate() {
         if remaining_capital == 0 {
@@ -97,10 +363,17 @@ pub fn calculate_optimal_allocation(
             / total_performance_score;
         
         // APPLY DIVERSIFICATION LIMITS
-        let max_single_allocation = (available_capital * risk_limits.max_single_strategy_bps) / 10000;
-        let min_single_allocation = (available_capital * risk_limits.min_single_strategy_bps) / 10000;
+        let max_single_allocation = bps_of(available_capital, risk_limits.max_single_strategy_bps)?;
+        let min_single_allocation = bps_of(available_capital, risk_limits.min_single_strategy_bps)?;
         
         let mut allocation_amount = performance_allocation as u64;
+        let protocol_minimum = risk_limits.minimum_balance(&strategy.protocol_type);
+        
+        // STARVATION PROTECTION: guarantee a minimum slice after repeated skips
+        if strategy.is_starved(risk_limits.max_skips) {
+            let guaranteed_slice = min_single_allocation.max(protocol_minimum);
+            allocation_amount = allocation_amount.max(guaranteed_slice);
+        }
         
         // ENFORCE MAXIMUM ALLOCATION LIMIT
         if allocation_amount > max_single_allocation {
@@ -113,33 +386,63 @@ pub fn calculate_optimal_allocation(
         }
         
         // PROTOCOL-SPECIFIC MINIMUM REQUIREMENTS
-        match strategy.protocol_type {
-            ProtocolType::StableLending { .. } => {
-                if allocation_amount < 100_000_000 { // 0.1 SOL minimum for lending
-                    continue;
-                }
-            },
-            ProtocolType::YieldFarming { .. } => {
-                if allocation_amount < 500_000_000 { // 0.5 SOL minimum for LP positions
-                    continue;
-                }
-            },
-            ProtocolType::LiquidStaking { .. } => {
-                if allocation_amount < 1_000_000_000 { // 1 SOL minimum for staking
-                    continue;
-                }
-            },
+        if allocation_amount < protocol_minimum {
+            continue;
         }
         
         // RISK-ADJUSTED ALLOCATION MODIFIER
         let risk_adjustment = calculate_risk_adjustment(strategy.volatility_score, risk_limits);
         allocation_amount = (allocation_amount as u128 * risk_adjustment as u128 / 10000u128) as u64;
         
+        // RISK FLOOR: never leave a sub-minimum position behind
+        if allocation_amount < protocol_minimum {
+            if risk_limits.floor_risk_adjusted_to_minimum {
+                allocation_amount = protocol_minimum;
+            } else {
+                msg!("Skipping strategy {}: risk-adjusted allocation {} below protocol minimum {}",
+                     strategy.strategy_id, allocation_amount, protocol_minimum);
+                continue;
+            }
+        }
+        
         // ENSURE WE DON'T OVERALLOCATE
         if allocation_amount > remaining_capital {
             allocation_amount = remaining_capital;
         }
         
+        // CATEGORY CEILING: overflow stays in remaining_capital for other categories
+        let category = strategy.protocol_type.category_index();
+        let category_headroom = category_caps[category].saturating_sub(category_allocated[category]);
+        if allocation_amount > category_headroom {
+            if category_headroom < protocol_minimum {
+                msg!("Skipping strategy {}: {} category ceiling reached",
+                     strategy.strategy_id, strategy.protocol_type.get_protocol_name());
+                continue;
+            }
+            allocation_amount = category_headroom;
+        }
+        
+        if allocation_amount < protocol_minimum {
+            msg!("Skipping strategy {}: remaining capital {} below protocol minimum {}",
+                 strategy.strategy_id, remaining_capital, protocol_minimum);
+            continue;
+        }
+        
+        // GAS-AWARE PRUNING: each allocation pays for its own transfer, and
+        // allocations left too small after gas fall through to the dust sweep
+        let mut transfer_gas = 0u64;
+        if risk_limits.gas_cost_per_transfer > 0 {
+            let net_allocation = allocation_amount.saturating_sub(risk_limits.gas_cost_per_transfer);
+            if net_allocation == 0 || net_allocation < risk_limits.min_net_allocation {
+                msg!("Skipping strategy {}: allocation {} nets {} after {} gas, below {}",
+                     strategy.strategy_id, allocation_amount, net_allocation,
+                     risk_limits.gas_cost_per_transfer, risk_limits.min_net_allocation);
+                continue;
+            }
+            transfer_gas = risk_limits.gas_cost_per_transfer;
+            allocation_amount = net_allocation;
+        }
+        
         if allocation_amount > 0 {
             let allocation_type = if index < 3 {
                 AllocationType::TopPerformer
@@ -153,21 +456,122 @@ pub fn calculate_optimal_allocation(
                 allocation_type,
             });
             
-            remaining_capital = remaining_capital.saturating_sub(allocation_amount);
+            remaining_capital = remaining_capital
+                .saturating_sub(allocation_amount)
+                .saturating_sub(transfer_gas);
+            category_allocated[category] = category_allocated[category]
+                .saturating_add(allocation_amount)
+                .saturating_add(transfer_gas);
         }
     }
     
-    // REDISTRIBUTE ANY REMAINING DUST TO TOP PERFORMER
-    if remaining_capital > 1_000_000 && !allocations.is_empty() { // 0.001 SOL threshold
-        if let Some(top_allocation) = allocations.iter_mut()
-            .find(|a| matches!(a.allocation_type, AllocationType::TopPerformer)) {
+    // STRICT MODE: eligible strategies split all post-fee capital between them
+    if risk_limits.normalize_to_capital {
+        normalize_strategy_allocations(
+            &mut allocations,
+            top_strategies,
+            distributable_capital,
+            risk_limits.gas_cost_per_transfer,
+        )?;
+        let deployed = allocations.iter()
+            .filter(|a| a.allocation_type.targets_strategy())
+            .try_fold(0u64, |sum, a| {
+                sum.checked_add(a.amount)?.checked_add(risk_limits.gas_cost_per_transfer)
+            })
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        return Ok(OptimalAllocation {
+            allocations,
+            unallocated_dust: distributable_capital.saturating_sub(deployed),
+        });
+    }
+    
+    // REDISTRIBUTE ANY REMAINING DUST TO TOP PERFORMERS
+    // Goes to the highest-ranked top performer whose category still has room,
+    // spilling into the next one when a ceiling is hit. Whatever stays behind
+    // is reported as unallocated_dust.
+    if remaining_capital > risk_limits.dust_threshold_lamports && !allocations.is_empty() {
+        for top_allocation in allocations.iter_mut()
+            .filter(|a| matches!(a.allocation_type, AllocationType::TopPerformer)) {
+            let Some(category) = top_strategies.iter()
+                .find(|s| s.strategy_id == top_allocation.strategy_id)
+                .map(|s| s.protocol_type.category_index()) else {
+                continue;
+            };
+            let sweep = remaining_capital
+                .min(category_caps[category].saturating_sub(category_allocated[category]));
             top_allocation.amount = top_allocation.amount
-                .checked_add(remaining_capital)
+                .checked_add(sweep)
                 .ok_or(ErrorCode::BalanceOverflow)?;
+            category_allocated[category] += sweep;
+            remaining_capital -= sweep;
+            if remaining_capital == 0 {
+                break;
+            }
         }
     }
     
-    Ok(allocations)
+    Ok(OptimalAllocation {
+        allocations,
+        unallocated_dust: remaining_capital,
+    })
+}
+
+// `bps` basis points of `amount`, computed in u128 so large capital cannot overflow
+fn bps_of(amount: u64, bps: u64) -> Result<u64> {
+    u64::try_from(amount as u128 * bps as u128 / 10000).map_err(|_| ErrorCode::BalanceOverflow.into())
+}
+
+// Re-weight the strategy allocations that survived the threshold checks by
+// performance score so they (plus their transfer gas) consume exactly
+// `distributable_capital`. Integer rounding leftovers go to the top-ranked
+// eligible strategy. Skipped strategies stay skipped; caps are not re-applied.
+fn normalize_strategy_allocations(
+    allocations: &mut Vec<CapitalAllocation>,
+    top_strategies: &[StrategyPerformanceData],
+    distributable_capital: u64,
+    gas_cost_per_transfer: u64,
+) -> Result<()> {
+    let eligible: Vec<(usize, u128)> = allocations
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.allocation_type.targets_strategy())
+        .map(|(i, a)| {
+            let score = top_strategies
+                .iter()
+                .find(|s| s.strategy_id == a.strategy_id)
+                .map_or(0, |s| s.performance_score as u128);
+            (i, score)
+        })
+        .collect();
+    
+    if eligible.is_empty() {
+        return Ok(());
+    }
+    
+    let total_gas = gas_cost_per_transfer
+        .checked_mul(eligible.len() as u64)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    let pool = distributable_capital.saturating_sub(total_gas) as u128;
+    
+    // Starved strategies can be eligible with a zero score; split evenly then
+    let total_score: u128 = eligible.iter().map(|(_, score)| score).sum();
+    let weight = |score: u128| if total_score == 0 { 1 } else { score };
+    let total_weight = if total_score == 0 { eligible.len() as u128 } else { total_score };
+    
+    let mut assigned = 0u128;
+    for (i, score) in &eligible {
+        let amount = pool * weight(*score) / total_weight;
+        allocations[*i].amount = amount as u64;
+        assigned += amount;
+    }
+    
+    let top_index = eligible[0].0;
+    allocations[top_index].amount = allocations[top_index].amount
+        .checked_add((pool - assigned) as u64)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    allocations.retain(|a| a.amount > 0);
+    Ok(())
 }
 
 // RISK ADJUSTMENT CALCULATION
@@ -203,6 +607,12 @@ pub fn validate_allocations(allocations: &[CapitalAllocation]) -> Result<u64> {
             return Err(ErrorCode::DuplicateStrategy.into());
         }
         
+        // FEES MUST HAVE A REAL DESTINATION
+        require!(
+            allocation.allocation_type.targets_strategy() || allocation.strategy_id != Pubkey::default(),
+            ErrorCode::TreasuryNotConfigured
+        );
+        
         // VALIDATE ALLOCATION AMOUNT
         require!(allocation.amount > 0, ErrorCode::InsufficientBalance);
         require!(allocation.amount < u64::MAX / 1000, ErrorCode::BalanceOverflow);
@@ -224,49 +634,48 @@ pub struct StrategyPerformanceData {
     pub volatility_score: u32,
     pub protocol_type: ProtocolType,
     pub percentile_rank: u8,
+    pub skip_count: u8,
 }
 
-#[derive(Debug, Clone)]
-pub struct RiskLimits {
-    pub max_single_strategy_bps: u64,    // Maximum % of capital to single strategy
-    pub min_single_strategy_bps: u64,    // Minimum % threshold for allocation
-    pub platform_fee_bps: u64,           // Platform fee percentage
-    pub manager_fee_bps: u64,            // Manager fee percentage
-    pub risk_tolerance_bps: u64,         // Overall risk tolerance modifier
-    pub platform_treasury: Pubkey,       // Platform fee destination
-    pub manager_treasury: Pubkey,        // Manager fee destination
-}
-
-impl Default for RiskLimits {
-    fn default() -> Self {
-        RiskLimits {
-            max_single_strategy_bps: 4000,    // 40% max single strategy
-            min_single_strategy_bps: 100,     // 1% minimum allocation
-            platform_fee_bps: 50,             // 0.5% platform fee
-            manager_fee_bps: 150,              // 1.5% manager fee
-            risk_tolerance_bps: 8000,          // 80% risk tolerance (conservative)
-            platform_treasury: Pubkey::default(),
-            manager_treasury: Pubkey::default(),
+impl StrategyPerformanceData {
+    pub fn from_strategy(strategy: &Strategy) -> Self {
+        StrategyPerformanceData {
+            strategy_id: strategy.strategy_id,
+            performance_score: strategy.performance_score,
+            current_balance: strategy.current_balance,
+            volatility_score: strategy.volatility_score,
+            protocol_type: strategy.protocol_type,
+            percentile_rank: strategy.percentile_rank,
+            skip_count: strategy.skip_count,
         }
     }
+    
+    pub fn is_starved(&self, max_skips: u8) -> bool {
+        max_skips > 0 && self.skip_count > max_skips
+    }
 }
 
 // PORTFOLIO REBALANCING WORKFLOW
 pub fn execute_complete_rebalancing(
     portfolio: &Portfolio,
     strategies: &[StrategyPerformanceData],
+    risk_limits: &RiskLimits,
 ) -> Result<RebalancingPlan> {
     // STEP 1: IDENTIFY UNDERPERFORMERS
-    let underperformers: Vec<&StrategyPerformanceData> = strategies
-        .iter()
-        .filter(|s| s.percentile_rank < portfolio.rebalance_threshold)
-        .collect();
+    // Exits whose fees would exceed the portfolio's cap are left in place
+    let mut underperformers: Vec<&StrategyPerformanceData> = Vec::new();
+    for strategy in strategies.iter().filter(|s| s.percentile_rank < portfolio.rebalance_threshold) {
+        let extractable = portfolio.extractable_balance(strategy.current_balance);
+        if is_extraction_economical(&strategy.protocol_type, extractable, portfolio.max_extraction_fee_bps)? {
+            underperformers.push(strategy);
+        }
+    }
     
     // STEP 2: IDENTIFY TOP PERFORMERS
     let top_performers: Vec<&StrategyPerformanceData> = strategies
         .iter()
-        .filter(|s| s.percentile_rank >= 75) // Top quartile
-        .take(5) // Limit to top 5 for diversification
+        .filter(|s| s.percentile_rank >= risk_limits.top_performer_percentile)
+        .take(risk_limits.max_top_performers as usize)
         .collect();
     
     require!(!underperformers.is_empty(), ErrorCode::InsufficientStrategies);
@@ -275,38 +684,66 @@ pub fn execute_complete_rebalancing(
     // STEP 3: CALCULATE TOTAL EXTRACTABLE CAPITAL
     let total_extractable: u64 = underperformers
         .iter()
-        .map(|s| s.current_balance.saturating_sub(10_000_000)) // Keep rent minimum
+        .map(|s| portfolio.extractable_balance(s.current_balance)) // Keep rent buffer
         .sum();
     
     require!(total_extractable > 100_000_000, ErrorCode::InsufficientBalance); // 0.1 SOL minimum
     
+    // STEP 3B: PRICE EXTRACTION WITH EACH PROTOCOL'S ACTUAL FEE MODEL
+    let mut estimated_fees = 0u64;
+    for strategy in underperformers.iter() {
+        let extractable = portfolio.extractable_balance(strategy.current_balance);
+        estimated_fees = estimated_fees
+            .checked_add(estimate_extraction_fees(&strategy.protocol_type, extractable)?)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+    }
+    
     // STEP 4: GENERATE OPTIMAL ALLOCATION  
-    let risk_limits = RiskLimits::default();
     let top_performers_data: Vec<StrategyPerformanceData> = top_performers.iter().map(|&s| s.clone()).collect();
-    let allocations = calculate_optimal_allocation(
+    let OptimalAllocation { allocations, unallocated_dust } = calculate_optimal_allocation(
         total_extractable,
         &top_performers_data,
-        &risk_limits,
+        risk_limits,
     )?;
     
     Ok(RebalancingPlan {
         extraction_targets: underperformers.iter().map(|s| s.strategy_id).collect(),
         total_to_extract: total_extractable,
         redistribution_plan: allocations,
-        estimated_fees: (total_extractable * 200) / 10000, // 2% estimated fees
+        unallocated_dust,
+        estimated_fees,
         expected_improvement: calculate_expected_improvement(&top_performers),
     })
 }
 
-#[derive(Debug, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct RebalancingPlan {
     pub extraction_targets: Vec<Pubkey>,
     pub total_to_extract: u64,
     pub redistribution_plan: Vec<CapitalAllocation>,
+    pub unallocated_dust: u64,         // Extracted capital the plan leaves undeployed
     pub estimated_fees: u64,
     pub expected_improvement: u64, // Expected performance score improvement
 }
 
+impl RebalancingPlan {
+    // WORTHWHILE-REBALANCE GATE
+    // Improvement must exceed fees scaled by the portfolio's efficiency ratio,
+    // so at 30000 bps a rebalance needs 3x its fees in expected improvement.
+    pub fn is_worthwhile(&self, min_improvement_ratio_bps: u16) -> bool {
+        let required = self.estimated_fees as u128 * min_improvement_ratio_bps as u128;
+        self.expected_improvement as u128 * 10000 > required
+    }
+    
+    pub fn ensure_worthwhile(&self, portfolio: &Portfolio) -> Result<()> {
+        require!(
+            self.is_worthwhile(portfolio.min_improvement_ratio_bps),
+            ErrorCode::RebalanceNotWorthwhile
+        );
+        Ok(())
+    }
+}
+
 pub fn calculate_expected_improvement(top_performers: &[&StrategyPerformanceData]) -> u64 {
     if top_performers.is_empty() {
         return 0;
@@ -319,4 +756,824 @@ pub fn calculate_expected_improvement(top_performers: &[&StrategyPerformanceData
     
     // Estimate 10-20% performance improvement from rebalancing
     (average_top_score * 15) / 100
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::test_strategy;
+    
+    fn staking_strategy(performance_score: u64, volatility_score: u32) -> StrategyPerformanceData {
+        StrategyPerformanceData {
+            strategy_id: Pubkey::new_unique(),
+            performance_score,
+            current_balance: 5_000_000_000,
+            volatility_score,
+            protocol_type: ProtocolType::LiquidStaking {
+                validator_id: Pubkey::new_unique(),
+                commission: 500,
+                stake_pool: Pubkey::new_unique(),
+                unstake_delay: 10,
+            },
+            percentile_rank: 90,
+            skip_count: 0,
+        }
+    }
+    
+    // Only the settings every rebalancing test leans on; tests of a single
+    // feature set that feature's fields themselves
+    fn test_portfolio() -> Portfolio {
+        let zeroed = Portfolio::deserialize(&mut &[0u8; Portfolio::MAX_SIZE - 8][..]).unwrap();
+        Portfolio {
+            manager: Pubkey::new_unique(),
+            rebalance_threshold: 25,
+            min_rebalance_interval: 3600,
+            performance_fee_bps: 200,
+            bump: 255,
+            min_improvement_ratio_bps: 10000,
+            yield_weight_bps: 4500,
+            balance_weight_bps: 3500,
+            volatility_weight_bps: 2000,
+            min_strategies_for_rebalance: 2,
+            schema_version: CURRENT_PORTFOLIO_SCHEMA,
+            max_strategies: MAX_STRATEGIES_LIMIT,
+            ..zeroed
+        }
+    }
+    
+    fn ranked_strategy(protocol_type: ProtocolType, balance: u64, percentile_rank: u8) -> StrategyPerformanceData {
+        StrategyPerformanceData {
+            strategy_id: Pubkey::new_unique(),
+            performance_score: 1000 + percentile_rank as u64 * 50,
+            current_balance: balance,
+            volatility_score: 3000,
+            protocol_type,
+            percentile_rank,
+            skip_count: 0,
+        }
+    }
+    
+    fn configured_risk_limits() -> RiskLimits {
+        RiskLimits {
+            platform_treasury: Pubkey::new_unique(),
+            manager_treasury: Pubkey::new_unique(),
+            ..RiskLimits::default()
+        }
+    }
+    
+    #[test]
+    fn test_fees_to_unset_treasury_rejected() {
+        let strategy = staking_strategy(5000, 2000);
+        
+        // Default limits charge nonzero fees but leave both treasuries unset
+        let result = calculate_optimal_allocation(
+            10_000_000_000,
+            std::slice::from_ref(&strategy),
+            &RiskLimits::default(),
+        );
+        assert_eq!(result.unwrap_err(), ErrorCode::TreasuryNotConfigured.into());
+        
+        // Only the platform treasury set: manager fee still has nowhere to go
+        let platform_only = RiskLimits {
+            platform_treasury: Pubkey::new_unique(),
+            ..RiskLimits::default()
+        };
+        let result = calculate_optimal_allocation(
+            10_000_000_000,
+            std::slice::from_ref(&strategy),
+            &platform_only,
+        );
+        assert_eq!(result.unwrap_err(), ErrorCode::TreasuryNotConfigured.into());
+        
+        // Fee-free configuration does not need treasuries
+        let fee_free = RiskLimits {
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            ..RiskLimits::default()
+        };
+        assert!(calculate_optimal_allocation(10_000_000_000, &[strategy], &fee_free).is_ok());
+    }
+    
+    #[test]
+    fn test_configured_treasuries_receive_fees() {
+        let risk_limits = configured_risk_limits();
+        let allocations = calculate_optimal_allocation(
+            10_000_000_000,
+            &[staking_strategy(5000, 2000)],
+            &risk_limits,
+        ).unwrap().allocations;
+        
+        let platform = allocations.iter()
+            .find(|a| matches!(a.allocation_type, AllocationType::PlatformFee))
+            .unwrap();
+        assert_eq!(platform.strategy_id, risk_limits.platform_treasury);
+        assert_eq!(platform.amount, 50_000_000);
+    }
+    
+    #[test]
+    fn test_protocol_accurate_fee_estimate_for_mixed_portfolio() {
+        let lending = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        let farming = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 30,
+        };
+        let staking = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        
+        let strategies = vec![
+            ranked_strategy(lending, 2_010_000_000, 10),
+            ranked_strategy(farming, 2_010_000_000, 15),
+            ranked_strategy(staking, 2_010_000_000, 20),
+            ranked_strategy(lending, 5_000_000_000, 90),
+        ];
+        
+        let plan = execute_complete_rebalancing(&test_portfolio(), &strategies, &configured_risk_limits()).unwrap();
+        
+        // Each underperformer has 2 SOL extractable after the rent buffer
+        assert_eq!(plan.total_to_extract, 6_000_000_000);
+        let flat_estimate = plan.total_to_extract * 200 / 10000;
+        
+        // Lending: free; farming: 0.5% slippage + 0.3% fee; staking: 2% penalty + 5% commission on the rest
+        let lending_fees = 0;
+        let farming_fees = 10_000_000 + 6_000_000;
+        let staking_fees = 40_000_000 + 98_000_000;
+        assert_eq!(plan.estimated_fees, lending_fees + farming_fees + staking_fees);
+        
+        // The staking commission makes the real cost exceed the flat 2% guess
+        assert!(plan.estimated_fees > flat_estimate);
+    }
+    
+    #[test]
+    fn test_repeatedly_skipped_strategy_eventually_allocated() {
+        let lending = |performance_score: u64| StrategyPerformanceData {
+            strategy_id: Pubkey::new_unique(),
+            performance_score,
+            current_balance: 1_000_000_000,
+            volatility_score: 3000,
+            protocol_type: ProtocolType::StableLending {
+                pool_id: Pubkey::new_unique(),
+                utilization: 5000,
+                reserve_address: Pubkey::new_unique(),
+            },
+            percentile_rank: 80,
+            skip_count: 0,
+        };
+        let dominant = lending(10000);
+        let mut marginal = lending(40);
+        let risk_limits = RiskLimits {
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            ..RiskLimits::default()
+        };
+        
+        let mut allocated_cycle = None;
+        for cycle in 0..10 {
+            let allocations = calculate_optimal_allocation(
+                10_000_000_000,
+                &[dominant.clone(), marginal.clone()],
+                &risk_limits,
+            ).unwrap().allocations;
+            
+            let allocated = allocations.iter().any(|a| a.strategy_id == marginal.strategy_id);
+            if allocated {
+                allocated_cycle = Some(cycle);
+                break;
+            }
+            
+            // Improving but still below the 1% minimum share on its own
+            marginal.skip_count = marginal.skip_count.saturating_add(1);
+            marginal.performance_score += 5;
+        }
+        
+        // Skipped for max_skips + 1 cycles, then guaranteed a slice
+        assert_eq!(allocated_cycle, Some(risk_limits.max_skips as usize + 1));
+    }
+    
+    #[test]
+    fn test_extreme_volatility_floors_to_protocol_minimum() {
+        // Calm strategy first so it absorbs the dust sweep
+        let calm = staking_strategy(5000, 0);
+        let volatile = staking_strategy(5000, 10000);
+        let risk_limits = RiskLimits {
+            risk_tolerance_bps: 1000, // multiplier collapses to 5% for the volatile strategy
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            ..RiskLimits::default()
+        };
+        
+        let allocations = calculate_optimal_allocation(
+            10_000_000_000,
+            &[calm, volatile.clone()],
+            &risk_limits,
+        ).unwrap().allocations;
+        
+        let allocation = allocations.iter()
+            .find(|a| a.strategy_id == volatile.strategy_id)
+            .expect("volatile strategy should receive the protocol minimum");
+        assert_eq!(allocation.amount, risk_limits.minimum_balance(&volatile.protocol_type));
+    }
+    
+    #[test]
+    fn test_extreme_volatility_skipped_without_floor() {
+        let volatile = staking_strategy(5000, 10000);
+        let risk_limits = RiskLimits {
+            risk_tolerance_bps: 1000,
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            floor_risk_adjusted_to_minimum: false,
+            ..RiskLimits::default()
+        };
+        
+        let allocations = calculate_optimal_allocation(
+            10_000_000_000,
+            std::slice::from_ref(&volatile),
+            &risk_limits,
+        ).unwrap().allocations;
+        
+        // Skipped cleanly, never a sub-minimum position
+        assert!(allocations.iter().all(|a| a.strategy_id != volatile.strategy_id));
+    }
+    
+    #[test]
+    fn test_positive_rebalance_below_efficiency_ratio_rejected() {
+        let plan = RebalancingPlan {
+            extraction_targets: vec![Pubkey::new_unique()],
+            total_to_extract: 1_000_000_000,
+            redistribution_plan: vec![],
+            unallocated_dust: 0,
+            estimated_fees: 1_000,
+            expected_improvement: 2_000, // 2x the fees: positive but marginal
+        };
+        
+        // Default 1x gate accepts any improvement above fees
+        let mut portfolio = test_portfolio();
+        assert!(plan.ensure_worthwhile(&portfolio).is_ok());
+        
+        // A manager demanding 3x rejects it
+        portfolio.min_improvement_ratio_bps = 30000;
+        assert!(plan.ensure_worthwhile(&portfolio).is_err());
+        
+        // Improvement that merely equals fees never passes
+        let break_even = RebalancingPlan { expected_improvement: 1_000, ..plan };
+        assert!(!break_even.is_worthwhile(10000));
+    }
+    
+    #[test]
+    fn test_gas_negative_allocations_pruned() {
+        let lending = |performance_score: u64| StrategyPerformanceData {
+            strategy_id: Pubkey::new_unique(),
+            performance_score,
+            current_balance: 1_000_000_000,
+            volatility_score: 5000, // Neutral risk multiplier
+            protocol_type: ProtocolType::StableLending {
+                pool_id: Pubkey::new_unique(),
+                utilization: 5000,
+                reserve_address: Pubkey::new_unique(),
+            },
+            percentile_rank: 90,
+            skip_count: 0,
+        };
+        
+        // Many small slices ahead of one dominant strategy
+        let mut strategies: Vec<StrategyPerformanceData> = (0..9).map(|_| lending(150)).collect();
+        strategies.push(lending(9000));
+        
+        let limits = RiskLimits {
+            max_single_strategy_bps: 10000,
+            min_single_strategy_bps: 0,
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            risk_tolerance_bps: 10000,
+            ..RiskLimits::default()
+        };
+        let available = 10_000_000_000;
+        
+        let without_gas = calculate_optimal_allocation(available, &strategies, &limits).unwrap().allocations;
+        assert_eq!(without_gas.len(), 10);
+        
+        let gas_limits = RiskLimits {
+            gas_cost_per_transfer: 5_000_000,
+            min_net_allocation: 135_000_000,
+            ..limits
+        };
+        let with_gas = calculate_optimal_allocation(available, &strategies, &gas_limits).unwrap().allocations;
+        
+        // The smallest slices are pruned and no allocation is worth less than the threshold
+        assert!(with_gas.len() < without_gas.len());
+        assert!(with_gas.iter().all(|a| a.amount >= gas_limits.min_net_allocation));
+        assert!(with_gas.iter().any(|a| a.strategy_id == strategies[9].strategy_id));
+        
+        // Credited capital plus gas never exceeds what was available
+        let credited: u64 = with_gas.iter().map(|a| a.amount).sum();
+        assert!(credited + with_gas.len() as u64 * gas_limits.gas_cost_per_transfer <= available);
+    }
+    
+    #[test]
+    fn test_over_constrained_minimum_is_rejected() {
+        let strategies: Vec<StrategyPerformanceData> = (0..4)
+            .map(|_| staking_strategy(5000, 1000))
+            .collect();
+        
+        // 4 strategies x 30% minimum = 120%, impossible to satisfy
+        let limits = RiskLimits {
+            max_single_strategy_bps: 10000,
+            min_single_strategy_bps: 3000,
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            ..RiskLimits::default()
+        };
+        let result = calculate_optimal_allocation(10_000_000_000, &strategies, &limits);
+        assert_eq!(result.unwrap_err(), ErrorCode::OverConstrainedAllocation.into());
+        
+        // 25% each fits exactly
+        let feasible = RiskLimits { min_single_strategy_bps: 2500, ..limits };
+        assert!(calculate_optimal_allocation(10_000_000_000, &strategies, &feasible).is_ok());
+    }
+    
+    #[test]
+    fn test_free_rebalance_allowance_waives_then_charges() {
+        let mut portfolio = Portfolio {
+            free_rebalances_per_period: 2,
+            free_rebalance_period_seconds: 2_592_000,
+            ..test_portfolio()
+        };
+        let limits = configured_risk_limits();
+        let strategies = vec![staking_strategy(8000, 2000), staking_strategy(6000, 3000)];
+        let start = 1_700_000_000;
+        
+        let has_fees = |waived: bool| {
+            let effective = if waived { limits.with_fees_waived() } else { limits.clone() };
+            calculate_optimal_allocation(10_000_000_000, &strategies, &effective)
+                .unwrap().allocations
+                .iter()
+                .any(|a| !a.allocation_type.targets_strategy())
+        };
+        
+        // First two rebalances in the period are free, the third pays fees
+        assert!(!has_fees(portfolio.consume_free_rebalance(start)));
+        assert!(!has_fees(portfolio.consume_free_rebalance(start + 86_400)));
+        assert!(!portfolio.free_rebalance_available(start + 172_800));
+        assert!(has_fees(portfolio.consume_free_rebalance(start + 172_800)));
+        
+        // The counter resets on the period boundary
+        let next_period = start + portfolio.free_rebalance_period_seconds;
+        assert!(portfolio.free_rebalance_available(next_period));
+        assert!(portfolio.consume_free_rebalance(next_period));
+        assert_eq!(portfolio.free_rebalances_used, 1);
+        assert_eq!(portfolio.free_rebalance_window_start, next_period);
+    }
+    
+    #[test]
+    fn test_stored_risk_limits_route_fees() {
+        let limits = configured_risk_limits();
+        assert!(limits.validate().is_ok());
+        assert_eq!(limits.treasury_for(&AllocationType::PlatformFee), Some(limits.platform_treasury));
+        assert_eq!(limits.treasury_for(&AllocationType::ManagerIncentive), Some(limits.manager_treasury));
+        assert_eq!(limits.treasury_for(&AllocationType::TopPerformer), None);
+        
+        // Defaults charge fees without treasuries and cannot be stored
+        assert_eq!(RiskLimits::default().validate().unwrap_err(), ErrorCode::TreasuryNotConfigured.into());
+        let inverted = RiskLimits { min_single_strategy_bps: 5000, max_single_strategy_bps: 4000, ..limits.clone() };
+        assert_eq!(inverted.validate().unwrap_err(), ErrorCode::InvalidRiskLimits.into());
+        
+        // Fee allocations to the default pubkey are rejected outright
+        let allocations = vec![CapitalAllocation {
+            strategy_id: Pubkey::default(),
+            amount: 1_000_000,
+            allocation_type: AllocationType::PlatformFee,
+        }];
+        assert_eq!(validate_allocations(&allocations).unwrap_err(), ErrorCode::TreasuryNotConfigured.into());
+    }
+    
+    #[test]
+    fn test_normalized_allocations_sum_to_post_fee_capital() {
+        // Deterministic LCG so the randomized cases are reproducible
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        
+        for _ in 0..500 {
+            let count = 1 + next(8) as usize;
+            let strategies: Vec<StrategyPerformanceData> = (0..count)
+                .map(|_| staking_strategy(1 + next(10_000), next(10_000) as u32))
+                .collect();
+            let limits = RiskLimits {
+                max_single_strategy_bps: 2000 + next(8001),
+                min_single_strategy_bps: next(1000 / count as u64 + 1),
+                gas_cost_per_transfer: next(3) * 5_000,
+                normalize_to_capital: true,
+                ..configured_risk_limits()
+            };
+            let available = 1_000_000_000 + next(100_000_000_000);
+            
+            let allocations = calculate_optimal_allocation(available, &strategies, &limits).unwrap().allocations;
+            let fees: u64 = allocations.iter()
+                .filter(|a| !a.allocation_type.targets_strategy())
+                .map(|a| a.amount)
+                .sum();
+            let credited: Vec<u64> = allocations.iter()
+                .filter(|a| a.allocation_type.targets_strategy())
+                .map(|a| a.amount)
+                .collect();
+            if credited.is_empty() {
+                continue;
+            }
+            
+            let gas = credited.len() as u64 * limits.gas_cost_per_transfer;
+            assert_eq!(credited.iter().sum::<u64>() + gas, available - fees);
+            assert!(validate_allocations(&allocations).is_ok());
+        }
+    }
+    
+    #[test]
+    fn test_normalization_reclaims_skipped_capital() {
+        let strategies = vec![staking_strategy(8000, 2000), staking_strategy(6000, 3000)];
+        let limits = RiskLimits {
+            max_single_strategy_bps: 10000,
+            min_single_strategy_bps: 0,
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            ..RiskLimits::default()
+        };
+        let available = 10_000_000_000;
+        
+        // By default the risk-adjusted shortfall is swept to the top performer
+        let loose = calculate_optimal_allocation(available, &strategies, &limits).unwrap().allocations;
+        assert!(loose.iter().map(|a| a.amount).sum::<u64>() <= available);
+        
+        // Strict mode splits everything by performance score instead
+        let strict_limits = RiskLimits { normalize_to_capital: true, ..limits };
+        let strict = calculate_optimal_allocation(available, &strategies, &strict_limits).unwrap().allocations;
+        assert_eq!(strict.iter().map(|a| a.amount).sum::<u64>(), available);
+        assert_eq!(strict[1].amount, available * 6000 / 14000);
+        assert!(strict[1].amount > loose[1].amount);
+    }
+    
+    #[test]
+    fn test_saturated_category_overflows_to_other_categories() {
+        let mut lending = staking_strategy(3000, 0);
+        lending.protocol_type = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        let strategies = vec![staking_strategy(9000, 0), staking_strategy(8000, 0), lending];
+        let limits = RiskLimits {
+            max_single_strategy_bps: 10000,
+            min_single_strategy_bps: 0,
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            risk_tolerance_bps: 10000,
+            max_staking_bps: 5000,
+            ..RiskLimits::default()
+        };
+        assert!(limits.validate().is_ok());
+        let available = 10_000_000_000;
+        
+        let allocations = calculate_optimal_allocation(available, &strategies, &limits).unwrap().allocations;
+        let amount_for = |id: Pubkey| allocations.iter().find(|a| a.strategy_id == id).map(|a| a.amount);
+        
+        // Staking fills its 50% ceiling with the first strategy; the second is skipped
+        assert_eq!(amount_for(strategies[0].strategy_id), Some(5_000_000_000));
+        assert_eq!(amount_for(strategies[1].strategy_id), None);
+        
+        // The overflow, including the dust sweep, lands in lending
+        assert_eq!(amount_for(strategies[2].strategy_id), Some(5_000_000_000));
+        assert_eq!(allocations.iter().map(|a| a.amount).sum::<u64>(), available);
+        
+        let over_cap = RiskLimits { max_farming_bps: 10001, ..limits };
+        assert_eq!(over_cap.validate().unwrap_err(), ErrorCode::InvalidRiskLimits.into());
+    }
+    
+    #[test]
+    fn test_dry_run_reports_failure_without_mutating() {
+        let portfolio = test_portfolio();
+        let strategy_id = Pubkey::new_unique();
+        let allocation = |amount: u64| CapitalAllocation {
+            strategy_id,
+            amount,
+            allocation_type: AllocationType::TopPerformer,
+        };
+        
+        // A duplicate destination is reported with its error code, not raised
+        let mut preview = portfolio.clone();
+        let outcome = prepare_redistribution(&mut preview, &[allocation(100), allocation(200)], 1_700_000_000)
+            .map(|total| RedistributionOutcome::succeeded(total, &AppliedAllocations::default()))
+            .unwrap_or_else(|error| RedistributionOutcome::failed(&error));
+        assert!(!outcome.would_succeed);
+        assert_eq!(outcome.reason_code, u32::from(ErrorCode::DuplicateStrategy));
+        
+        // A passing plan reports totals net of skips; only the copy consumed the free rebalance
+        let mut portfolio = portfolio;
+        portfolio.free_rebalances_per_period = 1;
+        portfolio.undeployed_capital = 500;
+        let mut preview = portfolio.clone();
+        let total = prepare_redistribution(&mut preview, &[allocation(500)], 1_700_000_000).unwrap();
+        let applied = AppliedAllocations { fees_allocated: 0, skipped_amount: 200 };
+        let outcome = RedistributionOutcome::succeeded(total, &applied);
+        assert!(outcome.would_succeed);
+        assert_eq!(outcome.total_allocated, 300);
+        assert_eq!(preview.free_rebalances_used, 1);
+        assert_eq!(portfolio.free_rebalances_used, 0);
+    }
+    
+    // Strategy account as the program would hold it at its PDA under `portfolio`.
+    // Leaked so it can stand in for remaining_accounts.
+    fn strategy_account(portfolio: &Pubkey, mut strategy: Strategy) -> AccountInfo<'static> {
+        let (key, bump) = Pubkey::find_program_address(
+            &[b"strategy", portfolio.as_ref(), strategy.strategy_id.as_ref()],
+            &crate::ID,
+        );
+        strategy.bump = bump;
+        let mut data = Vec::new();
+        strategy.try_serialize(&mut data).unwrap();
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            true,
+            Box::leak(Box::new(1_000_000)),
+            Box::leak(data.into_boxed_slice()),
+            &crate::ID,
+            false,
+            0,
+        )
+    }
+    
+    #[test]
+    fn test_applied_allocations_record_skips() {
+        let portfolio_key = Pubkey::new_unique();
+        let funded = Strategy { skip_count: 2, ..test_strategy(1_000_000_000, 1_000_000_000) };
+        let passed_over = Strategy { skip_count: 2, ..test_strategy(1_000_000_000, 1_000_000_000) };
+        let funded_id = funded.strategy_id;
+        let accounts: &'static [AccountInfo<'static>] = Box::leak(Box::new([
+            strategy_account(&portfolio_key, funded),
+            strategy_account(&portfolio_key, passed_over),
+        ]));
+        
+        let allocations = [CapitalAllocation {
+            strategy_id: funded_id,
+            amount: 500_000_000,
+            allocation_type: AllocationType::TopPerformer,
+        }];
+        apply_allocations(&allocations, accounts, &test_portfolio(), &portfolio_key, None, 1_700_000_000, true)
+            .unwrap();
+        
+        let reload = |info: &AccountInfo| Strategy::try_deserialize(&mut &info.try_borrow_data().unwrap()[..]).unwrap();
+        let funded = reload(&accounts[0]);
+        assert_eq!(funded.current_balance, 1_500_000_000);
+        assert_eq!(funded.skip_count, 0);
+        
+        // Passed in but left out of the plan: one step closer to a guaranteed slice
+        assert_eq!(reload(&accounts[1]).skip_count, 3);
+    }
+    
+    #[test]
+    fn test_duplicate_destination_rejected() {
+        let portfolio_key = Pubkey::new_unique();
+        let destination = strategy_account(&portfolio_key, test_strategy(1_000_000_000, 1_000_000_000));
+        let strategy_id = Strategy::try_deserialize(&mut &destination.try_borrow_data().unwrap()[..])
+            .unwrap()
+            .strategy_id;
+        
+        // The stale second copy would be written back last and erase the credit
+        let accounts: &'static [AccountInfo<'static>] = Box::leak(Box::new([destination.clone(), destination]));
+        let allocations = [CapitalAllocation {
+            strategy_id,
+            amount: 500_000_000,
+            allocation_type: AllocationType::TopPerformer,
+        }];
+        assert_eq!(
+            apply_allocations(&allocations, accounts, &test_portfolio(), &portfolio_key, None, 1_700_000_000, true)
+                .unwrap_err(),
+            ErrorCode::DuplicateStrategy.into()
+        );
+    }
+    
+    #[test]
+    fn test_redistribution_limited_to_undeployed_capital() {
+        let mut portfolio = Portfolio { undeployed_capital: 1_000, ..test_portfolio() };
+        let allocation = |amount: u64| CapitalAllocation {
+            strategy_id: Pubkey::new_unique(),
+            amount,
+            allocation_type: AllocationType::TopPerformer,
+        };
+        
+        // Nothing beyond what extraction released can be handed out
+        assert_eq!(
+            prepare_redistribution(&mut portfolio.clone(), &[allocation(600), allocation(401)], 1_700_000_000)
+                .unwrap_err(),
+            ErrorCode::ExceedsUndeployedCapital.into()
+        );
+        let total = prepare_redistribution(&mut portfolio, &[allocation(600), allocation(400)], 1_700_000_000).unwrap();
+        
+        // Skipped capital stays available for the next redistribution
+        portfolio.deploy_capital(total - 400).unwrap();
+        assert_eq!(portfolio.undeployed_capital, 400);
+        assert_eq!(portfolio.deploy_capital(401).unwrap_err(), ErrorCode::ExceedsUndeployedCapital.into());
+        
+        portfolio.release_capital(250).unwrap();
+        assert_eq!(portfolio.undeployed_capital, 650);
+    }
+    
+    #[test]
+    fn test_rent_buffer_never_undercuts_rent_exemption() {
+        let floor = Portfolio::rent_exempt_floor(&Rent::default());
+        assert_eq!(
+            Portfolio::validate_rent_buffer(floor - 1, floor).unwrap_err(),
+            ErrorCode::InvalidRentBuffer.into()
+        );
+        assert!(Portfolio::validate_rent_buffer(floor, floor).is_ok());
+        assert!(Portfolio::validate_rent_buffer(1_000_000_001, floor).is_err());
+        
+        // Unset falls back to the default, which already clears the floor
+        let mut portfolio = test_portfolio();
+        portfolio.rent_buffer_lamports = 0;
+        assert!(portfolio.rent_buffer() >= floor);
+        
+        // Whatever the balance, an extraction leaves at least the floor behind
+        for buffer in [0, floor, 50_000_000] {
+            portfolio.rent_buffer_lamports = buffer;
+            for balance in [floor, floor + 1, 20_000_000, 5_000_000_000] {
+                let remaining = balance - portfolio.extractable_balance(balance);
+                assert!(remaining >= floor, "buffer {} balance {}", buffer, balance);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_configured_minimums_decide_eligibility() {
+        let lending = ranked_strategy(
+            ProtocolType::StableLending {
+                pool_id: Pubkey::new_unique(),
+                utilization: 5000,
+                reserve_address: Pubkey::new_unique(),
+            },
+            5_000_000_000,
+            80,
+        );
+        let staking = staking_strategy(lending.performance_score, 3000);
+        let strategies = [lending.clone(), staking.clone()];
+        let fee_free = RiskLimits {
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            floor_risk_adjusted_to_minimum: false,
+            ..RiskLimits::default()
+        };
+        let allocated = |limits: &RiskLimits| -> Vec<Pubkey> {
+            calculate_optimal_allocation(10_000_000_000, &strategies, limits)
+                .unwrap().allocations
+                .iter()
+                .map(|a| a.strategy_id)
+                .collect()
+        };
+        
+        // Default minimums leave room for both
+        let both = allocated(&fee_free);
+        assert!(both.contains(&lending.strategy_id) && both.contains(&staking.strategy_id));
+        
+        // A staking minimum above the 40% single-strategy cap drops it
+        let strict_staking = RiskLimits { min_balance_staking: 5_000_000_000, ..fee_free.clone() };
+        let lending_only = allocated(&strict_staking);
+        assert!(lending_only.contains(&lending.strategy_id) && !lending_only.contains(&staking.strategy_id));
+        
+        // The same minimum gates registration and deposits
+        assert!(staking.protocol_type.validate_balance_constraints(1_000_000_000, &fee_free).is_ok());
+        assert_eq!(
+            staking.protocol_type.validate_balance_constraints(1_000_000_000, &strict_staking).unwrap_err(),
+            ErrorCode::InsufficientBalance.into()
+        );
+    }
+    
+    #[test]
+    fn test_fee_math_with_huge_capital() {
+        let available_capital = u64::MAX / 1000;
+        let strategies = [staking_strategy(5000, 2000), staking_strategy(4000, 2000)];
+        let limits = configured_risk_limits();
+        
+        // 40% of this capital overflows a plain u64 product
+        let allocations = calculate_optimal_allocation(available_capital, &strategies, &limits).unwrap().allocations;
+        
+        let fee = |strategy_id: Pubkey| allocations.iter()
+            .find(|a| a.strategy_id == strategy_id)
+            .map(|a| a.amount)
+            .unwrap();
+        assert_eq!(fee(limits.platform_treasury), (available_capital as u128 * 50 / 10000) as u64);
+        assert_eq!(fee(limits.manager_treasury), (available_capital as u128 * 150 / 10000) as u64);
+        
+        // Both strategies funded, and nothing allocated beyond the capital
+        assert!(strategies.iter().all(|s| allocations.iter().any(|a| a.strategy_id == s.strategy_id)));
+        let total: u128 = allocations.iter().map(|a| a.amount as u128).sum();
+        assert!(total <= available_capital as u128);
+        
+        assert_eq!(bps_of(u64::MAX, 10000).unwrap(), u64::MAX);
+        assert_eq!(bps_of(u64::MAX, 10001).unwrap_err(), ErrorCode::BalanceOverflow.into());
+    }
+    
+    #[test]
+    fn test_wider_top_performer_window_allocates_more() {
+        let lending = || ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        let strategies = vec![
+            ranked_strategy(lending(), 20_010_000_000, 10),
+            ranked_strategy(lending(), 5_000_000_000, 95),
+            ranked_strategy(lending(), 5_000_000_000, 85),
+            ranked_strategy(lending(), 5_000_000_000, 70),
+            ranked_strategy(lending(), 5_000_000_000, 60),
+        ];
+        let funded = |limits: &RiskLimits| -> usize {
+            execute_complete_rebalancing(&test_portfolio(), &strategies, limits)
+                .unwrap()
+                .redistribution_plan
+                .iter()
+                .filter(|a| a.allocation_type.targets_strategy())
+                .count()
+        };
+        
+        // The default top quartile funds the two leaders only
+        assert_eq!(funded(&configured_risk_limits()), 2);
+        
+        // Dropping the cutoff to 55 brings in the 70th and 60th percentiles
+        let wide = RiskLimits { top_performer_percentile: 55, ..configured_risk_limits() };
+        assert_eq!(funded(&wide), 4);
+        
+        // Breadth still caps how many of them are funded
+        let narrow = RiskLimits { max_top_performers: 3, ..wide.clone() };
+        assert_eq!(funded(&narrow), 3);
+        
+        // Both settings are bounded
+        assert!(wide.validate().is_ok());
+        for invalid in [
+            RiskLimits { top_performer_percentile: 49, ..wide.clone() },
+            RiskLimits { top_performer_percentile: 100, ..wide.clone() },
+            RiskLimits { max_top_performers: 0, ..wide.clone() },
+            RiskLimits { max_top_performers: 21, ..wide.clone() },
+        ] {
+            assert_eq!(invalid.validate().unwrap_err(), ErrorCode::InvalidRiskLimits.into());
+        }
+    }
+    
+    #[test]
+    fn test_racing_keepers_second_rejected() {
+        let mut portfolio = test_portfolio();
+        portfolio.last_rebalance = 1_700_000_000;
+        
+        // Both keepers read the same state before either lands
+        let observed_by_first = portfolio.last_rebalance;
+        let observed_by_second = portfolio.last_rebalance;
+        
+        // The first cycle passes its guard and advances last_rebalance
+        portfolio.require_last_rebalance(Some(observed_by_first)).unwrap();
+        portfolio.last_rebalance = 1_700_003_600;
+        
+        // The second now fails cleanly instead of ranking again
+        assert_eq!(
+            portfolio.require_last_rebalance(Some(observed_by_second)).unwrap_err(),
+            ErrorCode::StaleRebalanceState.into()
+        );
+        
+        // Callers that opt out are unaffected
+        assert!(portfolio.require_last_rebalance(None).is_ok());
+    }
+    
+    #[test]
+    fn test_sub_threshold_remainder_reported() {
+        // A single strategy capped at 40% leaves most of the capital behind
+        let strategies = [staking_strategy(5000, 2000)];
+        let available = 10_000_000_000;
+        let accounted = |outcome: &OptimalAllocation| -> u64 {
+            outcome.allocations.iter().map(|a| a.amount).sum::<u64>() + outcome.unallocated_dust
+        };
+        
+        // Above the threshold the leftover is swept into the top performer
+        let swept = calculate_optimal_allocation(available, &strategies, &configured_risk_limits()).unwrap();
+        assert_eq!(swept.unallocated_dust, 0);
+        assert_eq!(accounted(&swept), available);
+        
+        // Below a raised threshold it stays put and is reported, not lost
+        let limits = RiskLimits { dust_threshold_lamports: 100_000_000_000, ..configured_risk_limits() };
+        let kept = calculate_optimal_allocation(available, &strategies, &limits).unwrap();
+        assert!(kept.unallocated_dust > 0);
+        assert_eq!(accounted(&kept), available);
+        
+        // Every lamport is still accounted for once gas is charged
+        let gas_limits = RiskLimits { gas_cost_per_transfer: 5_000, ..limits };
+        let with_gas = calculate_optimal_allocation(available, &strategies, &gas_limits).unwrap();
+        let strategy_allocations = with_gas.allocations.iter().filter(|a| a.allocation_type.targets_strategy()).count();
+        assert_eq!(accounted(&with_gas) + 5_000 * strategy_allocations as u64, available);
+    }
+}
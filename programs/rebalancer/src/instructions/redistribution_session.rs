@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::redistribute_capital::{apply_allocations, validate_allocations, AppliedAllocations, MAX_ALLOCATIONS_PER_TX};
+
+// CHUNKED REDISTRIBUTION
+// begin_redistribution opens a session for an expected total, redistribute_chunk
+// credits up to MAX_ALLOCATIONS_PER_TX allocations per call (destinations are
+// deduplicated across chunks), and finalize_redistribution closes the session
+// once the chunks add up to exactly the expected total. Each chunk draws what it
+// credits from portfolio.undeployed_capital, as redistribute_capital does.
+// cancel_redistribution closes a session that can no longer reach its total,
+// keeping what the chunks already deployed. redistribute_capital refuses to run
+// while a session is open.
+
+#[derive(Accounts)]
+pub struct BeginRedistribution<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = RedistributionSession::MAX_SIZE,
+        seeds = [b"redistribution", portfolio.key().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, RedistributionSession>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn begin_redistribution(ctx: Context<BeginRedistribution>, total_expected: u64) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let portfolio = &mut ctx.accounts.portfolio;
+    let session = &mut ctx.accounts.session;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!portfolio.extraction_paused, ErrorCode::ExtractionPaused);
+    require!(portfolio.rebalance_permitted(), ErrorCode::DispersionBelowThreshold);
+    require!(!session.active, ErrorCode::RedistributionInProgress);
+    require!(total_expected > 0, ErrorCode::InsufficientBalance);
+    require!(total_expected <= portfolio.undeployed_capital, ErrorCode::ExceedsUndeployedCapital);
+    
+    // One session is one rebalance against the fee-free allowance
+    let fees_waived = portfolio.consume_free_rebalance(current_time);
+    
+    session.portfolio = portfolio_key;
+    session.expected_total = total_expected;
+    session.accumulated_total = 0;
+    session.fees_allocated = 0;
+    session.opened_at = current_time;
+    session.active = true;
+    session.fees_waived = fees_waived;
+    session.bump = ctx.bumps.session;
+    session.allocated_ids.clear();
+    
+    msg!("Redistribution session opened: expected={} lamports, fees_waived={}", total_expected, fees_waived);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RedistributeChunk<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"redistribution", portfolio.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, RedistributionSession>,
+    
+    // Required only when the chunk includes fees
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+    
+    pub manager: Signer<'info>,
+}
+
+// remaining_accounts: the destination Strategy account of every strategy allocation in the chunk
+pub fn redistribute_chunk<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RedistributeChunk<'info>>,
+    allocations: Vec<CapitalAllocation>,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let portfolio = &mut ctx.accounts.portfolio;
+    let session = &mut ctx.accounts.session;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!portfolio.extraction_paused, ErrorCode::ExtractionPaused);
+    require!(session.active, ErrorCode::NoActiveRedistribution);
+    require!(!allocations.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(allocations.len() <= MAX_ALLOCATIONS_PER_TX, ErrorCode::TooManyStrategies);
+    require!(
+        !session.fees_waived || allocations.iter().all(|a| a.allocation_type.targets_strategy()),
+        ErrorCode::FeesWaivedThisRebalance
+    );
+    
+    let chunk_total = validate_allocations(&allocations)?;
+    session.record_chunk(&allocations, chunk_total)?;
+    
+    // Skipped allocations still count toward the session's planned total
+    let AppliedAllocations { fees_allocated, skipped_amount } = apply_allocations(
+        &allocations,
+        ctx.remaining_accounts,
+        portfolio,
+        &portfolio_key,
+        ctx.accounts.risk_config.as_ref(),
+        current_time,
+        true,
+    )?;
+    portfolio.deploy_capital(chunk_total - skipped_amount)?;
+    session.fees_allocated = session.fees_allocated
+        .checked_add(fees_allocated)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    msg!("Redistribution chunk: {} lamports across {} allocations, {} skipped ({}/{} allocated)",
+         chunk_total, allocations.len(), skipped_amount, session.accumulated_total, session.expected_total);
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FinalizeRedistribution<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"redistribution", portfolio.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, RedistributionSession>,
+    
+    pub manager: Signer<'info>,
+}
+
+pub fn finalize_redistribution(ctx: Context<FinalizeRedistribution>) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let portfolio = &mut ctx.accounts.portfolio;
+    let session = &mut ctx.accounts.session;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(session.active, ErrorCode::NoActiveRedistribution);
+    require!(session.accumulated_total == session.expected_total, ErrorCode::RedistributionTotalMismatch);
+    
+    portfolio.total_fees_allocated = portfolio.total_fees_allocated
+        .checked_add(session.fees_allocated)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    portfolio.total_capital_moved = portfolio.total_capital_moved
+        .checked_add(session.accumulated_total)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    session.active = false;
+    
+    emit!(CapitalRedistributed {
+        portfolio: portfolio_key,
+        total_allocated: session.accumulated_total,
+        allocation_count: session.allocated_ids.len() as u32,
+        fees_allocated: session.fees_allocated,
+        timestamp: current_time,
+    });
+    
+    msg!("Redistribution session finalized: {} lamports across {} allocations",
+         session.accumulated_total, session.allocated_ids.len());
+    
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelRedistribution<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"redistribution", portfolio.key().as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, RedistributionSession>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Close a session short of its expected total, e.g. after a destination was
+// paused mid-session or undeployed capital ran out. Chunks already credited
+// stay deployed and are booked like a finalized session; the rest of the
+// expected total was never drawn from undeployed_capital.
+pub fn cancel_redistribution(ctx: Context<CancelRedistribution>) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let portfolio = &mut ctx.accounts.portfolio;
+    let session = &mut ctx.accounts.session;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(session.active, ErrorCode::NoActiveRedistribution);
+    
+    portfolio.total_fees_allocated = portfolio.total_fees_allocated
+        .checked_add(session.fees_allocated)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    portfolio.total_capital_moved = portfolio.total_capital_moved
+        .checked_add(session.accumulated_total)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    
+    session.active = false;
+    
+    emit!(RedistributionCancelled {
+        portfolio: portfolio_key,
+        expected_total: session.expected_total,
+        accumulated_total: session.accumulated_total,
+        fees_allocated: session.fees_allocated,
+        timestamp: current_time,
+    });
+    
+    msg!("Redistribution session cancelled: {} of {} lamports allocated",
+         session.accumulated_total, session.expected_total);
+    
+    Ok(())
+}
+
+// Whether the portfolio's redistribution session account, if it was ever
+// created, is still open. The caller pins the address via its seeds.
+pub fn redistribution_session_open(session_info: &AccountInfo) -> Result<bool> {
+    if session_info.owner != &crate::ID || session_info.data_is_empty() {
+        return Ok(false);
+    }
+    let session = RedistributionSession::try_deserialize(&mut &session_info.try_borrow_data()?[..])?;
+    Ok(session.active)
+}
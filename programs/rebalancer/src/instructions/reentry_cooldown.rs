@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+// RE-ENTRY DISCIPLINE
+// A strategy's protocol target is recorded as exited when the strategy is
+// deprecated and again whenever capital is extracted from it afterwards, and
+// register_strategy refuses that target until reentry_cooldown_seconds pass
+// from the last of those.
+
+// Records `target` as exited now; every path that deprecates a strategy or
+// extracts from a deprecated one calls this, so the cooldown can't be skipped
+pub fn record_strategy_exit(
+    portfolio: &mut Account<Portfolio>,
+    strategy_id: Pubkey,
+    target: Pubkey,
+    current_time: i64,
+) {
+    portfolio.recent_exits.record(target, current_time);
+    
+    emit!(ProtocolTargetExited {
+        portfolio: portfolio.key(),
+        strategy_id,
+        target,
+        cooldown_ends: current_time.saturating_add(portfolio.reentry_cooldown_seconds),
+        timestamp: current_time,
+    });
+    
+    msg!("Strategy exit recorded: strategy={}, target={}, cooldown={}s",
+         strategy_id, target, portfolio.reentry_cooldown_seconds);
+}
+
+#[derive(Accounts)]
+pub struct OverrideReentryCooldown<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+pub fn override_reentry_cooldown(
+    ctx: Context<OverrideReentryCooldown>,
+    target: Pubkey,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    
+    require!(portfolio.recent_exits.clear(&target), ErrorCode::StrategyNotFound);
+    
+    emit!(ReentryCooldownOverridden {
+        portfolio: portfolio.key(),
+        target,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Re-entry cooldown overridden for target {}", target);
+    
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::oracle::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey, position_index: u8)]
+pub struct RefreshPositionIl<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", strategy.key().as_ref(), &[position_index]],
+        bump = position.bump,
+        constraint = position.strategy_id == strategy_id @ ErrorCode::StrategyNotFound
+    )]
+    pub position: Account<'info, CapitalPosition>,
+    
+    /// CHECK: Must be the strategy's bound token A feed, validated in read_bound_oracle_quote
+    pub price_feed_a: UncheckedAccount<'info>,
+    
+    /// CHECK: Must be the strategy's bound token B feed, validated in read_bound_oracle_quote
+    pub price_feed_b: UncheckedAccount<'info>,
+    
+    pub manager: Signer<'info>,
+}
+
+// ORACLE-FED IMPERMANENT LOSS
+// Prices both legs of a liquidity-pair position from Pyth PriceUpdateV2
+// (pyth-solana-receiver-sdk 0.6 layout) or Switchboard On-Demand pull feeds
+// (switchboard-on-demand 0.1), see oracle.rs, and records the IL against its
+// entry prices. Each feed must be the one bound to that leg in
+// strategy.price_feeds, so the caller can't price the pair off other tokens.
+// The older of the two publish times must be within MAX_PRICE_AGE_SECONDS.
+pub fn refresh_position_il(
+    ctx: Context<RefreshPositionIl>,
+    _strategy_id: Pubkey,
+    _position_index: u8,
+) -> Result<()> {
+    let strategy = &ctx.accounts.strategy;
+    let position = &mut ctx.accounts.position;
+    
+    require!(
+        matches!(strategy.protocol_type, ProtocolType::YieldFarming { .. }),
+        ErrorCode::InvalidProtocolType
+    );
+    require!(position.position_type == PositionType::LiquidityPair, ErrorCode::InvalidProtocolType);
+    
+    let feeds = &strategy.price_feeds;
+    let (price_a, published_a) = read_bound_oracle_quote(&ctx.accounts.price_feed_a, feeds, &feeds.feed_a)?;
+    let (price_b, published_b) = read_bound_oracle_quote(&ctx.accounts.price_feed_b, feeds, &feeds.feed_b)?;
+    
+    position.impermanent_loss = position.calculate_current_impermanent_loss(
+        price_a,
+        price_b,
+        published_a.min(published_b),
+    )?;
+    
+    msg!("Position IL refreshed: strategy={}, source={:?}, price_a={}, price_b={}, IL={}bps",
+         strategy.strategy_id, feeds.source, price_a, price_b, position.impermanent_loss_bps());
+    
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::events::*;
 use crate::error::ErrorCode;
 
 #[derive(Accounts)]
@@ -7,8 +8,9 @@ use crate::error::ErrorCode;
 pub struct RegisterStrategy<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
         bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
         has_one = manager @ ErrorCode::UnauthorizedManager
     )]
     pub portfolio: Account<'info, Portfolio>,
@@ -22,6 +24,13 @@ pub struct RegisterStrategy<'info> {
     )]
     pub strategy: Account<'info, Strategy>,
     
+    // Per-protocol minimum balances; defaults apply without a stored config
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+    
     #[account(mut)]
     pub manager: Signer<'info>,
     
@@ -33,6 +42,7 @@ pub fn register_strategy(
     strategy_id: Pubkey,
     protocol_type: ProtocolType,
     initial_balance: u64,
+    price_feeds: Option<PriceFeedBinding>,
 ) -> Result<()> {
     let portfolio = &mut ctx.accounts.portfolio;
     let strategy = &mut ctx.accounts.strategy;
@@ -42,11 +52,22 @@ pub fn register_strategy(
     require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
     require!(strategy_id != Pubkey::default(), ErrorCode::InvalidStrategyId);
     require!(initial_balance > 0, ErrorCode::InsufficientBalance);
+    portfolio.require_strategy_capacity()?;
     Strategy::validate_balance_update(initial_balance)?;
     
     // PROTOCOL-SPECIFIC VALIDATION
     protocol_type.validate()?;
-    protocol_type.validate_balance_constraints(initial_balance)?;
+    let risk_limits = ctx.accounts.risk_config.as_ref()
+        .map(|risk_config| risk_config.limits.clone())
+        .unwrap_or_default();
+    protocol_type.validate_balance_constraints(initial_balance, &risk_limits)?;
+    portfolio.validate_leverage_policy(&protocol_type)?;
+    require!(
+        !portfolio.recent_exits.in_cooldown(
+            &protocol_type.target_id(), current_time, portfolio.reentry_cooldown_seconds
+        ),
+        ErrorCode::ProtocolTargetInCooldown
+    );
     
     // STRATEGY INITIALIZATION WITH SAFE DEFAULTS
     strategy.strategy_id = strategy_id;
@@ -61,14 +82,41 @@ pub fn register_strategy(
     strategy.total_deposits = initial_balance;
     strategy.total_withdrawals = 0;
     strategy.creation_time = current_time;
+    strategy.high_water_mark = initial_balance;
+    strategy.skip_count = 0;
     strategy.bump = ctx.bumps.strategy;
-    strategy.reserved = [0u8; 23];
+    strategy.compound_frequency_seconds = protocol_type.default_compound_frequency();
+    strategy.last_compounded = 0; // First compound allowed immediately
+    strategy.daily_withdrawal_limit_bps = 0; // Unlimited until configured
+    strategy.withdrawn_in_window = 0;
+    strategy.withdrawal_window_start = 0;
+    strategy.position_count = 0;
+    strategy.pending_balance = 0;
+    strategy.pending_until = 0;
+    strategy.last_rebalance = 0;
+    strategy.schema_version = CURRENT_STRATEGY_SCHEMA;
+    strategy.peak_balance = initial_balance;
+    strategy.max_drawdown_bps = 0;
+    strategy.price_feeds = PriceFeedBinding::UNBOUND; // Oracle-verified positions need a binding
+    if let Some(price_feeds) = price_feeds {
+        strategy.bind_price_feeds(price_feeds)?;
+    }
+    strategy.reserved = [0u8; 2];
     
     // UPDATE PORTFOLIO COUNTERS WITH OVERFLOW PROTECTION
     portfolio.total_strategies = portfolio.total_strategies
         .checked_add(1)
         .ok_or(ErrorCode::BalanceOverflow)?;
     
+    emit!(StrategyRegistered {
+        portfolio: portfolio.key(),
+        strategy_id,
+        protocol_type: strategy.protocol_type,
+        initial_balance,
+        price_feeds: strategy.price_feeds,
+        timestamp: current_time,
+    });
+    
     msg!("Strategy registered: ID={}, Protocol={}, Balance={}", 
          strategy_id, protocol_type.get_protocol_name(), initial_balance);
     
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use crate::instructions::migrate_portfolio::grow_account;
+
+// written_back and the entry count sit at the same offsets in every
+// RankingBuffer layout; only the entries after them changed
+const WRITTEN_BACK_OFFSET: usize = 8 + 32 + 8 + 1;
+const ENTRY_COUNT_OFFSET: usize = WRITTEN_BACK_OFFSET + 4 + 1;
+
+#[derive(Accounts)]
+pub struct ResetRankingBuffer<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    /// CHECK: may predate the current entry layout; rewritten by hand in reset_ranking_buffer
+    #[account(
+        mut,
+        owner = crate::ID @ ErrorCode::RankingBufferOutdated,
+        seeds = [b"ranking_buffer", portfolio.key().as_ref()],
+        bump
+    )]
+    pub ranking_buffer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// TWO-PHASE RANKING: RESET
+// Discards an unfinished cycle so submit_scores can start over, e.g. when a
+// submission went stale or a sorted cycle can no longer be written back.
+// A buffer written under an older entry layout is resized to the current one
+// on the way. Ranks already written to strategies stay as they are.
+pub fn reset_ranking_buffer(ctx: Context<ResetRankingBuffer>) -> Result<()> {
+    let buffer_info = ctx.accounts.ranking_buffer.to_account_info();
+    let manager_info = ctx.accounts.manager.to_account_info();
+    
+    let (ranks_written, entries_discarded) = {
+        let data = buffer_info.try_borrow_data()?;
+        require!(
+            data.len() >= ENTRY_COUNT_OFFSET + 4 && data[..8] == *RankingBuffer::DISCRIMINATOR,
+            ErrorCode::RankingBufferOutdated
+        );
+        (
+            u32::from_le_bytes(data[WRITTEN_BACK_OFFSET..WRITTEN_BACK_OFFSET + 4].try_into().unwrap()),
+            u32::from_le_bytes(data[ENTRY_COUNT_OFFSET..ENTRY_COUNT_OFFSET + 4].try_into().unwrap()),
+        )
+    };
+    
+    // FIT THE ACCOUNT TO THE CURRENT LAYOUT
+    let old_len = buffer_info.data_len();
+    if old_len < RankingBuffer::MAX_SIZE {
+        grow_account(
+            &buffer_info,
+            &manager_info,
+            &ctx.accounts.system_program.to_account_info(),
+            RankingBuffer::MAX_SIZE,
+        )?;
+    } else if old_len > RankingBuffer::MAX_SIZE {
+        buffer_info.resize(RankingBuffer::MAX_SIZE)?;
+        let surplus = buffer_info.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(RankingBuffer::MAX_SIZE));
+        buffer_info.sub_lamports(surplus)?;
+        manager_info.add_lamports(surplus)?;
+    }
+    
+    let ranking_buffer = RankingBuffer {
+        portfolio: ctx.accounts.portfolio.key(),
+        cycle_started: 0,
+        sorted: false,
+        written_back: 0,
+        bump: ctx.bumps.ranking_buffer,
+        entries: Vec::new(),
+    };
+    let mut data = buffer_info.try_borrow_mut_data()?;
+    data.fill(0);
+    let mut writer: &mut [u8] = &mut data;
+    ranking_buffer.try_serialize(&mut writer)?;
+    
+    emit!(RankingBufferReset {
+        portfolio: ranking_buffer.portfolio,
+        entries_discarded,
+        ranks_written,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Ranking buffer reset: {} entries discarded, {} ranks already written",
+         entries_discarded, ranks_written);
+    
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct SetCompoundFrequency<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    pub manager: Signer<'info>,
+}
+
+pub fn set_compound_frequency(
+    ctx: Context<SetCompoundFrequency>,
+    _strategy_id: Pubkey,
+    compound_frequency_seconds: i64,
+) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+    
+    Strategy::validate_compound_frequency(compound_frequency_seconds)?;
+    strategy.compound_frequency_seconds = compound_frequency_seconds;
+    
+    msg!("Compound frequency set: strategy={}, frequency={}s",
+         strategy.strategy_id, compound_frequency_seconds);
+    
+    Ok(())
+}
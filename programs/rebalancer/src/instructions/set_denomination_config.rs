@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct SetDenominationConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = DenominationConfig::MAX_SIZE,
+        seeds = [b"denomination", portfolio.key().as_ref()],
+        bump
+    )]
+    pub denomination_config: Account<'info, DenominationConfig>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// STABLE-UNIT DENOMINATION
+// Create or replace the price feed update_performance uses to score balances
+// by stable value. The feed is read at scoring time and must be no older than
+// MAX_PRICE_AGE_SECONDS (60s); a stale quote fails the update with StalePrice
+// instead of falling back to lamports, so scores never mix the two units.
+// Setting it marks the portfolio denomination_enabled, after which
+// update_performance refuses to score without it.
+pub fn set_denomination_config(
+    ctx: Context<SetDenominationConfig>,
+    oracle_source: OracleSource,
+    price_feed: Pubkey,
+    reference_price: u64,
+) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(reference_price > 0, ErrorCode::InvalidReferencePrice);
+    require!(price_feed != Pubkey::default(), ErrorCode::InvalidOracleAccount);
+    
+    let denomination_config = &mut ctx.accounts.denomination_config;
+    denomination_config.portfolio = ctx.accounts.portfolio.key();
+    denomination_config.price_feed = price_feed;
+    denomination_config.oracle_source = oracle_source;
+    denomination_config.reference_price = reference_price;
+    denomination_config.last_updated = current_time;
+    denomination_config.bump = ctx.bumps.denomination_config;
+    ctx.accounts.portfolio.denomination_enabled = true;
+    
+    emit!(DenominationConfigUpdated {
+        portfolio: denomination_config.portfolio,
+        price_feed,
+        oracle_source,
+        reference_price,
+        timestamp: current_time,
+    });
+    
+    msg!("Denomination config updated: feed={} ({:?}), reference_price={}",
+         price_feed, oracle_source, reference_price);
+    
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct SetEmergencyPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Full stop for every state-changing instruction. Deliberately skips the
+// emergency_pause check itself so the manager can always unpause.
+pub fn set_emergency_pause(
+    ctx: Context<SetEmergencyPause>,
+    paused: bool,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let previous = portfolio.emergency_pause;
+    portfolio.emergency_pause = paused;
+    
+    emit!(EmergencyPauseChanged {
+        portfolio: portfolio.key(),
+        previous,
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Emergency pause: {} -> {}", previous, paused);
+    
+    Ok(())
+}
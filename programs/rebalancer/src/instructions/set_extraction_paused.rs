@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct SetExtractionPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Halts capital movement (extraction/redistribution) during network instability
+// without the full emergency pause: performance updates and reads keep working.
+pub fn set_extraction_paused(
+    ctx: Context<SetExtractionPaused>,
+    paused: bool,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    portfolio.extraction_paused = paused;
+    
+    emit!(ExtractionPauseChanged {
+        portfolio: portfolio.key(),
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Extraction paused: {}", paused);
+    
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct SetRiskConfig<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = RiskConfig::MAX_SIZE,
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump
+    )]
+    pub risk_config: Account<'info, RiskConfig>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Create or replace the portfolio's stored risk limits and fee treasuries.
+// A config written under an older RiskLimits layout must run
+// migrate_risk_config first.
+pub fn set_risk_config(ctx: Context<SetRiskConfig>, limits: RiskLimits) -> Result<()> {
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    limits.validate()?;
+    
+    let risk_config = &mut ctx.accounts.risk_config;
+    risk_config.portfolio = ctx.accounts.portfolio.key();
+    risk_config.limits = limits;
+    risk_config.last_updated = current_time;
+    risk_config.bump = ctx.bumps.risk_config;
+    
+    emit!(RiskConfigUpdated {
+        portfolio: risk_config.portfolio,
+        platform_treasury: risk_config.limits.platform_treasury,
+        manager_treasury: risk_config.limits.manager_treasury,
+        platform_fee_bps: risk_config.limits.platform_fee_bps,
+        manager_fee_bps: risk_config.limits.manager_fee_bps,
+        timestamp: current_time,
+    });
+    
+    msg!("Risk config updated: max={}bps, min={}bps, platform_fee={}bps -> {}, manager_fee={}bps -> {}",
+         risk_config.limits.max_single_strategy_bps, risk_config.limits.min_single_strategy_bps,
+         risk_config.limits.platform_fee_bps, risk_config.limits.platform_treasury,
+         risk_config.limits.manager_fee_bps, risk_config.limits.manager_treasury);
+    
+    Ok(())
+}
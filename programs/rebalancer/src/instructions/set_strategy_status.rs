@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::reentry_cooldown::record_strategy_exit;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct SetStrategyStatus<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Paused is reversible; Deprecated is a one-way door toward extraction and
+// starts the re-entry cooldown on the strategy's protocol target
+pub fn set_strategy_status(
+    ctx: Context<SetStrategyStatus>,
+    strategy_id: Pubkey,
+    new_status: StrategyStatus,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    
+    let previous_status = strategy.status;
+    require!(previous_status.can_transition_to(new_status), ErrorCode::InvalidStatusTransition);
+    strategy.status = new_status;
+    
+    if new_status == StrategyStatus::Deprecated && previous_status != StrategyStatus::Deprecated {
+        record_strategy_exit(portfolio, strategy_id, strategy.protocol_type.target_id(), current_time);
+    }
+    
+    emit!(StrategyStatusChanged {
+        portfolio: portfolio.key(),
+        strategy_id,
+        previous_status,
+        new_status,
+        timestamp: current_time,
+    });
+    
+    msg!("Strategy status changed: strategy={}, {:?} -> {:?}", strategy_id, previous_status, new_status);
+    
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct SetWithdrawalLimit<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    pub manager: Signer<'info>,
+}
+
+pub fn set_withdrawal_limit(
+    ctx: Context<SetWithdrawalLimit>,
+    _strategy_id: Pubkey,
+    daily_withdrawal_limit_bps: u16,
+) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+    
+    Strategy::validate_withdrawal_limit(daily_withdrawal_limit_bps)?;
+    strategy.daily_withdrawal_limit_bps = daily_withdrawal_limit_bps;
+    
+    msg!("Daily withdrawal limit set: strategy={}, limit={}bps",
+         strategy.strategy_id, daily_withdrawal_limit_bps);
+    
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+use super::redistribute_capital::{execute_complete_rebalancing, RebalancingPlan, StrategyPerformanceData};
+
+// Return data is capped at 1024 bytes. Extraction targets and top performers
+// are disjoint subsets of the strategies passed, so with 20 strategies any
+// split of targets and allocations (plus 2 fees) serializes to under that
+pub const MAX_SIMULATED_STRATEGIES: usize = 20;
+
+#[derive(Accounts)]
+pub struct SimulateRebalance<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    // Without a stored config the plan is priced with default limits and no fees
+    #[account(
+        seeds = [b"risk_config", portfolio.key().as_ref()],
+        bump = risk_config.bump
+    )]
+    pub risk_config: Option<Account<'info, RiskConfig>>,
+}
+
+// READ-ONLY: builds the RebalancingPlan a keeper would execute over the
+// strategies passed via remaining_accounts, without touching any account
+pub fn simulate_rebalance<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SimulateRebalance<'info>>,
+) -> Result<RebalancingPlan> {
+    let portfolio = &ctx.accounts.portfolio;
+    let portfolio_key = portfolio.key();
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    require!(ctx.remaining_accounts.len() <= MAX_SIMULATED_STRATEGIES, ErrorCode::TooManyStrategies);
+    
+    let mut strategies: Vec<StrategyPerformanceData> = Vec::with_capacity(ctx.remaining_accounts.len());
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(!strategies.iter().any(|s| s.strategy_id == strategy.strategy_id), ErrorCode::DuplicateStrategy);
+        if strategy.status == StrategyStatus::Active {
+            strategies.push(StrategyPerformanceData::from_strategy(&strategy));
+        }
+    }
+    
+    let risk_limits = match ctx.accounts.risk_config.as_ref() {
+        Some(risk_config) => risk_config.limits.clone(),
+        None => RiskLimits::default().with_fees_waived(),
+    };
+    let risk_limits = if portfolio.free_rebalance_available(current_time) {
+        risk_limits.with_fees_waived()
+    } else {
+        risk_limits
+    };
+    
+    let plan = execute_complete_rebalancing(portfolio, &strategies, &risk_limits)?;
+    
+    msg!("Simulated rebalance: {} targets, {} lamports to extract, fees={}, improvement={}",
+         plan.extraction_targets.len(), plan.total_to_extract, plan.estimated_fees, plan.expected_improvement);
+    
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_largest_plan_fits_return_data() {
+        let allocation = CapitalAllocation {
+            strategy_id: Pubkey::new_unique(),
+            amount: u64::MAX,
+            allocation_type: AllocationType::TopPerformer,
+        };
+        for top_performers in 1..MAX_SIMULATED_STRATEGIES {
+            let plan = RebalancingPlan {
+                extraction_targets: vec![Pubkey::new_unique(); MAX_SIMULATED_STRATEGIES - top_performers],
+                total_to_extract: u64::MAX,
+                redistribution_plan: vec![allocation.clone(); top_performers + 2],
+                unallocated_dust: u64::MAX,
+                estimated_fees: u64::MAX,
+                expected_improvement: u64::MAX,
+            };
+            
+            assert!(plan.try_to_vec().unwrap().len() <= 1024);
+        }
+    }
+}
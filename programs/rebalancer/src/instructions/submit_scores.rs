@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct SubmitScores<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        init_if_needed,
+        payer = manager,
+        space = RankingBuffer::MAX_SIZE,
+        seeds = [b"ranking_buffer", portfolio.key().as_ref()],
+        bump
+    )]
+    pub ranking_buffer: Account<'info, RankingBuffer>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// TWO-PHASE RANKING: PHASE 1
+// Records the scores of the strategies passed via remaining_accounts into the
+// ranking buffer. May be called across many transactions until every registered
+// strategy has been submitted.
+pub fn submit_scores<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SubmitScores<'info>>,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let ranking_buffer = &mut ctx.accounts.ranking_buffer;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InsufficientStrategies);
+    
+    // START A NEW CYCLE WHEN THE BUFFER IS FRESH OR THE LAST CYCLE COMPLETED
+    if ranking_buffer.entries.is_empty() || ranking_buffer.is_complete() {
+        ranking_buffer.portfolio = portfolio.key();
+        ranking_buffer.bump = ctx.bumps.ranking_buffer;
+        ranking_buffer.cycle_started = current_time;
+        ranking_buffer.sorted = false;
+        ranking_buffer.written_back = 0;
+        ranking_buffer.entries.clear();
+    }
+    
+    require!(!ranking_buffer.sorted, ErrorCode::RankingInProgress);
+    
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio.key())?;
+        
+        require!(
+            !ranking_buffer.entries.iter().any(|e| e.strategy_id == strategy.strategy_id),
+            ErrorCode::DuplicateStrategy
+        );
+        require!(
+            ranking_buffer.entries.len() < RankingBuffer::MAX_ENTRIES,
+            ErrorCode::RankingBufferFull
+        );
+        
+        ranking_buffer.entries.push(RankingEntry {
+            strategy_id: strategy.strategy_id,
+            performance_score: portfolio.ranking_score(&strategy, current_time),
+            weight: strategy.current_balance,
+            tie_key: portfolio.tie_break.key(&strategy),
+            percentile: 0,
+            written_back: false,
+        });
+    }
+    
+    msg!("Scores submitted: {} buffered of {} strategies",
+         ranking_buffer.entries.len(), portfolio.total_strategies);
+    
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
+use crate::events::*;
 use crate::error::ErrorCode;
 
 #[derive(Accounts)]
@@ -7,8 +8,9 @@ use crate::error::ErrorCode;
 pub struct UpdatePerformance<'info> {
     #[account(
         mut,
-        seeds = [b"portfolio", portfolio.manager.as_ref()],
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
         bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
         has_one = manager @ ErrorCode::UnauthorizedManager
     )]
     pub portfolio: Account<'info, Portfolio>,
@@ -17,10 +19,20 @@ pub struct UpdatePerformance<'info> {
         mut,
         seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
         bump = strategy.bump,
-        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
     )]
     pub strategy: Account<'info, Strategy>,
     
+    #[account(
+        seeds = [b"denomination", portfolio.key().as_ref()],
+        bump = denomination_config.bump
+    )]
+    pub denomination_config: Option<Account<'info, DenominationConfig>>,
+    
+    /// CHECK: Feed named by denomination_config, validated in DenominationConfig::read_price
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
     #[account(mut)]
     pub manager: Signer<'info>,
 }
@@ -31,7 +43,10 @@ pub fn update_performance(
     yield_rate: u64,
     volatility_score: u32,
     current_balance: u64,
+    rate_type: RateType,
+    allow_yield_jump: bool,
 ) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
     let strategy = &mut ctx.accounts.strategy;
     let current_time = Clock::get()?.unix_timestamp;
     
@@ -40,19 +55,101 @@ pub fn update_performance(
     Strategy::validate_volatility_score(volatility_score)?;
     Strategy::validate_balance_update(current_balance)?;
     require!(strategy.status == StrategyStatus::Active, ErrorCode::StrategyNotFound);
+    portfolio.validate_leverage_policy(&strategy.protocol_type)?;
+    
+    // PERFORMANCE FEE ACCRUAL ON GAINS ABOVE THE HIGH-WATER MARK
+    // Gains reported before the eligibility window still advance the mark,
+    // so a young strategy's early gains never earn fees
+    strategy.current_balance = current_balance;
+    let fee = strategy.fee_eligible_gain(portfolio.performance_fee_bps);
+    if fee > 0 && strategy.is_fee_eligible(current_time, portfolio.fee_eligibility_seconds) {
+        portfolio.accrued_performance_fees = Portfolio::checked_fee_accrual(
+            portfolio.accrued_performance_fees, fee, portfolio.max_uncollected_fees
+        )?;
+        
+        if Portfolio::fee_collection_due(portfolio.accrued_performance_fees, portfolio.max_uncollected_fees) {
+            emit!(FeeCollectionDue {
+                portfolio: portfolio.key(),
+                accrued_fees: portfolio.accrued_performance_fees,
+                max_uncollected_fees: portfolio.max_uncollected_fees,
+                timestamp: current_time,
+            });
+        }
+    }
+    strategy.advance_high_water_mark();
+    strategy.track_drawdown();
+    
+    // NORMALIZE TO APY SO PROTOCOLS WITH DIFFERENT COMPOUNDING COMPARE FAIRLY
+    let yield_rate = normalize_to_apy(yield_rate, rate_type, strategy.compound_frequency_seconds)?;
+    
+    // YIELD ANOMALY CHECK
+    // An implausible jump usually means a bad feed; the manager can override
+    // for genuine regime changes
+    require!(
+        allow_yield_jump || !strategy.yield_jump_suspicious(yield_rate, portfolio.max_yield_delta_bps),
+        ErrorCode::SuspiciousYieldUpdate
+    );
+    
+    // VOLATILITY CIRCUIT BREAKER
+    // A sudden spike usually means trouble at the protocol; pause instead of
+    // failing so the new metrics are still recorded for the manager to review
+    if strategy.volatility_circuit_tripped(volatility_score, portfolio.volatility_circuit_bps) {
+        strategy.status = StrategyStatus::Paused;
+        emit!(CircuitBreakerTripped {
+            portfolio: portfolio.key(),
+            strategy_id: strategy.strategy_id,
+            previous_volatility: strategy.volatility_score,
+            new_volatility: volatility_score,
+            circuit_bps: portfolio.volatility_circuit_bps,
+            timestamp: current_time,
+        });
+        msg!("Circuit breaker tripped: strategy {} volatility {} -> {}, paused",
+             strategy.strategy_id, strategy.volatility_score, volatility_score);
+    }
     
     // UPDATE STRATEGY METRICS
     strategy.yield_rate = yield_rate;
     strategy.volatility_score = volatility_score;
-    strategy.current_balance = current_balance;
     strategy.last_updated = current_time;
     
     // CALCULATE PERFORMANCE SCORE WITH WEIGHTED FORMULA
-    strategy.performance_score = calculate_performance_score(
+    // Capital still inside its settlement delay is not yet earning, so it is left out.
+    // Stable-denominated portfolios score balances by value; the stored balance stays in lamports.
+    // Once enabled the config can't be left out to score this strategy in lamports instead.
+    let settled_balance = strategy.settled_balance(current_balance, current_time);
+    let scoring_balance = match &ctx.accounts.denomination_config {
+        Some(denomination) => {
+            let feed = ctx.accounts.price_feed.as_ref().ok_or(ErrorCode::OracleFeedRequired)?;
+            let price = denomination.read_price(feed, current_time)?;
+            denomination.scoring_balance(settled_balance, price)
+        }
+        None => {
+            require!(!portfolio.denomination_enabled, ErrorCode::DenominationConfigRequired);
+            settled_balance
+        }
+    };
+    let raw_score = calculate_performance_score(
         yield_rate,
-        current_balance,
+        scoring_balance,
         volatility_score,
+        portfolio.scoring_weights(),
     )?;
+    strategy.performance_score = apply_longevity_bonus(
+        raw_score,
+        current_time.saturating_sub(strategy.creation_time),
+        portfolio.longevity_bonus_bps_per_day,
+        portfolio.max_longevity_bonus_bps,
+    );
+    
+    emit!(PerformanceUpdated {
+        portfolio: portfolio.key(),
+        strategy_id: strategy.strategy_id,
+        yield_rate,
+        volatility_score,
+        balance: current_balance,
+        performance_score: strategy.performance_score,
+        timestamp: current_time,
+    });
     
     msg!("Performance updated: strategy={}, yield={}bps, volatility={}, balance={}, score={}", 
          strategy.strategy_id, yield_rate, volatility_score, current_balance, strategy.performance_score);
@@ -65,6 +162,7 @@ pub fn calculate_performance_score(
     yield_rate: u64,      // Annual yield in basis points (0-50000)
     balance: u64,         // Current capital allocated in lamports
     volatility: u32,      // Risk score 0-10000 (100.00% max)
+    weights: ScoringWeights, // Basis-point split, sums to 10000
 ) -> Result<u64> {
     // NORMALIZATION TO 0-10000 SCALE FOR EACH METRIC
     
@@ -91,26 +189,21 @@ pub fn calculate_performance_score(
             .ok_or(ErrorCode::BalanceOverflow)?;
         (numerator / 100_000_000u128) as u64
     } else {
-        // FIXED-POINT LOGARITHMIC APPROXIMATION (avoiding f64)
-        // Using integer-only log approximation: log(x) ≈ (x-1)/x scaling
-        let balance_scaled = balance / 100_000_000u64; // Scale to SOL units
-        let log_approx = if balance_scaled <= 1 {
-            0u64
-        } else {
-            // Integer log approximation: more accurate than floating point
-            // Use bit position as log base 2, then scale
-            let bit_pos = 64 - balance_scaled.leading_zeros() as u64;
-            let log_scaled = bit_pos.saturating_sub(1) * 1443; // * ln(2) * 1000 ≈ 693 * 2
-            log_scaled.min(10000)
-        };
-        log_approx
+        // FIXED-POINT LOGARITHMIC SCALING (avoiding f64)
+        // 1000 at 0.1 SOL rising to 10000 at 100 SOL in proportion to log2(balance),
+        // continuing the linear segment below 0.1 SOL without a step
+        let log_floor = fixed_log2(100_000_000u64);
+        let log_range = fixed_log2(100_000_000_000u64) - log_floor;
+        let log_offset = fixed_log2(balance) - log_floor;
+        1000 + (log_offset as u128 * 9000u128 / log_range as u128) as u64
     };
     
     // Normalize inverse volatility: 0-10000 volatility -> 10000-0 inverse scale
     let normalized_inverse_volatility = 10000u32.saturating_sub(volatility.min(10000)) as u64;
     
     // PRECISION-SAFE WEIGHTED COMPOSITE CALCULATION
-    // Yield(45%) + Balance(35%) + InverseVolatility(20%) = 100%
+    // Yield + Balance + InverseVolatility weights = 100%
+    weights.validate()?;
     
     // Validate normalized values are within expected bounds
     require!(normalized_yield <= 10000, ErrorCode::BalanceOverflow);
@@ -119,19 +212,19 @@ pub fn calculate_performance_score(
     
     // Use 128-bit intermediate calculations with rounding
     let yield_component = {
-        let intermediate = (normalized_yield as u128 * 4500u128).checked_add(5000u128)
+        let intermediate = (normalized_yield as u128 * weights.yield_bps as u128).checked_add(5000u128)
             .ok_or(ErrorCode::BalanceOverflow)?;
         (intermediate / 10000u128) as u64
     };
     
     let balance_component = {
-        let intermediate = (normalized_balance as u128 * 3500u128).checked_add(5000u128)
+        let intermediate = (normalized_balance as u128 * weights.balance_bps as u128).checked_add(5000u128)
             .ok_or(ErrorCode::BalanceOverflow)?;
         (intermediate / 10000u128) as u64
     };
     
     let volatility_component = {
-        let intermediate = (normalized_inverse_volatility as u128 * 2000u128).checked_add(5000u128)
+        let intermediate = (normalized_inverse_volatility as u128 * weights.volatility_bps as u128).checked_add(5000u128)
             .ok_or(ErrorCode::BalanceOverflow)?;
         (intermediate / 10000u128) as u64
     };
@@ -149,6 +242,95 @@ pub fn calculate_performance_score(
     Ok(performance_score)
 }
 
+// FIXED-POINT LOG2
+// Integer part from the leading bit, then LOG2_FRACTION_BITS of mantissa by
+// repeated squaring. Returns log2(x) * 2^LOG2_FRACTION_BITS; monotonic in x.
+const LOG2_FRACTION_BITS: u32 = 16;
+
+pub fn fixed_log2(x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+    
+    let integer_part = 63 - x.leading_zeros();
+    // Mantissa in Q32: [1.0, 2.0)
+    let mut mantissa = ((x as u128) << 32) >> integer_part;
+    let mut fraction = 0u64;
+    for bit in (0..LOG2_FRACTION_BITS).rev() {
+        mantissa = (mantissa * mantissa) >> 32;
+        if mantissa >= 2u128 << 32 {
+            mantissa >>= 1;
+            fraction |= 1 << bit;
+        }
+    }
+    
+    ((integer_part as u64) << LOG2_FRACTION_BITS) | fraction
+}
+
+// APR -> APY CONVERSION
+// APY = (1 + APR / n)^n - 1, with n = compounding periods per year derived from
+// the strategy's compound frequency. Computed in 1e12 fixed point by squaring.
+pub const RATE_PRECISION: u128 = 1_000_000_000_000;
+const SECONDS_PER_YEAR: i64 = 31_536_000;
+
+pub fn compounding_periods_per_year(compound_frequency_seconds: i64) -> u64 {
+    (SECONDS_PER_YEAR / compound_frequency_seconds.max(1)).max(1) as u64
+}
+
+// (1 + period_rate)^periods in RATE_PRECISION fixed point
+pub fn compound_factor(period_rate: u128, periods: u64) -> Result<u128> {
+    let mut base = RATE_PRECISION + period_rate;
+    let mut factor = RATE_PRECISION;
+    let mut exponent = periods;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            factor = factor
+                .checked_mul(base)
+                .ok_or(ErrorCode::BalanceOverflow)?
+                / RATE_PRECISION;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base.checked_mul(base).ok_or(ErrorCode::BalanceOverflow)? / RATE_PRECISION;
+        }
+    }
+    Ok(factor)
+}
+
+pub fn normalize_to_apy(
+    yield_rate: u64,                // Annual rate in basis points
+    rate_type: RateType,
+    compound_frequency_seconds: i64,
+) -> Result<u64> {
+    if rate_type == RateType::Apy || yield_rate == 0 {
+        return Ok(yield_rate);
+    }
+    
+    let periods = compounding_periods_per_year(compound_frequency_seconds);
+    let period_rate = yield_rate as u128 * RATE_PRECISION / (10000u128 * periods as u128);
+    let factor = compound_factor(period_rate, periods)?;
+    
+    let apy_bps = (factor - RATE_PRECISION) * 10000 / RATE_PRECISION;
+    u64::try_from(apy_bps).map_err(|_| ErrorCode::BalanceOverflow.into())
+}
+
+// LONGEVITY BONUS
+// Seasoned strategies earn bps_per_day score points per full day since creation,
+// capped at max_bonus_bps and at the 10000 score ceiling. Disabled when the cap is 0.
+pub fn apply_longevity_bonus(
+    score: u64,
+    age_seconds: i64,
+    bps_per_day: u16,
+    max_bonus_bps: u16,
+) -> u64 {
+    let age_days = (age_seconds.max(0) / 86_400) as u64;
+    let bonus = age_days
+        .saturating_mul(bps_per_day as u64)
+        .min(max_bonus_bps as u64);
+    
+    score.saturating_add(bonus).min(10000)
+}
+
 // PRECISION VALIDATION HELPER
 pub fn validate_calculation_precision(
     yield_rate: u64,
@@ -157,7 +339,7 @@ pub fn validate_calculation_precision(
     expected_min: u64,
     expected_max: u64,
 ) -> Result<()> {
-    let score = calculate_performance_score(yield_rate, balance, volatility)?;
+    let score = calculate_performance_score(yield_rate, balance, volatility, ScoringWeights::LEGACY)?;
     require!(score >= expected_min && score <= expected_max, ErrorCode::BalanceOverflow);
     Ok(())
 }
@@ -173,6 +355,7 @@ mod tests {
             20000,        // 200% yield
             50_000_000_000, // 50 SOL
             1000,         // 10% volatility
+            ScoringWeights::LEGACY,
         ).unwrap();
         
         // Test case 2: Low yield, low balance, high volatility (worst case)
@@ -180,6 +363,7 @@ mod tests {
             500,          // 5% yield
             100_000_000,  // 0.1 SOL
             9000,         // 90% volatility
+            ScoringWeights::LEGACY,
         ).unwrap();
         
         // Score1 should be significantly higher than Score2
@@ -191,15 +375,147 @@ mod tests {
     #[test]
     fn test_edge_cases() {
         // Zero balance
-        let score_zero = calculate_performance_score(10000, 0, 5000).unwrap();
+        let score_zero = calculate_performance_score(10000, 0, 5000, ScoringWeights::LEGACY).unwrap();
         assert_eq!(score_zero, 5000); // Should only get yield + volatility components
         
         // Maximum values
-        let score_max = calculate_performance_score(50000, 100_000_000_000, 0).unwrap();
+        let score_max = calculate_performance_score(50000, 100_000_000_000, 0, ScoringWeights::LEGACY).unwrap();
         assert_eq!(score_max, 10000); // Perfect score
         
         // Minimum values  
-        let score_min = calculate_performance_score(0, 100_000_000, 10000).unwrap();
+        let score_min = calculate_performance_score(0, 100_000_000, 10000, ScoringWeights::LEGACY).unwrap();
         assert!(score_min < 5000); // Low score as expected
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn test_longevity_bonus_favors_older_strategy() {
+        let raw_score = calculate_performance_score(1200, 5_000_000_000, 2500, ScoringWeights::LEGACY).unwrap();
+        let year_old = 365 * 86_400;
+        let week_old = 7 * 86_400;
+        
+        // Disabled by default: identical metrics score identically regardless of age
+        assert_eq!(apply_longevity_bonus(raw_score, year_old, 0, 0), raw_score);
+        assert_eq!(apply_longevity_bonus(raw_score, year_old, 2, 0), raw_score);
+        
+        // Enabled at 2 bps/day capped at 300 bps: the older strategy outranks the newer
+        let older = apply_longevity_bonus(raw_score, year_old, 2, 300);
+        let newer = apply_longevity_bonus(raw_score, week_old, 2, 300);
+        assert!(older > newer);
+        assert_eq!(older, raw_score + 300);
+        assert_eq!(newer, raw_score + 14);
+        
+        // Never pushes a score beyond the 10000 ceiling
+        assert_eq!(apply_longevity_bonus(9900, year_old, 2, 300), 10000);
+    }
+    
+    #[test]
+    fn test_apr_and_apy_normalization() {
+        let lending = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        let staking = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        
+        // 10% APR compounded hourly is ~10.52% APY; 10% APY is taken as is
+        let lending_apy = normalize_to_apy(1000, RateType::Apr, lending.default_compound_frequency()).unwrap();
+        let staking_apy = normalize_to_apy(1000, RateType::Apy, staking.default_compound_frequency()).unwrap();
+        assert!((1050..=1052).contains(&lending_apy));
+        assert_eq!(staking_apy, 1000);
+        
+        // Compounding less often yields less
+        let epoch_apy = normalize_to_apy(1000, RateType::Apr, staking.default_compound_frequency()).unwrap();
+        assert!(epoch_apy > 1000 && epoch_apy <= lending_apy);
+        
+        let lending_score = calculate_performance_score(lending_apy, 1_000_000_000, 3000, ScoringWeights::LEGACY).unwrap();
+        let staking_score = calculate_performance_score(staking_apy, 1_000_000_000, 3000, ScoringWeights::LEGACY).unwrap();
+        assert!(lending_score > staking_score);
+    }
+
+    
+    #[test]
+    fn test_fee_accrual_blocked_at_uncollected_cap() {
+        let cap = 1_000_000_000;
+        
+        // Accrue up to the cap; the nudge fires from 90% onwards
+        let accrued = Portfolio::checked_fee_accrual(0, 800_000_000, cap).unwrap();
+        assert!(!Portfolio::fee_collection_due(accrued, cap));
+        let accrued = Portfolio::checked_fee_accrual(accrued, 200_000_000, cap).unwrap();
+        assert_eq!(accrued, cap);
+        assert!(Portfolio::fee_collection_due(accrued, cap));
+        
+        // Any further accrual is refused until collection resets the ledger
+        assert_eq!(
+            Portfolio::checked_fee_accrual(accrued, 1, cap).unwrap_err(),
+            ErrorCode::UncollectedFeeCapReached.into()
+        );
+        assert_eq!(Portfolio::checked_fee_accrual(0, 1, cap).unwrap(), 1);
+        
+        // A zero cap leaves accrual unbounded
+        assert!(Portfolio::checked_fee_accrual(accrued, 1, 0).is_ok());
+        assert!(!Portfolio::fee_collection_due(accrued, 0));
+    }
+    
+    #[test]
+    fn test_configurable_scoring_weights() {
+        // High yield but volatile vs. modest yield but steady
+        let volatile = (30000, 5_000_000_000, 8000);
+        let steady = (1500, 5_000_000_000, 500);
+        let score = |(y, b, v): (u64, u64, u32), w| calculate_performance_score(y, b, v, w).unwrap();
+        
+        assert!(score(volatile, ScoringWeights::LEGACY) > score(steady, ScoringWeights::LEGACY));
+        
+        // A risk-averse split flips the ranking
+        let risk_averse = ScoringWeights { yield_bps: 2000, balance_bps: 2000, volatility_bps: 6000 };
+        assert!(score(steady, risk_averse) > score(volatile, risk_averse));
+        
+        // Weights must sum to 10000
+        let invalid = ScoringWeights { yield_bps: 5000, balance_bps: 5000, volatility_bps: 1000 };
+        assert_eq!(
+            calculate_performance_score(1500, 5_000_000_000, 500, invalid).unwrap_err(),
+            ErrorCode::InvalidScoringWeights.into()
+        );
+    }
+    
+    #[test]
+    fn test_fixed_log2_accuracy() {
+        assert_eq!(fixed_log2(1), 0);
+        assert_eq!(fixed_log2(1024), 10 << LOG2_FRACTION_BITS);
+        
+        // log2(3) = 1.58496..., within one fractional unit
+        let expected = (1.584_962_5 * 65536.0) as u64;
+        assert!(fixed_log2(3).abs_diff(expected) <= 1);
+    }
+    
+    #[test]
+    fn test_balance_normalization_is_smooth() {
+        let normalized = |balance: u64| {
+            // Balance-only weights expose the normalized balance directly
+            let weights = ScoringWeights { yield_bps: 0, balance_bps: 10000, volatility_bps: 0 };
+            calculate_performance_score(0, balance, 10000, weights).unwrap()
+        };
+        
+        // Every 10% increase from 0.01 SOL to past the 100 SOL cap never decreases
+        // the value and never jumps by more than 150 points
+        let mut balance = 10_000_000u64;
+        let mut previous = normalized(balance);
+        while balance < 200_000_000_000 {
+            balance += balance / 10;
+            let current = normalized(balance);
+            assert!(current >= previous, "decrease at {}", balance);
+            assert!(current - previous <= 150, "jump of {} at {}", current - previous, balance);
+            previous = current;
+        }
+        
+        // No power-of-two cliff between 1.9 and 2.1 SOL
+        assert!(normalized(2_100_000_000) - normalized(1_900_000_000) <= 150);
+        assert_eq!(normalized(100_000_000), 1000);
+        assert_eq!(normalized(100_000_000_000), 10000);
+    }
+
+}
@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct UpdatePortfolioConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Optional portfolio settings; fields left as None are unchanged. The update
+// is echoed in PortfolioConfigUpdated, so the event carries only what changed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct PortfolioConfigUpdate {
+    pub max_acceptable_il_bps: Option<u16>,
+    pub fee_eligibility_seconds: Option<i64>,
+    pub freshness_window_seconds: Option<i64>,
+    pub entry_price_policy: Option<EntryPricePolicy>,
+    pub entry_price_tolerance_bps: Option<u16>,
+    pub min_improvement_ratio_bps: Option<u16>,
+    pub max_portfolio_leverage: Option<u8>,
+    pub longevity_bonus_bps_per_day: Option<u16>,
+    pub max_longevity_bonus_bps: Option<u16>,
+    pub fee_treasury: Option<Pubkey>,
+    pub fee_token_mint: Option<Pubkey>, // Pubkey::default() settles fees in SOL
+    pub reentry_cooldown_seconds: Option<i64>,
+    pub min_dispersion_threshold: Option<u16>,
+    pub max_positions_per_strategy: Option<u8>,
+    pub free_rebalances_per_period: Option<u8>,
+    pub free_rebalance_period_seconds: Option<i64>,
+    pub settlement_delay_seconds: Option<[u32; 3]>, // Indexed by ProtocolType::category_index
+    pub max_extraction_fee_bps: Option<u16>,
+    pub max_uncollected_fees: Option<u64>,  // 0 = unlimited
+    pub scoring_weights: Option<ScoringWeights>, // Must sum to 10000
+    pub staleness_halflife: Option<i64>,    // 0 = no decay
+    pub min_strategies_for_rebalance: Option<u8>, // At least 2
+    pub strategy_cooldown: Option<i64>,     // 0 = no per-strategy cooldown
+    pub volatility_circuit_bps: Option<u16>, // 0 = never auto-pause on volatility spikes
+    pub max_yield_delta_bps: Option<u16>,   // 0 = no yield anomaly check
+    pub rent_buffer_lamports: Option<u64>,  // At least a CapitalPosition's rent-exempt minimum
+    pub tvl_snapshot_interval: Option<i64>, // 1 minute to 30 days
+    pub reconcile_tolerance_bps: Option<u16>,
+}
+
+pub fn update_portfolio_config(
+    ctx: Context<UpdatePortfolioConfig>,
+    config: PortfolioConfigUpdate,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    
+    if let Some(max_acceptable_il_bps) = config.max_acceptable_il_bps {
+        Portfolio::validate_il_threshold(max_acceptable_il_bps)?;
+        portfolio.max_acceptable_il_bps = max_acceptable_il_bps;
+    }
+    
+    if let Some(fee_eligibility_seconds) = config.fee_eligibility_seconds {
+        Portfolio::validate_fee_eligibility_seconds(fee_eligibility_seconds)?;
+        portfolio.fee_eligibility_seconds = fee_eligibility_seconds;
+    }
+    
+    if let Some(freshness_window_seconds) = config.freshness_window_seconds {
+        Portfolio::validate_freshness_window(freshness_window_seconds)?;
+        portfolio.freshness_window_seconds = freshness_window_seconds;
+    }
+    
+    if let Some(entry_price_policy) = config.entry_price_policy {
+        portfolio.entry_price_policy = entry_price_policy;
+    }
+    
+    if let Some(entry_price_tolerance_bps) = config.entry_price_tolerance_bps {
+        Portfolio::validate_entry_price_tolerance(entry_price_tolerance_bps)?;
+        portfolio.entry_price_tolerance_bps = entry_price_tolerance_bps;
+    }
+    
+    if let Some(min_improvement_ratio_bps) = config.min_improvement_ratio_bps {
+        Portfolio::validate_improvement_ratio(min_improvement_ratio_bps)?;
+        portfolio.min_improvement_ratio_bps = min_improvement_ratio_bps;
+    }
+    
+    if let Some(max_portfolio_leverage) = config.max_portfolio_leverage {
+        Portfolio::validate_max_leverage(max_portfolio_leverage)?;
+        portfolio.max_portfolio_leverage = max_portfolio_leverage;
+    }
+    
+    if config.longevity_bonus_bps_per_day.is_some() || config.max_longevity_bonus_bps.is_some() {
+        let bps_per_day = config.longevity_bonus_bps_per_day.unwrap_or(portfolio.longevity_bonus_bps_per_day);
+        let max_bonus_bps = config.max_longevity_bonus_bps.unwrap_or(portfolio.max_longevity_bonus_bps);
+        Portfolio::validate_longevity_bonus(bps_per_day, max_bonus_bps)?;
+        portfolio.longevity_bonus_bps_per_day = bps_per_day;
+        portfolio.max_longevity_bonus_bps = max_bonus_bps;
+    }
+    
+    if let Some(fee_treasury) = config.fee_treasury {
+        portfolio.fee_treasury = fee_treasury;
+    }
+    
+    if let Some(fee_token_mint) = config.fee_token_mint {
+        portfolio.fee_token_mint = fee_token_mint;
+    }
+    
+    if let Some(reentry_cooldown_seconds) = config.reentry_cooldown_seconds {
+        Portfolio::validate_reentry_cooldown(reentry_cooldown_seconds)?;
+        portfolio.reentry_cooldown_seconds = reentry_cooldown_seconds;
+    }
+    
+    if let Some(min_dispersion_threshold) = config.min_dispersion_threshold {
+        Portfolio::validate_dispersion_threshold(min_dispersion_threshold)?;
+        portfolio.min_dispersion_threshold = min_dispersion_threshold;
+    }
+    
+    if let Some(max_positions_per_strategy) = config.max_positions_per_strategy {
+        Portfolio::validate_max_positions(max_positions_per_strategy)?;
+        portfolio.max_positions_per_strategy = max_positions_per_strategy;
+    }
+    
+    if config.free_rebalances_per_period.is_some() || config.free_rebalance_period_seconds.is_some() {
+        let per_period = config.free_rebalances_per_period.unwrap_or(portfolio.free_rebalances_per_period);
+        let period_seconds = config.free_rebalance_period_seconds.unwrap_or(portfolio.free_rebalance_period_seconds);
+        Portfolio::validate_free_rebalance_allowance(per_period, period_seconds)?;
+        portfolio.free_rebalances_per_period = per_period;
+        portfolio.free_rebalance_period_seconds = period_seconds;
+    }
+    
+    if let Some(settlement_delay_seconds) = config.settlement_delay_seconds {
+        Portfolio::validate_settlement_delays(&settlement_delay_seconds)?;
+        portfolio.settlement_delay_seconds = settlement_delay_seconds;
+    }
+    
+    if let Some(max_extraction_fee_bps) = config.max_extraction_fee_bps {
+        Portfolio::validate_max_extraction_fee(max_extraction_fee_bps)?;
+        portfolio.max_extraction_fee_bps = max_extraction_fee_bps;
+    }
+    
+    if let Some(max_uncollected_fees) = config.max_uncollected_fees {
+        portfolio.max_uncollected_fees = max_uncollected_fees;
+    }
+    
+    if let Some(scoring_weights) = config.scoring_weights {
+        portfolio.set_scoring_weights(scoring_weights)?;
+    }
+    
+    if let Some(staleness_halflife) = config.staleness_halflife {
+        Portfolio::validate_staleness_halflife(staleness_halflife)?;
+        portfolio.staleness_halflife = staleness_halflife;
+    }
+    
+    if let Some(min_strategies_for_rebalance) = config.min_strategies_for_rebalance {
+        Portfolio::validate_min_strategies_for_rebalance(min_strategies_for_rebalance)?;
+        portfolio.min_strategies_for_rebalance = min_strategies_for_rebalance;
+    }
+    
+    if let Some(strategy_cooldown) = config.strategy_cooldown {
+        Portfolio::validate_strategy_cooldown(strategy_cooldown)?;
+        portfolio.strategy_cooldown = strategy_cooldown;
+    }
+    
+    if let Some(volatility_circuit_bps) = config.volatility_circuit_bps {
+        Portfolio::validate_volatility_circuit(volatility_circuit_bps)?;
+        portfolio.volatility_circuit_bps = volatility_circuit_bps;
+    }
+    
+    if let Some(max_yield_delta_bps) = config.max_yield_delta_bps {
+        Portfolio::validate_max_yield_delta(max_yield_delta_bps)?;
+        portfolio.max_yield_delta_bps = max_yield_delta_bps;
+    }
+    
+    if let Some(rent_buffer_lamports) = config.rent_buffer_lamports {
+        Portfolio::validate_rent_buffer(rent_buffer_lamports, Portfolio::rent_exempt_floor(&Rent::get()?))?;
+        portfolio.rent_buffer_lamports = rent_buffer_lamports;
+    }
+    
+    if let Some(tvl_snapshot_interval) = config.tvl_snapshot_interval {
+        Portfolio::validate_tvl_snapshot_interval(tvl_snapshot_interval)?;
+        portfolio.tvl_snapshot_interval = tvl_snapshot_interval;
+    }
+    
+    if let Some(reconcile_tolerance_bps) = config.reconcile_tolerance_bps {
+        Portfolio::validate_reconcile_tolerance(reconcile_tolerance_bps)?;
+        portfolio.reconcile_tolerance_bps = reconcile_tolerance_bps;
+    }
+    
+    emit!(PortfolioConfigUpdated {
+        portfolio: portfolio.key(),
+        update: config,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Portfolio config updated");
+    
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+pub struct UpdateRebalanceConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+// Adjusts the settings fixed at initialize_portfolio; None leaves a field untouched
+pub fn update_rebalance_config(
+    ctx: Context<UpdateRebalanceConfig>,
+    new_threshold: Option<u8>,
+    new_interval: Option<i64>,
+    new_ranking_mode: Option<RankingMode>,
+    new_tie_break: Option<TieBreak>,
+) -> Result<()> {
+    let portfolio = &mut ctx.accounts.portfolio;
+    let old_threshold = portfolio.rebalance_threshold;
+    let old_interval = portfolio.min_rebalance_interval;
+    let old_ranking_mode = portfolio.ranking_mode;
+    let old_tie_break = portfolio.tie_break;
+    
+    if let Some(threshold) = new_threshold {
+        Portfolio::validate_rebalance_threshold(threshold)?;
+        portfolio.rebalance_threshold = threshold;
+    }
+    
+    if let Some(interval) = new_interval {
+        Portfolio::validate_min_interval(interval)?;
+        portfolio.min_rebalance_interval = interval;
+    }
+    
+    if let Some(ranking_mode) = new_ranking_mode {
+        portfolio.ranking_mode = ranking_mode;
+    }
+    
+    if let Some(tie_break) = new_tie_break {
+        portfolio.tie_break = tie_break;
+    }
+    
+    emit!(RebalanceConfigUpdated {
+        portfolio: portfolio.key(),
+        old_threshold,
+        new_threshold: portfolio.rebalance_threshold,
+        old_interval,
+        new_interval: portfolio.min_rebalance_interval,
+        old_ranking_mode,
+        new_ranking_mode: portfolio.ranking_mode,
+        old_tie_break,
+        new_tie_break: portfolio.tie_break,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Rebalance config updated: threshold {}% -> {}%, interval {}s -> {}s, ranking {:?} -> {:?}, tie-break {:?} -> {:?}",
+         old_threshold, portfolio.rebalance_threshold, old_interval, portfolio.min_rebalance_interval,
+         old_ranking_mode, portfolio.ranking_mode, old_tie_break, portfolio.tie_break);
+    
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use std::collections::HashSet;
+
+#[derive(Accounts)]
+pub struct VerifyStrategyCount<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    pub manager: Signer<'info>,
+}
+
+// STRATEGY COUNTER AUDIT
+// Every registered strategy of the portfolio must be passed via remaining_accounts.
+// On mismatch the counter is corrected when `repair` is set, otherwise the call fails.
+// Repair only ever raises the counter: the strategies passed prove a lower bound,
+// while leaving some out would let the count undercut max_strategies and the
+// ranking cycle's completeness check.
+pub fn verify_strategy_count<'info>(
+    ctx: Context<'_, '_, 'info, 'info, VerifyStrategyCount<'info>>,
+    repair: bool,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let mut seen = HashSet::new();
+    
+    for account_info in ctx.remaining_accounts.iter() {
+        let strategy = Strategy::load_checked(account_info, &portfolio_key)?;
+        require!(seen.insert(strategy.strategy_id), ErrorCode::DuplicateStrategy);
+    }
+    
+    let actual_count = u32::try_from(seen.len()).map_err(|_| ErrorCode::BalanceOverflow)?;
+    let portfolio = &mut ctx.accounts.portfolio;
+    let recorded_count = portfolio.total_strategies;
+    
+    if recorded_count == actual_count {
+        msg!("Strategy count verified: {}", actual_count);
+        return Ok(());
+    }
+    
+    require!(repair, ErrorCode::StrategyCountMismatch);
+    require!(actual_count > recorded_count, ErrorCode::StrategyCountCannotDecrease);
+    
+    portfolio.total_strategies = actual_count;
+    
+    emit!(StrategyCountRepaired {
+        portfolio: portfolio_key,
+        recorded_count,
+        actual_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Strategy count repaired: recorded={}, actual={}", recorded_count, actual_count);
+    
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+use super::extract_capital::{extract_from_protocol, load_protocol_exits, ExtractionResult, ProtocolExits};
+use super::reentry_cooldown::record_strategy_exit;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey, destination: Pubkey)]
+pub struct WindDownStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    /// CHECK: only receives extracted capital; owns the destination token accounts
+    #[account(mut, address = destination)]
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    
+    // Required for any real protocol exit (every strategy except simulated staking)
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// STRATEGY WIND-DOWN
+// Deprecates the strategy and force-exits its position to `destination`,
+// leaving a zero balance behind, ready for close_strategy. Calling it again
+// once wound down is a no-op.
+// remaining_accounts: the (Strategy, CapitalPosition) pair, then the protocol
+// accounts extract_capital takes for that strategy, with the destination
+// token accounts owned by `destination` instead of the manager.
+pub fn wind_down_strategy<'info>(
+    ctx: Context<'_, '_, 'info, 'info, WindDownStrategy<'info>>,
+    strategy_id: Pubkey,
+    destination: Pubkey,
+    max_slippage_bps: u16,
+) -> Result<()> {
+    let portfolio_key = ctx.accounts.portfolio.key();
+    let portfolio = &mut ctx.accounts.portfolio;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!portfolio.extraction_paused, ErrorCode::ExtractionPaused);
+    require!(max_slippage_bps <= 10000, ErrorCode::InvalidSlippageTolerance);
+    require!(ctx.remaining_accounts.len() >= 2, ErrorCode::StrategyNotFound);
+    
+    let (strategy_info, position_info) = (&ctx.remaining_accounts[0], &ctx.remaining_accounts[1]);
+    let mut strategy = Strategy::load_checked(strategy_info, &portfolio_key)?;
+    require!(strategy.strategy_id == strategy_id, ErrorCode::StrategyNotFound);
+    let mut position = CapitalPosition::load_checked(position_info, &strategy_info.key())?;
+    position.require_compatible(&strategy.protocol_type)?;
+    
+    let previous_status = strategy.status;
+    if previous_status == StrategyStatus::Deprecated && strategy.current_balance == 0 {
+        msg!("Strategy {} already wound down", strategy_id);
+        return Ok(());
+    }
+    
+    let exits = if strategy.current_balance > 0 {
+        load_protocol_exits(
+            ctx.remaining_accounts,
+            ctx.accounts.token_program.as_ref(),
+            &strategy,
+            &strategy_info.key(),
+            position_info,
+            &position,
+            ctx.accounts.recipient.to_account_info(),
+        )?
+    } else {
+        ProtocolExits::default()
+    };
+    
+    let result = wind_down(&mut strategy, &mut position, &exits, max_slippage_bps, current_time)?;
+    record_strategy_exit(portfolio, strategy_id, strategy.protocol_type.target_id(), current_time);
+    
+    if previous_status != StrategyStatus::Deprecated {
+        emit!(StrategyStatusChanged {
+            portfolio: portfolio_key,
+            strategy_id,
+            previous_status,
+            new_status: StrategyStatus::Deprecated,
+            timestamp: current_time,
+        });
+    }
+    
+    if let Some(result) = result {
+        portfolio.total_capital_moved = portfolio.total_capital_moved
+            .checked_add(result.extracted_amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        
+        emit!(CapitalExtracted {
+            portfolio: portfolio_key,
+            strategy_id,
+            amount: result.extracted_amount,
+            pending_amount: result.pending_amount,
+            fees_paid: result.fees_paid,
+            timestamp: current_time,
+        });
+        
+        msg!("Strategy {} wound down: {} lamports to {}, {} pending, {} left",
+             strategy_id, result.extracted_amount, destination, result.pending_amount, strategy.current_balance);
+    }
+    
+    strategy.exit(&crate::ID)?;
+    position.exit(&crate::ID)?;
+    
+    Ok(())
+}
+
+// Deprecates the strategy and, if it still holds capital, exits the position
+// regardless of cost (liquid unstake for staking). Exit costs can leave tracked
+// balance that a fully unwound sole position no longer backs; it is written off
+// so the strategy ends at zero.
+pub fn wind_down(
+    strategy: &mut Strategy,
+    position: &mut CapitalPosition,
+    exits: &ProtocolExits,
+    max_slippage_bps: u16,
+    current_time: i64,
+) -> Result<Option<ExtractionResult>> {
+    strategy.status = StrategyStatus::Deprecated;
+    if strategy.current_balance == 0 {
+        return Ok(None);
+    }
+    
+    let result = extract_from_protocol(
+        strategy,
+        position,
+        None,
+        exits,
+        UnstakeMode::Liquid,
+        max_slippage_bps,
+        current_time,
+    )?;
+    strategy.last_rebalance = current_time;
+    
+    let unwound = position.platform_controlled_lp == 0
+        && position.token_a_amount == 0
+        && position.token_b_amount == 0;
+    if unwound && strategy.position_count <= 1 && strategy.current_balance > 0 {
+        msg!("Writing off {} lamports of exit costs", strategy.current_balance);
+        strategy.current_balance = 0;
+    }
+    
+    Ok(Some(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{test_position, test_strategy};
+    
+    fn farming_strategy(current_balance: u64) -> Strategy {
+        Strategy {
+            protocol_type: ProtocolType::YieldFarming {
+                pair_id: Pubkey::new_unique(),
+                reward_multiplier: 2,
+                token_a_mint: Pubkey::new_unique(),
+                token_b_mint: Pubkey::new_unique(),
+                fee_tier: 30,
+            },
+            yield_rate: 1800,
+            volatility_score: 4000,
+            position_count: 1,
+            ..test_strategy(current_balance, current_balance)
+        }
+    }
+    
+    fn lp_position(token_amount: u64) -> CapitalPosition {
+        CapitalPosition {
+            token_a_amount: token_amount,
+            token_b_amount: token_amount,
+            ..test_position(0)
+        }
+    }
+    
+    #[test]
+    fn test_yield_farming_wind_down() {
+        let mut strategy = farming_strategy(2_000_000_000);
+        let mut position = lp_position(1_000_000_000);
+        let exits = ProtocolExits::default();
+        let now = 1_700_000_000;
+        
+        // The whole LP share comes out, less the 0.5% simulated slippage
+        let result = wind_down(&mut strategy, &mut position, &exits, 100, now).unwrap().unwrap();
+        assert_eq!(result.extracted_amount, 1_990_000_000);
+        assert_eq!(strategy.status, StrategyStatus::Deprecated);
+        assert_eq!(position.platform_controlled_lp, 0);
+        assert_eq!(strategy.last_rebalance, now);
+        
+        // Slippage the empty position no longer backs is written off
+        assert_eq!(strategy.current_balance, 0);
+        assert_eq!(strategy.total_withdrawals, 1_990_000_000);
+        
+        // A second call finds nothing left to do
+        assert!(wind_down(&mut strategy, &mut position, &exits, 100, now + 60).unwrap().is_none());
+        assert_eq!(strategy.last_rebalance, now);
+        
+        // The slippage bound still applies to a forced exit
+        let mut strategy = farming_strategy(2_000_000_000);
+        let mut position = lp_position(1_000_000_000);
+        assert_eq!(
+            wind_down(&mut strategy, &mut position, &exits, 10, now).unwrap_err(),
+            ErrorCode::SlippageExceeded.into()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::state::*;
+use crate::events::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(strategy_id: Pubkey)]
+pub struct WithdrawCapital<'info> {
+    #[account(
+        seeds = [b"portfolio", portfolio.original_manager.as_ref()],
+        bump = portfolio.bump,
+        constraint = portfolio.schema_supported() @ ErrorCode::UnsupportedSchemaVersion,
+        has_one = manager @ ErrorCode::UnauthorizedManager
+    )]
+    pub portfolio: Account<'info, Portfolio>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", portfolio.key().as_ref(), strategy_id.as_ref()],
+        bump = strategy.bump,
+        constraint = strategy.strategy_id == strategy_id @ ErrorCode::StrategyNotFound,
+        constraint = strategy.schema_supported() @ ErrorCode::UnsupportedSchemaVersion
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    // Position whose holdings bound the withdrawal per the protocol's rules
+    #[account(
+        seeds = [b"position", strategy.key().as_ref(), &[position.position_index]],
+        bump = position.bump,
+        constraint = position.strategy_id == strategy_id @ ErrorCode::StrategyNotFound
+    )]
+    pub position: Account<'info, CapitalPosition>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", strategy.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+    
+    pub manager: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn withdraw_capital(
+    ctx: Context<WithdrawCapital>,
+    strategy_id: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let portfolio = &ctx.accounts.portfolio;
+    let strategy = &mut ctx.accounts.strategy;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    // SECURITY VALIDATIONS
+    require!(!portfolio.emergency_pause, ErrorCode::EmergencyPaused);
+    require!(!portfolio.extraction_paused, ErrorCode::ExtractionPaused);
+    require!(amount > 0, ErrorCode::InsufficientBalance);
+    require!(amount <= strategy.current_balance, ErrorCode::InsufficientBalance);
+    
+    // PROTOCOL RULES (YieldFarming: dust protection and max 50% per transaction)
+    ctx.accounts.position.validate_withdrawal_feasibility(amount, &strategy.protocol_type)?;
+    
+    // THE VAULT MUST STAY RENT-EXEMPT
+    let vault_lamports = ctx.accounts.vault.lamports();
+    let rent_minimum = Rent::get()?.minimum_balance(0);
+    require!(
+        vault_lamports.saturating_sub(amount) >= rent_minimum,
+        ErrorCode::VaultBelowRentExemption
+    );
+    
+    // UPDATE STRATEGY STATE
+    let balance_before = strategy.current_balance;
+    strategy.current_balance -= amount;
+    strategy.total_withdrawals = strategy.total_withdrawals
+        .checked_add(amount)
+        .ok_or(ErrorCode::BalanceOverflow)?;
+    strategy.record_withdrawal(amount, balance_before, current_time)?;
+    strategy.rescale_high_water_mark(balance_before)?;
+    strategy.vault_accounted = strategy.vault_accounted.saturating_sub(amount);
+    
+    // MOVE LAMPORTS OUT OF THE VAULT
+    let strategy_key = strategy.key();
+    let vault_seeds: &[&[u8]] = &[b"vault", strategy_key.as_ref(), &[ctx.bumps.vault]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.vault.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+            },
+            &[vault_seeds],
+        ),
+        amount,
+    )?;
+    
+    emit!(CapitalWithdrawn {
+        portfolio: portfolio.key(),
+        strategy_id,
+        destination: ctx.accounts.destination.key(),
+        amount,
+        balance: strategy.current_balance,
+        timestamp: current_time,
+    });
+    
+    msg!("Capital withdrawn: strategy={}, amount={}, destination={}, balance={}",
+         strategy_id, amount, ctx.accounts.destination.key(), strategy.current_balance);
+    
+    Ok(())
+}
@@ -1,10 +1,16 @@
 pub mod state;
 pub mod error;
+pub mod events;
+pub mod oracle;
+pub mod whirlpool;
+pub mod marinade;
+pub mod solend;
 pub mod instructions;
 
 use anchor_lang::prelude::*;
 
 pub use state::*;
+pub use events::*;
 pub use instructions::*;
 
 declare_id!("2Cpk3YWB8EQNvjva4PkxqN3EsxYYeep5m7SEXFQHaQpK");
@@ -18,8 +24,17 @@ pub mod portfolio_rebalancer {
         manager: Pubkey,
         rebalance_threshold: u8,
         min_rebalance_interval: i64,
+        max_strategies: u16,
+        emergency_destination: Pubkey,
     ) -> Result<()> {
-        instructions::initialize_portfolio(ctx, manager, rebalance_threshold, min_rebalance_interval)
+        instructions::initialize_portfolio(
+            ctx,
+            manager,
+            rebalance_threshold,
+            min_rebalance_interval,
+            max_strategies,
+            emergency_destination,
+        )
     }
     
     pub fn register_strategy(
@@ -27,8 +42,9 @@ pub mod portfolio_rebalancer {
         strategy_id: Pubkey,
         protocol_type: ProtocolType,
         initial_balance: u64,
+        price_feeds: Option<PriceFeedBinding>,
     ) -> Result<()> {
-        instructions::register_strategy(ctx, strategy_id, protocol_type, initial_balance)
+        instructions::register_strategy(ctx, strategy_id, protocol_type, initial_balance, price_feeds)
     }
     
     pub fn update_performance(
@@ -37,27 +53,405 @@ pub mod portfolio_rebalancer {
         yield_rate: u64,
         volatility_score: u32,
         current_balance: u64,
+        rate_type: RateType,
+        allow_yield_jump: bool,
     ) -> Result<()> {
-        instructions::update_performance(ctx, strategy_id, yield_rate, volatility_score, current_balance)
+        instructions::update_performance(ctx, strategy_id, yield_rate, volatility_score, current_balance, rate_type, allow_yield_jump)
     }
     
-    pub fn extract_capital(
-        ctx: Context<ExtractCapital>,
+    pub fn extract_capital<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExtractCapital<'info>>,
         strategy_ids: Vec<Pubkey>,
+        force: bool,
+        unstake_mode: UnstakeMode,
+        max_slippage_bps: u16,
     ) -> Result<()> {
-        instructions::extract_capital(ctx, strategy_ids)
+        instructions::extract_capital(ctx, strategy_ids, force, unstake_mode, max_slippage_bps)
     }
 
-    pub fn execute_ranking_cycle(
-        ctx: Context<ExecuteRankingCycle>,
+    pub fn execute_ranking_cycle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteRankingCycle<'info>>,
+        expected_last_rebalance: Option<i64>,
     ) -> Result<()> {
-        instructions::execute_ranking_cycle(ctx)
+        instructions::execute_ranking_cycle(ctx, expected_last_rebalance)
     }
     
-    pub fn redistribute_capital(
-        ctx: Context<RedistributeCapital>, 
+    pub fn redistribute_capital<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedistributeCapital<'info>>, 
         allocations: Vec<CapitalAllocation>,
+        dry_run: bool,
+    ) -> Result<RedistributionOutcome> {
+        instructions::redistribute_capital(ctx, allocations, dry_run)
+    }
+    
+    pub fn update_portfolio_config(
+        ctx: Context<UpdatePortfolioConfig>,
+        config: PortfolioConfigUpdate,
+    ) -> Result<()> {
+        instructions::update_portfolio_config(ctx, config)
+    }
+    
+    pub fn check_il_thresholds(
+        ctx: Context<CheckIlThresholds>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::check_il_thresholds(ctx, strategy_id)
+    }
+    
+    pub fn fund_keeper_budget(
+        ctx: Context<FundKeeperBudget>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::fund_keeper_budget(ctx, amount)
+    }
+    
+    pub fn draw_keeper_budget(
+        ctx: Context<DrawKeeperBudget>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::draw_keeper_budget(ctx, amount)
+    }
+    
+    pub fn verify_strategy_count<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyStrategyCount<'info>>,
+        repair: bool,
+    ) -> Result<()> {
+        instructions::verify_strategy_count(ctx, repair)
+    }
+    
+    pub fn submit_scores<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SubmitScores<'info>>,
+    ) -> Result<()> {
+        instructions::submit_scores(ctx)
+    }
+    
+    pub fn finalize_ranking<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FinalizeRanking<'info>>,
+    ) -> Result<()> {
+        instructions::finalize_ranking(ctx)
+    }
+    
+    pub fn get_staleness_report<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetStalenessReport<'info>>,
+    ) -> Result<Vec<StalenessEntry>> {
+        instructions::get_staleness_report(ctx)
+    }
+    
+    pub fn set_compound_frequency(
+        ctx: Context<SetCompoundFrequency>,
+        strategy_id: Pubkey,
+        compound_frequency_seconds: i64,
+    ) -> Result<()> {
+        instructions::set_compound_frequency(ctx, strategy_id, compound_frequency_seconds)
+    }
+    
+    pub fn compound_rewards(
+        ctx: Context<CompoundRewards>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::compound_rewards(ctx, strategy_id)
+    }
+    
+    pub fn get_portfolio_risk_score<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetPortfolioRiskScore<'info>>,
+    ) -> Result<u64> {
+        instructions::get_portfolio_risk_score(ctx)
+    }
+    
+    pub fn set_extraction_paused(
+        ctx: Context<SetExtractionPaused>,
+        paused: bool,
+    ) -> Result<()> {
+        instructions::set_extraction_paused(ctx, paused)
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_capital_position(
+        ctx: Context<CreateCapitalPosition>,
+        strategy_id: Pubkey,
+        position_type: PositionType,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        lp_tokens: u64,
+        entry_price_a: u64,
+        entry_price_b: u64,
+    ) -> Result<()> {
+        instructions::create_capital_position(
+            ctx, strategy_id, position_type, token_a_amount, token_b_amount,
+            lp_tokens, entry_price_a, entry_price_b,
+        )
+    }
+    
+    pub fn transfer_manager(
+        ctx: Context<TransferManager>,
+        new_manager: Pubkey,
+    ) -> Result<()> {
+        instructions::transfer_manager(ctx, new_manager)
+    }
+    
+    pub fn accept_manager(ctx: Context<AcceptManager>) -> Result<()> {
+        instructions::accept_manager(ctx)
+    }
+    
+    pub fn cancel_manager_proposal(ctx: Context<CancelManagerProposal>) -> Result<()> {
+        instructions::cancel_manager_proposal(ctx)
+    }
+    
+    pub fn calculate_rebalance_breakeven<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CalculateRebalanceBreakeven<'info>>,
+    ) -> Result<BreakevenReport> {
+        instructions::calculate_rebalance_breakeven(ctx)
+    }
+    
+    pub fn set_withdrawal_limit(
+        ctx: Context<SetWithdrawalLimit>,
+        strategy_id: Pubkey,
+        daily_withdrawal_limit_bps: u16,
+    ) -> Result<()> {
+        instructions::set_withdrawal_limit(ctx, strategy_id, daily_withdrawal_limit_bps)
+    }
+    
+    pub fn collect_performance_fees(ctx: Context<CollectPerformanceFees>) -> Result<()> {
+        instructions::collect_performance_fees(ctx)
+    }
+    
+    pub fn emergency_extract(ctx: Context<EmergencyExtract>) -> Result<()> {
+        instructions::emergency_extract(ctx)
+    }
+    
+    pub fn override_reentry_cooldown(
+        ctx: Context<OverrideReentryCooldown>,
+        target: Pubkey,
+    ) -> Result<()> {
+        instructions::override_reentry_cooldown(ctx, target)
+    }
+    
+    pub fn get_strategy_pnl(
+        ctx: Context<GetStrategyPnl>,
+        strategy_id: Pubkey,
+    ) -> Result<i64> {
+        instructions::get_strategy_pnl(ctx, strategy_id)
+    }
+    
+    pub fn set_emergency_pause(
+        ctx: Context<SetEmergencyPause>,
+        paused: bool,
+    ) -> Result<()> {
+        instructions::set_emergency_pause(ctx, paused)
+    }
+    
+    pub fn update_rebalance_config(
+        ctx: Context<UpdateRebalanceConfig>,
+        new_threshold: Option<u8>,
+        new_interval: Option<i64>,
+        new_ranking_mode: Option<RankingMode>,
+        new_tie_break: Option<TieBreak>,
+    ) -> Result<()> {
+        instructions::update_rebalance_config(ctx, new_threshold, new_interval, new_ranking_mode, new_tie_break)
+    }
+    
+    pub fn deposit_capital(
+        ctx: Context<DepositCapital>,
+        strategy_id: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::deposit_capital(ctx, strategy_id, amount)
+    }
+    
+    pub fn check_risk_config_feasibility<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CheckRiskConfigFeasibility<'info>>,
+        proposed: RiskLimits,
+    ) -> Result<Vec<RiskViolation>> {
+        instructions::check_risk_config_feasibility(ctx, proposed)
+    }
+    
+    pub fn withdraw_capital(
+        ctx: Context<WithdrawCapital>,
+        strategy_id: Pubkey,
+        amount: u64,
     ) -> Result<()> {
-        instructions::redistribute_capital(ctx, allocations)
+        instructions::withdraw_capital(ctx, strategy_id, amount)
+    }
+    
+    pub fn set_strategy_status(
+        ctx: Context<SetStrategyStatus>,
+        strategy_id: Pubkey,
+        new_status: StrategyStatus,
+    ) -> Result<()> {
+        instructions::set_strategy_status(ctx, strategy_id, new_status)
+    }
+    
+    pub fn set_risk_config(
+        ctx: Context<SetRiskConfig>,
+        limits: RiskLimits,
+    ) -> Result<()> {
+        instructions::set_risk_config(ctx, limits)
+    }
+    
+    pub fn simulate_rebalance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SimulateRebalance<'info>>,
+    ) -> Result<RebalancingPlan> {
+        instructions::simulate_rebalance(ctx)
+    }
+    
+    pub fn begin_redistribution(
+        ctx: Context<BeginRedistribution>,
+        total_expected: u64,
+    ) -> Result<()> {
+        instructions::begin_redistribution(ctx, total_expected)
+    }
+    
+    pub fn redistribute_chunk<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedistributeChunk<'info>>,
+        allocations: Vec<CapitalAllocation>,
+    ) -> Result<()> {
+        instructions::redistribute_chunk(ctx, allocations)
+    }
+    
+    pub fn finalize_redistribution(ctx: Context<FinalizeRedistribution>) -> Result<()> {
+        instructions::finalize_redistribution(ctx)
+    }
+    
+    pub fn cancel_redistribution(ctx: Context<CancelRedistribution>) -> Result<()> {
+        instructions::cancel_redistribution(ctx)
+    }
+    
+    pub fn get_portfolio_summary<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetPortfolioSummary<'info>>,
+    ) -> Result<PortfolioSummary> {
+        instructions::get_portfolio_summary(ctx)
+    }
+    
+    pub fn refresh_position_il(
+        ctx: Context<RefreshPositionIl>,
+        strategy_id: Pubkey,
+        position_index: u8,
+    ) -> Result<()> {
+        instructions::refresh_position_il(ctx, strategy_id, position_index)
+    }
+
+    pub fn claim_unstake(
+        ctx: Context<ClaimUnstake>,
+        strategy_id: Pubkey,
+        position_index: u8,
+    ) -> Result<()> {
+        instructions::claim_unstake(ctx, strategy_id, position_index)
+    }
+    
+    pub fn preview_allocation<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PreviewAllocation<'info>>,
+        available_capital: u64,
+    ) -> Result<Vec<CapitalAllocation>> {
+        instructions::preview_allocation(ctx, available_capital)
+    }
+    
+    pub fn migrate_portfolio(
+        ctx: Context<MigratePortfolio>,
+        emergency_destination: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::migrate_portfolio(ctx, emergency_destination)
+    }
+    
+    pub fn can_withdraw(
+        ctx: Context<CanWithdraw>,
+        strategy_id: Pubkey,
+        requested_amount: u64,
+    ) -> Result<WithdrawalFeasibility> {
+        instructions::can_withdraw(ctx, strategy_id, requested_amount)
+    }
+    
+    pub fn accrue_yield(ctx: Context<AccrueYield>, strategy_id: Pubkey) -> Result<()> {
+        instructions::accrue_yield(ctx, strategy_id)
+    }
+    
+    pub fn wind_down_strategy<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WindDownStrategy<'info>>,
+        strategy_id: Pubkey,
+        destination: Pubkey,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        instructions::wind_down_strategy(ctx, strategy_id, destination, max_slippage_bps)
+    }
+    
+    pub fn get_strategy_rankings<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetStrategyRankings<'info>>,
+    ) -> Result<Vec<RankingRow>> {
+        instructions::get_strategy_rankings(ctx)
+    }
+    
+    pub fn emergency_extract_capital<'info>(
+        ctx: Context<'_, '_, 'info, 'info, EmergencyExtractCapital<'info>>,
+        strategy_ids: Vec<Pubkey>,
+        confirmation_nonce: u32,
+        override_pause: bool,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        instructions::emergency_extract_capital(ctx, strategy_ids, confirmation_nonce, override_pause, max_slippage_bps)
+    }
+    
+    pub fn record_tvl_snapshot<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordTvlSnapshot<'info>>,
+    ) -> Result<()> {
+        instructions::record_tvl_snapshot(ctx)
+    }
+    
+    pub fn list_strategies_by_protocol<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ListStrategiesByProtocol<'info>>,
+        filter: ProtocolFilter,
+    ) -> Result<Vec<StrategyListing>> {
+        instructions::list_strategies_by_protocol(ctx, filter)
+    }
+    
+    pub fn reconcile_strategy(
+        ctx: Context<ReconcileStrategy>,
+        strategy_id: Pubkey,
+    ) -> Result<()> {
+        instructions::reconcile_strategy(ctx, strategy_id)
+    }
+    
+    pub fn set_denomination_config(
+        ctx: Context<SetDenominationConfig>,
+        oracle_source: OracleSource,
+        price_feed: Pubkey,
+        reference_price: u64,
+    ) -> Result<()> {
+        instructions::set_denomination_config(ctx, oracle_source, price_feed, reference_price)
+    }
+    
+    pub fn migrate_strategy(
+        ctx: Context<MigrateStrategy>,
+        price_feeds: Option<PriceFeedBinding>,
+    ) -> Result<()> {
+        instructions::migrate_strategy(ctx, price_feeds)
+    }
+    
+    pub fn migrate_risk_config(ctx: Context<MigrateRiskConfig>) -> Result<()> {
+        instructions::migrate_risk_config(ctx)
+    }
+    
+    pub fn register_keeper(
+        ctx: Context<RegisterKeeper>,
+        keeper: Pubkey,
+    ) -> Result<()> {
+        instructions::register_keeper(ctx, keeper)
+    }
+    
+    pub fn revoke_keeper(
+        ctx: Context<RevokeKeeper>,
+        keeper: Pubkey,
+    ) -> Result<()> {
+        instructions::revoke_keeper(ctx, keeper)
+    }
+    
+    pub fn claim_performance_fees(ctx: Context<ClaimPerformanceFees>) -> Result<()> {
+        instructions::claim_performance_fees(ctx)
+    }
+    
+    pub fn reset_ranking_buffer(ctx: Context<ResetRankingBuffer>) -> Result<()> {
+        instructions::reset_ranking_buffer(ctx)
+    }
+    
+    pub fn close_strategy(ctx: Context<CloseStrategy>, strategy_id: Pubkey) -> Result<()> {
+        instructions::close_strategy(ctx, strategy_id)
     }
 }
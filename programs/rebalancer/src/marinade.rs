@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::token::TokenAccount;
+use crate::oracle::read_bytes;
+use crate::instructions::extract_capital::find_token_account;
+use crate::error::ErrorCode;
+
+// Marinade liquid staking program, owner of the State and every TicketAccountData
+pub const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+// sha256("account:State")[..8] and sha256("account:TicketAccountData")[..8]
+pub const MARINADE_STATE_DISCRIMINATOR: [u8; 8] = [216, 146, 107, 94, 104, 75, 182, 177];
+pub const TICKET_ACCOUNT_DISCRIMINATOR: [u8; 8] = [133, 77, 18, 98, 211, 1, 231, 3];
+
+// sha256("global:<name>")[..8] for liquid_unstake, order_unstake and claim
+pub const LIQUID_UNSTAKE_DISCRIMINATOR: [u8; 8] = [30, 30, 119, 240, 191, 227, 12, 16];
+pub const ORDER_UNSTAKE_DISCRIMINATOR: [u8; 8] = [97, 167, 144, 107, 117, 190, 128, 36];
+pub const CLAIM_DISCRIMINATOR: [u8; 8] = [62, 198, 214, 193, 213, 159, 108, 210];
+
+// State offsets (marinade-finance 2.0, including the discriminator)
+pub const STATE_MSOL_MINT_OFFSET: usize = 8;
+pub const STATE_TREASURY_MSOL_OFFSET: usize = 104;
+
+// TicketAccountData layout: discriminator | state | beneficiary | lamports_amount | created_epoch
+pub const TICKET_ACCOUNT_SIZE: usize = 88;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarinadeTicket {
+    pub state: Pubkey,
+    pub beneficiary: Pubkey,
+    pub lamports_amount: u64,
+    pub created_epoch: u64,
+}
+
+impl MarinadeTicket {
+    // The strategy's unstake_delay is enforced on top of Marinade's own wait
+    pub fn claimable_epoch(&self, unstake_delay: u32) -> u64 {
+        self.created_epoch.saturating_add(unstake_delay as u64)
+    }
+}
+
+pub fn decode_marinade_ticket(data: &[u8]) -> Result<MarinadeTicket> {
+    require!(data.len() >= TICKET_ACCOUNT_SIZE, ErrorCode::InvalidUnstakeTicket);
+    require!(data[..8] == TICKET_ACCOUNT_DISCRIMINATOR, ErrorCode::InvalidUnstakeTicket);
+    
+    Ok(MarinadeTicket {
+        state: Pubkey::new_from_array(read_bytes(data, 8)?),
+        beneficiary: Pubkey::new_from_array(read_bytes(data, 40)?),
+        lamports_amount: u64::from_le_bytes(read_bytes(data, 72)?),
+        created_epoch: u64::from_le_bytes(read_bytes(data, 80)?),
+    })
+}
+
+pub fn marinade_pda(state: &Pubkey, seed: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[state.as_ref(), seed], &MARINADE_PROGRAM_ID).0
+}
+
+// ACCOUNTS FOR A REAL MARINADE UNSTAKE
+// The CapitalPosition PDA owns the mSOL and signs the burn. Liquid unstakes pay
+// the manager immediately at the pool's fee; delayed unstakes fill a ticket the
+// client pre-created (88 bytes, owned by Marinade) that is claimed later.
+pub struct MarinadeUnstake<'info> {
+    pub marinade_program: &'info AccountInfo<'info>,
+    pub state: &'info AccountInfo<'info>,
+    pub msol_mint: &'info AccountInfo<'info>,
+    pub msol_source: &'info AccountInfo<'info>,
+    pub position_authority: &'info AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub accounts: &'info [AccountInfo<'info>],
+    pub manager: AccountInfo<'info>,
+    pub strategy_key: Pubkey,
+}
+
+impl<'info> MarinadeUnstake<'info> {
+    pub fn load(
+        accounts: &'info [AccountInfo<'info>],
+        stake_pool: &Pubkey,
+        strategy_key: Pubkey,
+        position_authority: &'info AccountInfo<'info>,
+        manager: AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+    ) -> Result<Self> {
+        let state = find_by_key(accounts, stake_pool)?;
+        require!(state.owner == &MARINADE_PROGRAM_ID, ErrorCode::InvalidProtocolType);
+        let msol_mint_key = {
+            let data = state.try_borrow_data()?;
+            require!(data.len() >= STATE_TREASURY_MSOL_OFFSET + 32, ErrorCode::InvalidProtocolType);
+            require!(data[..8] == MARINADE_STATE_DISCRIMINATOR, ErrorCode::InvalidProtocolType);
+            Pubkey::new_from_array(read_bytes(&data, STATE_MSOL_MINT_OFFSET)?)
+        };
+        
+        Ok(Self {
+            marinade_program: find_by_key(accounts, &MARINADE_PROGRAM_ID)?,
+            state,
+            msol_mint: find_by_key(accounts, &msol_mint_key)?,
+            msol_source: find_token_account(accounts, &msol_mint_key, &position_authority.key())?,
+            position_authority,
+            token_program,
+            accounts,
+            manager,
+            strategy_key,
+        })
+    }
+    
+    // Every mSOL the position holds; extraction always exits in full
+    pub fn msol_balance(&self) -> Result<u64> {
+        Ok(Account::<TokenAccount>::try_from(self.msol_source)?.amount)
+    }
+    
+    // Swap mSOL through the liquidity pool, returning the lamports the manager received
+    pub fn liquid_unstake(&self, msol_amount: u64, position_index: u8, position_bump: u8) -> Result<u64> {
+        let treasury_key = Pubkey::new_from_array(read_bytes(&self.state.try_borrow_data()?, STATE_TREASURY_MSOL_OFFSET)?);
+        let sol_leg = find_by_key(self.accounts, &marinade_pda(self.state.key, b"liq_sol"))?;
+        let msol_leg = find_token_account(
+            self.accounts,
+            self.msol_mint.key,
+            &marinade_pda(self.state.key, b"liq_st_sol_authority"),
+        )?;
+        let treasury = find_by_key(self.accounts, &treasury_key)?;
+        let system = find_by_key(self.accounts, &System::id())?;
+        
+        let mut data = LIQUID_UNSTAKE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&msol_amount.to_le_bytes());
+        
+        let balance_before = self.manager.lamports();
+        invoke_signed(
+            &Instruction {
+                program_id: MARINADE_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(self.state.key(), false),
+                    AccountMeta::new(self.msol_mint.key(), false),
+                    AccountMeta::new(sol_leg.key(), false),
+                    AccountMeta::new(msol_leg.key(), false),
+                    AccountMeta::new(treasury.key(), false),
+                    AccountMeta::new(self.msol_source.key(), false),
+                    AccountMeta::new_readonly(self.position_authority.key(), true),
+                    AccountMeta::new(self.manager.key(), false),
+                    AccountMeta::new_readonly(system.key(), false),
+                    AccountMeta::new_readonly(self.token_program.key(), false),
+                ],
+                data,
+            },
+            &[
+                self.state.clone(),
+                self.msol_mint.clone(),
+                sol_leg.clone(),
+                msol_leg.clone(),
+                treasury.clone(),
+                self.msol_source.clone(),
+                self.position_authority.clone(),
+                self.manager.clone(),
+                system.clone(),
+                self.token_program.clone(),
+                self.marinade_program.clone(),
+            ],
+            &[&[b"position", self.strategy_key.as_ref(), &[position_index], &[position_bump]]],
+        )?;
+        
+        Ok(self.manager.lamports().saturating_sub(balance_before))
+    }
+    
+    // Burn mSOL into a delayed-unstake ticket, returning the filled ticket
+    pub fn order_unstake(&self, msol_amount: u64, position_index: u8, position_bump: u8) -> Result<(Pubkey, MarinadeTicket)> {
+        // A pre-created, still-empty ticket account
+        let ticket = self.accounts.iter()
+            .find(|info| {
+                info.owner == &MARINADE_PROGRAM_ID
+                    && info.data_len() == TICKET_ACCOUNT_SIZE
+                    && info.try_borrow_data().is_ok_and(|data| data[..8] == [0u8; 8])
+            })
+            .ok_or(ErrorCode::InvalidUnstakeTicket)?;
+        let clock = find_by_key(self.accounts, &sysvar::clock::ID)?;
+        let rent = find_by_key(self.accounts, &sysvar::rent::ID)?;
+        
+        let mut data = ORDER_UNSTAKE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&msol_amount.to_le_bytes());
+        
+        invoke_signed(
+            &Instruction {
+                program_id: MARINADE_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(self.state.key(), false),
+                    AccountMeta::new(self.msol_mint.key(), false),
+                    AccountMeta::new(self.msol_source.key(), false),
+                    AccountMeta::new_readonly(self.position_authority.key(), true),
+                    AccountMeta::new(ticket.key(), false),
+                    AccountMeta::new_readonly(clock.key(), false),
+                    AccountMeta::new_readonly(rent.key(), false),
+                    AccountMeta::new_readonly(self.token_program.key(), false),
+                ],
+                data,
+            },
+            &[
+                self.state.clone(),
+                self.msol_mint.clone(),
+                self.msol_source.clone(),
+                self.position_authority.clone(),
+                ticket.clone(),
+                clock.clone(),
+                rent.clone(),
+                self.token_program.clone(),
+                self.marinade_program.clone(),
+            ],
+            &[&[b"position", self.strategy_key.as_ref(), &[position_index], &[position_bump]]],
+        )?;
+        
+        let filled = decode_marinade_ticket(&ticket.try_borrow_data()?)?;
+        Ok((ticket.key(), filled))
+    }
+
+}
+
+fn find_by_key<'info>(accounts: &'info [AccountInfo<'info>], key: &Pubkey) -> Result<&'info AccountInfo<'info>> {
+    accounts.iter()
+        .find(|info| info.key == key)
+        .ok_or(error!(ErrorCode::TokenAccountNotProvided))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_decode_marinade_ticket_fixture() {
+        let state = Pubkey::new_unique();
+        let beneficiary = Pubkey::new_unique();
+        
+        let mut data = vec![0u8; TICKET_ACCOUNT_SIZE];
+        data[..8].copy_from_slice(&TICKET_ACCOUNT_DISCRIMINATOR);
+        data[8..40].copy_from_slice(state.as_ref());
+        data[40..72].copy_from_slice(beneficiary.as_ref());
+        data[72..80].copy_from_slice(&3_000_000_000u64.to_le_bytes());
+        data[80..88].copy_from_slice(&612u64.to_le_bytes());
+        
+        let ticket = decode_marinade_ticket(&data).unwrap();
+        assert_eq!((ticket.state, ticket.beneficiary), (state, beneficiary));
+        assert_eq!(ticket.lamports_amount, 3_000_000_000);
+        assert_eq!(ticket.claimable_epoch(2), 614);
+        
+        // An empty, pre-created ticket is not a filled one
+        assert!(decode_marinade_ticket(&[0u8; TICKET_ACCOUNT_SIZE]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use anchor_lang::prelude::*;
+use crate::state::{OracleSource, PriceFeedBinding};
+use crate::error::ErrorCode;
+
+// Pyth Solana receiver program, owner of every PriceUpdateV2 account
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+// sha256("account:PriceUpdateV2")[..8]
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+// Switchboard On-Demand program, owner of every PullFeedAccountData account
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+// sha256("account:PullFeedAccountData")[..8]
+pub const PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+
+// PullFeedAccountData offsets (switchboard-on-demand 0.1, including the discriminator)
+pub const PULL_FEED_LAST_UPDATE_OFFSET: usize = 2216;
+pub const PULL_FEED_RESULT_VALUE_OFFSET: usize = 2264;
+pub const SWITCHBOARD_DECIMALS: u32 = 18;
+
+// PriceUpdateV2 message (feed_id first) once verification_level is Full
+pub const PYTH_MESSAGE_OFFSET: usize = 41;
+
+pub const MAX_PRICE_AGE_SECONDS: i64 = 60;
+pub const PRICE_DECIMALS: i32 = 6;
+
+// PRICE UPDATE DECODING
+// PriceUpdateV2 layout (pyth-solana-receiver-sdk 0.6):
+//   discriminator (8) | write_authority (32) | verification_level (1 or 2)
+//   | feed_id (32) | price i64 | conf u64 | exponent i32 | publish_time i64 | ...
+// Decoded by hand so the program does not pull in the Pyth SDK dependency tree.
+// Only fully verified updates are accepted: a Partial update has been checked
+// against fewer Wormhole guardian signatures than the quorum.
+pub fn read_oracle_price(feed: &AccountInfo, current_time: i64) -> Result<u64> {
+    let (price, publish_time) = read_oracle_quote(feed)?;
+    require!(current_time - publish_time <= MAX_PRICE_AGE_SECONDS, ErrorCode::StalePrice);
+    Ok(price)
+}
+
+// Normalized price and its Pyth publish time, without a staleness check
+pub fn read_oracle_quote(feed: &AccountInfo) -> Result<(u64, i64)> {
+    read_oracle_quote_from(feed, OracleSource::Pyth)
+}
+
+// Dispatches to the source's decoder after checking the account owner. Both
+// sources normalize to the same 6-decimal price, so IL math is source-agnostic.
+pub fn read_oracle_quote_from(feed: &AccountInfo, source: OracleSource) -> Result<(u64, i64)> {
+    let owner = match source {
+        OracleSource::Pyth => PYTH_RECEIVER_PROGRAM_ID,
+        OracleSource::Switchboard => SWITCHBOARD_ON_DEMAND_PROGRAM_ID,
+    };
+    require!(feed.owner == &owner, ErrorCode::InvalidOracleAccount);
+    
+    let data = feed.try_borrow_data()?;
+    match source {
+        OracleSource::Pyth => decode_pyth_price_update(&data),
+        OracleSource::Switchboard => decode_switchboard_pull_feed(&data),
+    }
+}
+
+pub fn decode_pyth_price_update(data: &[u8]) -> Result<(u64, i64)> {
+    require_full_pyth_update(data)?;
+    let price_offset = PYTH_MESSAGE_OFFSET + 32;
+    require!(data.len() >= price_offset + 32, ErrorCode::InvalidOracleAccount);
+    
+    let price = i64::from_le_bytes(read_bytes(data, price_offset)?);
+    let exponent = i32::from_le_bytes(read_bytes(data, price_offset + 16)?);
+    let publish_time = i64::from_le_bytes(read_bytes(data, price_offset + 20)?);
+    
+    Ok((normalize_price(price, exponent)?, publish_time))
+}
+
+fn require_full_pyth_update(data: &[u8]) -> Result<()> {
+    require!(data.len() >= PYTH_MESSAGE_OFFSET, ErrorCode::InvalidOracleAccount);
+    require!(data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR, ErrorCode::InvalidOracleAccount);
+    
+    // VerificationLevel::Partial is tag 0 plus a num_signatures byte; Full is tag 1
+    match data[40] {
+        1 => Ok(()),
+        0 => err!(ErrorCode::PartiallyVerifiedPrice),
+        _ => err!(ErrorCode::InvalidOracleAccount),
+    }
+}
+
+pub fn decode_pyth_feed_id(data: &[u8]) -> Result<[u8; 32]> {
+    require_full_pyth_update(data)?;
+    read_bytes(data, PYTH_MESSAGE_OFFSET)
+}
+
+// Identifier a PriceFeedBinding leg is compared against
+pub fn oracle_feed_id(feed: &AccountInfo, source: OracleSource) -> Result<[u8; 32]> {
+    match source {
+        OracleSource::Pyth => decode_pyth_feed_id(&feed.try_borrow_data()?),
+        OracleSource::Switchboard => Ok(feed.key().to_bytes()),
+    }
+}
+
+// Quote from `feed` only if it is the feed bound for that leg, so a manager
+// cannot price a position off another asset's feed
+pub fn read_bound_oracle_quote(
+    feed: &AccountInfo,
+    binding: &PriceFeedBinding,
+    expected_feed: &[u8; 32],
+) -> Result<(u64, i64)> {
+    require!(binding.is_bound() && *expected_feed != [0u8; 32], ErrorCode::OracleFeedNotBound);
+    let quote = read_oracle_quote_from(feed, binding.source)?;
+    require!(oracle_feed_id(feed, binding.source)? == *expected_feed, ErrorCode::OracleFeedMismatch);
+    Ok(quote)
+}
+
+pub fn read_bound_oracle_price(
+    feed: &AccountInfo,
+    binding: &PriceFeedBinding,
+    expected_feed: &[u8; 32],
+    current_time: i64,
+) -> Result<u64> {
+    let (price, publish_time) = read_bound_oracle_quote(feed, binding, expected_feed)?;
+    require!(current_time - publish_time <= MAX_PRICE_AGE_SECONDS, ErrorCode::StalePrice);
+    Ok(price)
+}
+
+// Switchboard reports the feed's current result as an i128 with 18 decimals,
+// stamped with the unix time of the last update
+pub fn decode_switchboard_pull_feed(data: &[u8]) -> Result<(u64, i64)> {
+    require!(data.len() >= PULL_FEED_RESULT_VALUE_OFFSET + 16, ErrorCode::InvalidOracleAccount);
+    require!(data[..8] == PULL_FEED_DISCRIMINATOR, ErrorCode::InvalidOracleAccount);
+    
+    let last_update = i64::from_le_bytes(read_bytes(data, PULL_FEED_LAST_UPDATE_OFFSET)?);
+    let value = i128::from_le_bytes(read_bytes(data, PULL_FEED_RESULT_VALUE_OFFSET)?);
+    
+    require!(value > 0, ErrorCode::InvalidPrice);
+    let scale = 10i128.pow(SWITCHBOARD_DECIMALS - PRICE_DECIMALS as u32);
+    let normalized = value / scale;
+    
+    require!(normalized > 0, ErrorCode::InvalidPrice);
+    let price = u64::try_from(normalized).map_err(|_| error!(ErrorCode::BalanceOverflow))?;
+    Ok((price, last_update))
+}
+
+pub(crate) fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ErrorCode::InvalidOracleAccount.into())
+}
+
+// Rescale an oracle price (price * 10^exponent) to the 6-decimal convention
+// used by CapitalPosition entry prices
+pub fn normalize_price(price: i64, exponent: i32) -> Result<u64> {
+    require!(price > 0, ErrorCode::InvalidPrice);
+    
+    let shift = exponent + PRICE_DECIMALS;
+    let scale = 10u128.checked_pow(shift.unsigned_abs()).ok_or(ErrorCode::BalanceOverflow)?;
+    
+    let normalized = if shift >= 0 {
+        (price as u128).checked_mul(scale).ok_or(ErrorCode::BalanceOverflow)?
+    } else {
+        (price as u128) / scale
+    };
+    
+    require!(normalized > 0, ErrorCode::InvalidPrice);
+    u64::try_from(normalized).map_err(|_| error!(ErrorCode::BalanceOverflow))
+}
+
+// Returns the oracle price when the manager-provided price is within tolerance
+pub fn verify_entry_price(provided: u64, oracle_price: u64, tolerance_bps: u16) -> Result<u64> {
+    let deviation = (provided as u128).abs_diff(oracle_price as u128);
+    let max_deviation = (oracle_price as u128 * tolerance_bps as u128) / 10000;
+    
+    require!(deviation <= max_deviation, ErrorCode::EntryPriceDeviation);
+    Ok(oracle_price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_normalize_price() {
+        // $150.12345678 at exponent -8
+        assert_eq!(normalize_price(15_012_345_678, -8).unwrap(), 150_123_456);
+        assert_eq!(normalize_price(150, 0).unwrap(), 150_000_000);
+        assert!(normalize_price(0, -8).is_err());
+        assert!(normalize_price(-5, -8).is_err());
+    }
+    
+    #[test]
+    fn test_verify_entry_price() {
+        // Within 1% of the oracle price: the oracle price is recorded
+        assert_eq!(verify_entry_price(100_500_000, 100_000_000, 100).unwrap(), 100_000_000);
+        assert_eq!(verify_entry_price(99_000_000, 100_000_000, 100).unwrap(), 100_000_000);
+        
+        // Fabricated entry price far from the oracle is rejected
+        assert!(verify_entry_price(150_000_000, 100_000_000, 100).is_err());
+        assert!(verify_entry_price(98_999_999, 100_000_000, 100).is_err());
+    }
+    
+    // $150.12345678 with a publish time of 1_700_000_000, as each source encodes it
+    fn pyth_fixture() -> Vec<u8> {
+        let mut data = vec![0u8; 134];
+        data[..8].copy_from_slice(&PRICE_UPDATE_V2_DISCRIMINATOR);
+        data[40] = 1; // VerificationLevel::Full
+        data[PYTH_MESSAGE_OFFSET..PYTH_MESSAGE_OFFSET + 32].copy_from_slice(&[7u8; 32]); // feed_id
+        let price_offset = PYTH_MESSAGE_OFFSET + 32;
+        data[price_offset..price_offset + 8].copy_from_slice(&15_012_345_678i64.to_le_bytes());
+        data[price_offset + 16..price_offset + 20].copy_from_slice(&(-8i32).to_le_bytes());
+        data[price_offset + 20..price_offset + 28].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data
+    }
+    
+    fn switchboard_fixture(value: i128) -> Vec<u8> {
+        let mut data = vec![0u8; PULL_FEED_RESULT_VALUE_OFFSET + 16];
+        data[..8].copy_from_slice(&PULL_FEED_DISCRIMINATOR);
+        data[PULL_FEED_LAST_UPDATE_OFFSET..PULL_FEED_LAST_UPDATE_OFFSET + 8]
+            .copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[PULL_FEED_RESULT_VALUE_OFFSET..].copy_from_slice(&value.to_le_bytes());
+        data
+    }
+    
+    #[test]
+    fn test_oracle_sources_normalize_identically() {
+        let pyth = decode_pyth_price_update(&pyth_fixture()).unwrap();
+        let switchboard = decode_switchboard_pull_feed(&switchboard_fixture(150_123_456_780_000_000_000)).unwrap();
+        
+        assert_eq!(pyth, (150_123_456, 1_700_000_000));
+        assert_eq!(switchboard, pyth);
+        
+        // Zero/negative results and foreign accounts are rejected
+        assert_eq!(decode_switchboard_pull_feed(&switchboard_fixture(0)).unwrap_err(), ErrorCode::InvalidPrice.into());
+        assert_eq!(decode_switchboard_pull_feed(&switchboard_fixture(-1)).unwrap_err(), ErrorCode::InvalidPrice.into());
+        assert_eq!(decode_switchboard_pull_feed(&pyth_fixture()).unwrap_err(), ErrorCode::InvalidOracleAccount.into());
+    }
+    
+    #[test]
+    fn test_partially_verified_updates_rejected() {
+        let full = pyth_fixture();
+        assert_eq!(decode_pyth_feed_id(&full).unwrap(), [7u8; 32]);
+        
+        // Partial shifts the message by its num_signatures byte; neither the
+        // price nor the feed_id of such an update is trusted
+        let mut partial = vec![0u8; full.len() + 1];
+        partial[..40].copy_from_slice(&full[..40]);
+        partial[40] = 0;
+        partial[41] = 5;
+        partial[42..].copy_from_slice(&full[41..]);
+        assert_eq!(decode_pyth_price_update(&partial).unwrap_err(), ErrorCode::PartiallyVerifiedPrice.into());
+        assert_eq!(decode_pyth_feed_id(&partial).unwrap_err(), ErrorCode::PartiallyVerifiedPrice.into());
+    }
+    
+    #[test]
+    fn test_only_bound_feeds_quote() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = pyth_fixture();
+        let feed = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &PYTH_RECEIVER_PROGRAM_ID, false, 0);
+        
+        let binding = PriceFeedBinding { source: OracleSource::Pyth, feed_a: [7u8; 32], feed_b: [8u8; 32] };
+        assert_eq!(read_bound_oracle_quote(&feed, &binding, &binding.feed_a).unwrap(), (150_123_456, 1_700_000_000));
+        
+        // The same update can't stand in for the other leg, or for an unbound strategy
+        assert_eq!(
+            read_bound_oracle_quote(&feed, &binding, &binding.feed_b).unwrap_err(),
+            ErrorCode::OracleFeedMismatch.into()
+        );
+        assert_eq!(
+            read_bound_oracle_quote(&feed, &PriceFeedBinding::UNBOUND, &[7u8; 32]).unwrap_err(),
+            ErrorCode::OracleFeedNotBound.into()
+        );
+    }
+}
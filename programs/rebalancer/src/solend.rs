@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::TokenAccount;
+use crate::oracle::read_bytes;
+use crate::instructions::extract_capital::find_token_account;
+use crate::error::ErrorCode;
+
+// Solend token-lending program, owner of every Reserve account
+pub const SOLEND_PROGRAM_ID: Pubkey = pubkey!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
+
+// LendingInstruction::RedeemReserveCollateral tag (native program, no Anchor discriminator)
+pub const REDEEM_RESERVE_COLLATERAL_TAG: u8 = 5;
+
+// Reserve offsets (solend-token-lending 2.0 packed layout)
+pub const RESERVE_LEN: usize = 619;
+pub const RESERVE_LENDING_MARKET_OFFSET: usize = 10;
+pub const RESERVE_LIQUIDITY_MINT_OFFSET: usize = 42;
+pub const RESERVE_LIQUIDITY_SUPPLY_OFFSET: usize = 75;
+pub const RESERVE_AVAILABLE_AMOUNT_OFFSET: usize = 171;
+pub const RESERVE_BORROWED_WADS_OFFSET: usize = 179;
+pub const RESERVE_COLLATERAL_MINT_OFFSET: usize = 227;
+
+// Solend Decimal values are u128 scaled by 1e18
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+// Redeems are refused when the pool is lent out beyond this
+pub const MAX_LENDING_UTILIZATION_BPS: u64 = 9500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolendReserve {
+    pub lending_market: Pubkey,
+    pub liquidity_mint: Pubkey,
+    pub liquidity_supply: Pubkey,
+    pub available_amount: u64,
+    pub borrowed_amount: u64,
+    pub collateral_mint: Pubkey,
+}
+
+impl SolendReserve {
+    // Borrowed share of total reserve liquidity, in basis points
+    pub fn utilization_bps(&self) -> u64 {
+        let total = self.available_amount as u128 + self.borrowed_amount as u128;
+        if total == 0 {
+            return 0;
+        }
+        (self.borrowed_amount as u128 * 10000 / total) as u64
+    }
+}
+
+pub fn decode_solend_reserve(data: &[u8]) -> Result<SolendReserve> {
+    require!(data.len() >= RESERVE_LEN, ErrorCode::InvalidPoolState);
+    require!(data[0] == 1, ErrorCode::InvalidPoolState); // PROGRAM_VERSION
+    
+    let borrowed_wads = u128::from_le_bytes(read_bytes(data, RESERVE_BORROWED_WADS_OFFSET)?);
+    Ok(SolendReserve {
+        lending_market: Pubkey::new_from_array(read_bytes(data, RESERVE_LENDING_MARKET_OFFSET)?),
+        liquidity_mint: Pubkey::new_from_array(read_bytes(data, RESERVE_LIQUIDITY_MINT_OFFSET)?),
+        liquidity_supply: Pubkey::new_from_array(read_bytes(data, RESERVE_LIQUIDITY_SUPPLY_OFFSET)?),
+        available_amount: u64::from_le_bytes(read_bytes(data, RESERVE_AVAILABLE_AMOUNT_OFFSET)?),
+        borrowed_amount: u64::try_from(borrowed_wads / WAD).map_err(|_| error!(ErrorCode::BalanceOverflow))?,
+        collateral_mint: Pubkey::new_from_array(read_bytes(data, RESERVE_COLLATERAL_MINT_OFFSET)?),
+    })
+}
+
+// ACCOUNTS FOR A REAL SOLEND REDEEM
+// The CapitalPosition PDA holds the reserve's cTokens and signs the redeem;
+// liquidity lands in the manager's pre-created token account. Solend requires
+// a RefreshReserve earlier in the same transaction.
+pub struct SolendRedeem<'info> {
+    pub reserve_state: SolendReserve,
+    pub solend_program: &'info AccountInfo<'info>,
+    pub reserve: &'info AccountInfo<'info>,
+    pub collateral_mint: &'info AccountInfo<'info>,
+    pub liquidity_supply: &'info AccountInfo<'info>,
+    pub lending_market: &'info AccountInfo<'info>,
+    pub lending_market_authority: &'info AccountInfo<'info>,
+    pub collateral_source: &'info AccountInfo<'info>,
+    pub liquidity_destination: &'info AccountInfo<'info>,
+    pub position_authority: &'info AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub strategy_key: Pubkey,
+}
+
+impl<'info> SolendRedeem<'info> {
+    pub fn load(
+        accounts: &'info [AccountInfo<'info>],
+        reserve_address: &Pubkey,
+        strategy_key: Pubkey,
+        position_authority: &'info AccountInfo<'info>,
+        manager: &Pubkey,
+        token_program: AccountInfo<'info>,
+    ) -> Result<Self> {
+        let by_key = |key: &Pubkey| {
+            accounts.iter()
+                .find(|info| info.key == key)
+                .ok_or(error!(ErrorCode::TokenAccountNotProvided))
+        };
+        
+        let reserve = by_key(reserve_address)?;
+        require!(reserve.owner == &SOLEND_PROGRAM_ID, ErrorCode::InvalidPoolState);
+        let reserve_state = decode_solend_reserve(&reserve.try_borrow_data()?)?;
+        let lending_market_authority = Pubkey::find_program_address(
+            &[reserve_state.lending_market.as_ref()],
+            &SOLEND_PROGRAM_ID,
+        ).0;
+        
+        Ok(Self {
+            solend_program: by_key(&SOLEND_PROGRAM_ID)?,
+            reserve,
+            collateral_mint: by_key(&reserve_state.collateral_mint)?,
+            liquidity_supply: by_key(&reserve_state.liquidity_supply)?,
+            lending_market: by_key(&reserve_state.lending_market)?,
+            lending_market_authority: by_key(&lending_market_authority)?,
+            collateral_source: find_token_account(accounts, &reserve_state.collateral_mint, &position_authority.key())?,
+            liquidity_destination: find_token_account(accounts, &reserve_state.liquidity_mint, manager)?,
+            position_authority,
+            token_program,
+            strategy_key,
+            reserve_state,
+        })
+    }
+    
+    // Redeem every cToken the position holds, returning the liquidity received
+    pub fn redeem(&self, position_index: u8, position_bump: u8) -> Result<u64> {
+        require!(
+            self.reserve_state.utilization_bps() <= MAX_LENDING_UTILIZATION_BPS,
+            ErrorCode::ProtocolHighUtilization
+        );
+        
+        let collateral_amount = token_balance(self.collateral_source)?;
+        require!(collateral_amount > 0, ErrorCode::InsufficientBalance);
+        
+        let mut data = vec![REDEEM_RESERVE_COLLATERAL_TAG];
+        data.extend_from_slice(&collateral_amount.to_le_bytes());
+        
+        let balance_before = token_balance(self.liquidity_destination)?;
+        invoke_signed(
+            &Instruction {
+                program_id: SOLEND_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(self.collateral_source.key(), false),
+                    AccountMeta::new(self.liquidity_destination.key(), false),
+                    AccountMeta::new(self.reserve.key(), false),
+                    AccountMeta::new(self.collateral_mint.key(), false),
+                    AccountMeta::new(self.liquidity_supply.key(), false),
+                    AccountMeta::new_readonly(self.lending_market.key(), false),
+                    AccountMeta::new_readonly(self.lending_market_authority.key(), false),
+                    AccountMeta::new_readonly(self.position_authority.key(), true),
+                    AccountMeta::new_readonly(self.token_program.key(), false),
+                ],
+                data,
+            },
+            &[
+                self.collateral_source.clone(),
+                self.liquidity_destination.clone(),
+                self.reserve.clone(),
+                self.collateral_mint.clone(),
+                self.liquidity_supply.clone(),
+                self.lending_market.clone(),
+                self.lending_market_authority.clone(),
+                self.position_authority.clone(),
+                self.token_program.clone(),
+                self.solend_program.clone(),
+            ],
+            &[&[b"position", self.strategy_key.as_ref(), &[position_index], &[position_bump]]],
+        )?;
+        
+        Ok(token_balance(self.liquidity_destination)?.saturating_sub(balance_before))
+    }
+}
+
+fn token_balance<'info>(info: &'info AccountInfo<'info>) -> Result<u64> {
+    Ok(Account::<TokenAccount>::try_from(info)?.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_decode_solend_reserve_utilization() {
+        let mut data = vec![0u8; RESERVE_LEN];
+        data[0] = 1;
+        data[171..179].copy_from_slice(&40_000_000u64.to_le_bytes());
+        data[179..195].copy_from_slice(&(960_000_000u128 * WAD).to_le_bytes());
+        
+        // 960 borrowed of 1000 total
+        let reserve = decode_solend_reserve(&data).unwrap();
+        assert_eq!(reserve.borrowed_amount, 960_000_000);
+        assert_eq!(reserve.utilization_bps(), 9600);
+        assert!(reserve.utilization_bps() > MAX_LENDING_UTILIZATION_BPS);
+        
+        data[171..179].copy_from_slice(&240_000_000u64.to_le_bytes());
+        assert_eq!(decode_solend_reserve(&data).unwrap().utilization_bps(), 8000);
+        
+        data[0] = 0;
+        assert!(decode_solend_reserve(&data).is_err());
+    }
+}
@@ -1,6 +1,18 @@
 use anchor_lang::prelude::*;
 use crate::error::ErrorCode;
 
+pub const FEE_COLLECTION_NUDGE_BPS: u64 = 9000; // Nudge collection at 90% of the fee cap
+pub const STALE_CUTOFF_HALFLIVES: i64 = 8;        // Past 8 halvings a strategy ranks at the bottom
+pub const RISK_ADJUSTED_PRECISION: u64 = 10_000;  // Fixed-point scale of yield/volatility ranking keys
+pub const CURRENT_PORTFOLIO_SCHEMA: u8 = 1;       // Portfolio layout version written by initialize and migrate
+pub const CURRENT_STRATEGY_SCHEMA: u8 = 4;        // Strategy layout version written by register_strategy
+pub const DEFAULT_RENT_BUFFER_LAMPORTS: u64 = 10_000_000; // Balance left behind by extractions unless configured
+pub const MAX_TRANSACTION_ACCOUNTS: u16 = 64;    // Account locks one transaction may take
+pub const RANKING_CYCLE_FIXED_ACCOUNTS: u16 = 4; // Portfolio, manager, program id, compute budget program
+// execute_ranking_cycle loads every strategy in one transaction, so the cap is
+// what is left of the account limit once its fixed accounts are counted
+pub const MAX_STRATEGIES_LIMIT: u16 = MAX_TRANSACTION_ACCOUNTS - RANKING_CYCLE_FIXED_ACCOUNTS;
+
 #[account]
 #[derive(Debug)]
 pub struct Portfolio {
@@ -14,9 +26,56 @@ pub struct Portfolio {
     pub emergency_pause: bool,              // 1 byte - Emergency stop flag
     pub performance_fee_bps: u16,           // 2 bytes - Performance fee in basis points
     pub bump: u8,                           // 1 byte - PDA bump seed
-    pub reserved: [u8; 31],                 // 31 bytes - Future expansion buffer
+    pub max_acceptable_il_bps: u16,         // 2 bytes - IL stop-loss threshold (0 = disabled)
+    pub fee_eligibility_seconds: i64,       // 8 bytes - Minimum strategy age before gains earn fees
+    pub accrued_performance_fees: u64,      // 8 bytes - Performance fees owed (lamports)
+    pub freshness_window_seconds: i64,      // 8 bytes - Max age of strategy metrics before stale
+    pub extraction_paused: bool,            // 1 byte - Blocks capital movement only
+    pub entry_price_policy: EntryPricePolicy, // 1 byte - Source of position entry prices
+    pub entry_price_tolerance_bps: u16,     // 2 bytes - Max manager/oracle entry price deviation
+    pub min_improvement_ratio_bps: u16,     // 2 bytes - Required improvement/fees ratio (10000 = 1x)
+    pub original_manager: Pubkey,           // 32 bytes - PDA seed key, fixed across manager handoffs
+    pub pending_manager: Pubkey,            // 32 bytes - Proposed new manager (default = none)
+    pub max_portfolio_leverage: u8,         // 1 byte - Fund-wide leverage cap (whole multiples)
+    pub longevity_bonus_bps_per_day: u16,   // 2 bytes - Score boost per day of strategy age
+    pub max_longevity_bonus_bps: u16,       // 2 bytes - Cap on the longevity boost (0 = disabled)
+    pub fee_treasury: Pubkey,               // 32 bytes - Destination of collected performance fees
+    pub fee_token_mint: Pubkey,             // 32 bytes - Fee denomination (default = SOL)
+    pub emergency_destination: Pubkey,      // 32 bytes - Pre-committed emergency sink (fixed at initialization)
+    pub reentry_cooldown_seconds: i64,      // 8 bytes - Wait before an exited protocol target can return
+    pub recent_exits: RecentExits,          // 320 bytes - Recently exited protocol targets
+    pub total_fees_allocated: u64,          // 8 bytes - Lifetime fee and incentive allocations
+    pub min_dispersion_threshold: u16,      // 2 bytes - Score spread required to rebalance (0 = disabled)
+    pub dispersion_gate_open: bool,         // 1 byte - Last ranking cycle's spread cleared the threshold
+    pub max_positions_per_strategy: u8,     // 1 byte - Cap on CapitalPositions per strategy
+    pub free_rebalances_per_period: u8,     // 1 byte - Fee-free rebalances allowed per period (0 = none)
+    pub free_rebalances_used: u8,           // 1 byte - Fee-free rebalances used in the current period
+    pub free_rebalance_period_seconds: i64, // 8 bytes - Length of the fee-free allowance period
+    pub free_rebalance_window_start: i64,   // 8 bytes - Start of the current allowance period
+    pub settlement_delay_seconds: [u32; 3], // 12 bytes - Per-category wait before new capital counts as earning
+    pub max_extraction_fee_bps: u16,        // 2 bytes - Max exit fees as a share of the amount (0 = disabled)
+    pub max_uncollected_fees: u64,          // 8 bytes - Cap on accrued, uncollected fees (0 = unlimited)
+    pub yield_weight_bps: u16,              // 2 bytes - Yield share of the score (all weights 0 = legacy)
+    pub balance_weight_bps: u16,            // 2 bytes - Balance share of the score
+    pub volatility_weight_bps: u16,         // 2 bytes - Inverse-volatility share of the score
+    pub staleness_halflife: i64,            // 8 bytes - Ranking score halves this often once stale (0 = disabled)
+    pub min_strategies_for_rebalance: u8,   // 1 byte - Strategies required before a ranking cycle (>= 2)
+    pub ranking_mode: RankingMode,          // 1 byte - Sort key used by ranking cycles
+    pub strategy_cooldown: i64,             // 8 bytes - Min seconds between rebalances touching one strategy (0 = disabled)
+    pub schema_version: u8,                 // 1 byte - Layout version (0 = created before versioning)
+    pub volatility_circuit_bps: u16,        // 2 bytes - Volatility jump that auto-pauses a strategy (0 = disabled)
+    pub max_yield_delta_bps: u16,           // 2 bytes - Largest accepted APY change per update (0 = disabled)
+    pub rent_buffer_lamports: u64,          // 8 bytes - Balance extractions leave behind (0 = default)
+    pub emergency_extract_count: u32,       // 4 bytes - Emergency strategy extractions performed
+    pub tvl_snapshot_interval: i64,         // 8 bytes - Minimum seconds between TVL snapshots
+    pub tie_break: TieBreak,                // 1 byte - Secondary ranking key for tied scores
+    pub reconcile_tolerance_bps: u16,       // 2 bytes - PnL drift reconcile_strategy accepts, of net principal
+    pub max_strategies: u16,                // 2 bytes - Registration cap keeping a ranking cycle within one transaction
+    pub denomination_enabled: bool,         // 1 byte - Scores must use the DenominationConfig price
+    pub undeployed_capital: u64,            // 8 bytes - Extracted capital not yet redistributed
+    pub reserved: [u8; 26],                 // 26 bytes - Future expansion buffer
 }
-// Total: 136 bytes
+// Total: 739 bytes
 
 #[account]
 #[derive(Debug)]
@@ -33,10 +92,26 @@ pub struct Strategy {
     pub total_deposits: u64,                // 8 bytes - Lifetime deposits tracking
     pub total_withdrawals: u64,             // 8 bytes - Lifetime withdrawals tracking
     pub creation_time: i64,                 // 8 bytes - Strategy creation timestamp
+    pub high_water_mark: u64,               // 8 bytes - Peak balance for performance fees
+    pub skip_count: u8,                     // 1 byte - Consecutive allocation cycles skipped
     pub bump: u8,                           // 1 byte - PDA bump seed
-    pub reserved: [u8; 23],                 // 23 bytes - Future expansion
+    pub compound_frequency_seconds: i64,    // 8 bytes - Minimum seconds between compounds
+    pub last_compounded: i64,               // 8 bytes - Last reward compounding timestamp
+    pub daily_withdrawal_limit_bps: u16,    // 2 bytes - Max share withdrawable per day (0 = unlimited)
+    pub withdrawn_in_window: u64,           // 8 bytes - Lamports withdrawn in the current window
+    pub withdrawal_window_start: i64,       // 8 bytes - Start of the current withdrawal window
+    pub position_count: u8,                 // 1 byte - CapitalPositions opened (next position index)
+    pub pending_balance: u64,               // 8 bytes - Redistributed capital not yet settled
+    pub pending_until: i64,                 // 8 bytes - When pending capital starts earning
+    pub last_rebalance: i64,                // 8 bytes - Last extraction or redistribution touching this strategy
+    pub schema_version: u8,                 // 1 byte - Layout version (0 = registered before versioning)
+    pub peak_balance: u64,                  // 8 bytes - Highest balance reported (drawdown reference)
+    pub max_drawdown_bps: u16,              // 2 bytes - Worst peak-to-trough balance drop seen
+    pub price_feeds: PriceFeedBinding,      // 65 bytes - Oracle feeds committed for the strategy's token legs
+    pub vault_accounted: u64,               // 8 bytes - Vault lamports already counted in current_balance
+    pub reserved: [u8; 2],                  // 2 bytes - Future expansion
 }
-// Total: ~144 bytes + protocol_type size
+// Total: 249 bytes + protocol_type size (max 100 bytes)
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
 pub enum ProtocolType {
@@ -60,6 +135,56 @@ pub enum ProtocolType {
     },  // 70 bytes total
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EntryPricePolicy {
+    ManagerProvided, // Entry prices taken as passed by the manager
+    OracleVerified,  // Entry prices derived from oracle feeds at position creation
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OracleSource {
+    Pyth,           // Pyth PriceUpdateV2 (pull oracle)
+    Switchboard,    // Switchboard On-Demand PullFeedAccountData
+}
+
+// Feeds a strategy's positions may be priced from, fixed when the strategy is
+// registered. A Pyth leg is its 32-byte feed_id, which every PriceUpdateV2
+// for that asset carries; a Switchboard leg is the pull feed account itself.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PriceFeedBinding {
+    pub source: OracleSource,               // 1 byte - Decoder both legs are read with
+    pub feed_a: [u8; 32],                   // 32 bytes - Token A feed (all zero = unbound)
+    pub feed_b: [u8; 32],                   // 32 bytes - Token B feed (zero for single-asset strategies)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum UnstakeMode {
+    Liquid,         // Swap through the liquidity pool, paid out immediately
+    Delayed,        // Order a ticket, claimable after unstake_delay epochs
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RankingMode {
+    Composite,      // Stored performance_score
+    RiskAdjusted,   // yield_rate / volatility_score, see Strategy::risk_adjusted_return
+}
+
+// Secondary sort key for strategies tied on ranking score. strategy_id bytes
+// always break whatever ties remain, so ranking is a total order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TieBreak {
+    StrategyId,        // No secondary key: tied scores share a percentile
+    OldestFirst,       // Earliest creation_time wins
+    HighestBalance,    // Largest current_balance wins
+    LowestVolatility,  // Smallest volatility_score wins
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RateType {
+    Apr, // Simple annual rate, compounded at the strategy's frequency before scoring
+    Apy, // Already-compounded annual yield, scored as is
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum StrategyStatus {
     Active,      // Normal operation, participates in rebalancing
@@ -82,11 +207,153 @@ pub struct CapitalPosition {
     pub accrued_fees: u64,                  // 8 bytes - Accumulated fees in position
     pub impermanent_loss: i64,              // 8 bytes - IL tracking (can be negative)
     pub bump: u8,                           // 1 byte - PDA bump seed
-    pub reserved: [u8; 15],                 // 15 bytes - Future expansion
+    pub position_index: u8,                 // 1 byte - PDA seed index within the strategy
+    pub reserved: [u8; 14],                 // 14 bytes - Future expansion
 }
 // Total: 145 bytes
 
+#[account]
+#[derive(Debug)]
+pub struct KeeperBudget {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub balance: u64,                       // 8 bytes - Lamports available to keepers
+    pub total_funded: u64,                  // 8 bytes - Lifetime lamports funded
+    pub total_drawn: u64,                   // 8 bytes - Lifetime lamports drawn
+    pub last_drawn: i64,                    // 8 bytes - Last drawdown timestamp
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 65 bytes
+
+#[account]
+#[derive(Debug)]
+pub struct KeeperRegistration {
+    pub portfolio: Pubkey,                  // 32 bytes - Portfolio whose budget the keeper draws on
+    pub keeper: Pubkey,                     // 32 bytes - Keeper signer allowed to draw
+    pub registered_at: i64,                 // 8 bytes - Registration timestamp
+    pub bump: u8,                           // 1 byte - PDA bump seed
+}
+// Total: 73 bytes
+
+#[account]
+#[derive(Debug)]
+pub struct RiskConfig {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub limits: RiskLimits,                 // 245 bytes - Allocation limits, fees, treasuries and fee feeds
+    pub last_updated: i64,                  // 8 bytes - Last update timestamp
+    pub bump: u8,                           // 1 byte - PDA bump seed
+    pub reserved: [u8; 15],                 // 15 bytes - Future expansion
+}
+// Total: 301 bytes
+
+#[account]
+#[derive(Debug)]
+pub struct RankingBuffer {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub cycle_started: i64,                 // 8 bytes - Timestamp of first submission
+    pub sorted: bool,                       // 1 byte - Entries sorted for finalization
+    pub written_back: u32,                  // 4 bytes - Ranks persisted this cycle
+    pub bump: u8,                           // 1 byte - PDA bump seed
+    pub entries: Vec<RankingEntry>,         // 4 + 66 * MAX_ENTRIES bytes
+}
+
+#[account]
+#[derive(Debug)]
+pub struct RedistributionSession {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub expected_total: u64,                // 8 bytes - Amount the session must allocate
+    pub accumulated_total: u64,             // 8 bytes - Allocated so far across chunks
+    pub fees_allocated: u64,                // 8 bytes - Fee allocations so far
+    pub opened_at: i64,                     // 8 bytes - begin_redistribution timestamp
+    pub active: bool,                       // 1 byte - Session open for chunks
+    pub fees_waived: bool,                  // 1 byte - Opened on a fee-free rebalance
+    pub bump: u8,                           // 1 byte - PDA bump seed
+    pub allocated_ids: Vec<Pubkey>,         // 4 + 32 * MAX_ALLOCATIONS bytes
+}
+
+// Append-only audit trail of redistribute_capital decisions; once full the
+// oldest entry is overwritten, and sequence numbers expose any gap
+#[account]
+#[derive(Debug)]
+pub struct AllocationLog {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub next_sequence: u64,                 // 8 bytes - Sequence number of the next entry
+    pub bump: u8,                           // 1 byte - PDA bump seed
+    pub entries: [AllocationLogEntry; 32],  // 832 bytes - Ring of the latest decisions
+}
+// Total: 873 bytes
+
+// Periodic total-value-locked snapshots for charting; once full the oldest
+// snapshot is overwritten
+#[account]
+#[derive(Debug)]
+pub struct TvlHistory {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub snapshot_count: u64,                // 8 bytes - Snapshots ever recorded
+    pub bump: u8,                           // 1 byte - PDA bump seed
+    pub entries: [TvlSnapshot; 64],         // 1024 bytes - Ring of the latest snapshots
+}
+// Total: 1065 bytes
+
+// Reference price for funds accounted in a stable unit rather than SOL; when
+// present, performance scoring sizes strategy balances by their stable value
+#[account]
+#[derive(Debug)]
+pub struct DenominationConfig {
+    pub portfolio: Pubkey,                  // 32 bytes - Owning portfolio
+    pub price_feed: Pubkey,                 // 32 bytes - SOL price feed in the stable unit
+    pub oracle_source: OracleSource,        // 1 byte - Decoder for price_feed
+    pub reference_price: u64,               // 8 bytes - Price (6 decimals) at which lamports score unchanged
+    pub last_updated: i64,                  // 8 bytes - Last update timestamp
+    pub bump: u8,                           // 1 byte - PDA bump seed
+    pub reserved: [u8; 16],                 // 16 bytes - Future expansion
+}
+// Total: 98 bytes
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct TvlSnapshot {
+    pub timestamp: i64,                     // 8 bytes - Block timestamp (0 = empty slot)
+    pub total_tvl: u64,                     // 8 bytes - Sum of strategy balances, in lamports
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct AllocationLogEntry {
+    pub sequence: u64,                      // 8 bytes - Position in the full history (1-based, 0 = empty)
+    pub total_allocated: u64,               // 8 bytes - Lamports deployed by the decision
+    pub strategy_count: u16,                // 2 bytes - Strategy allocations in the decision
+    pub timestamp: i64,                     // 8 bytes - Block timestamp
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExitedTarget {
+    pub target: Pubkey,                     // 32 bytes - Protocol target (pool, pair or validator)
+    pub exited_at: i64,                     // 8 bytes - Exit timestamp (0 = empty slot)
+}
+
+// Ring of the most recently exited protocol targets; the oldest slot is reused
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RecentExits {
+    pub entries: [ExitedTarget; 8],         // 320 bytes
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RankingEntry {
+    pub strategy_id: Pubkey,                // 32 bytes - Strategy identifier
+    pub performance_score: u64,             // 8 bytes - Score captured at submission
+    pub weight: u64,                        // 8 bytes - Balance captured at submission
+    pub tie_key: i128,                      // 16 bytes - TieBreak::key captured at submission
+    pub percentile: u8,                     // 1 byte - Capital-weighted percentile once sorted
+    pub written_back: bool,                 // 1 byte - Rank persisted to the strategy
+}
+
+// Basis-point split of the composite performance score
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScoringWeights {
+    pub yield_bps: u16,
+    pub balance_bps: u16,
+    pub volatility_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum PositionType {
     SingleAsset,
     LiquidityPair,
@@ -108,40 +375,1021 @@ pub enum AllocationType {
     PlatformFee,
 }
 
+impl ScoringWeights {
+    // Yield(45%) + Balance(35%) + InverseVolatility(20%)
+    pub const LEGACY: ScoringWeights = ScoringWeights {
+        yield_bps: 4500,
+        balance_bps: 3500,
+        volatility_bps: 2000,
+    };
+    
+    pub fn validate(&self) -> Result<()> {
+        let total = self.yield_bps as u32 + self.balance_bps as u32 + self.volatility_bps as u32;
+        require!(total == 10000, ErrorCode::InvalidScoringWeights);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct RiskLimits {
+    pub max_single_strategy_bps: u64,    // Maximum % of capital to single strategy
+    pub min_single_strategy_bps: u64,    // Minimum % threshold for allocation
+    pub platform_fee_bps: u64,           // Platform fee percentage
+    pub manager_fee_bps: u64,            // Manager fee percentage
+    pub risk_tolerance_bps: u64,         // Overall risk tolerance modifier
+    pub platform_treasury: Pubkey,       // Platform fee destination
+    pub manager_treasury: Pubkey,        // Manager fee destination
+    pub floor_risk_adjusted_to_minimum: bool, // Lift sub-minimum risk-adjusted allocations to the protocol minimum (else skip)
+    pub max_skips: u8,                   // Skipped cycles before a strategy gets a guaranteed slice (0 = off)
+    pub gas_cost_per_transfer: u64,      // Estimated lamports each allocation transfer costs (0 = ignore gas)
+    pub min_net_allocation: u64,         // Smallest allocation worth making after its gas cost
+    pub normalize_to_capital: bool,      // Rescale eligible strategies' shares so allocations sum to post-fee capital
+    pub max_lending_bps: u64,            // Ceiling on total capital allocated to StableLending strategies
+    pub max_farming_bps: u64,            // Ceiling on total capital allocated to YieldFarming strategies
+    pub max_staking_bps: u64,            // Ceiling on total capital allocated to LiquidStaking strategies
+    pub min_balance_lending: u64,        // Smallest StableLending balance or allocation, in lamports
+    pub min_balance_farming: u64,        // Smallest YieldFarming balance or allocation, in lamports
+    pub min_balance_staking: u64,        // Smallest LiquidStaking balance or allocation, in lamports
+    pub top_performer_percentile: u8,    // Lowest percentile_rank that receives rebalanced capital (50-99)
+    pub max_top_performers: u8,          // Most strategies that receive rebalanced capital (1-20)
+    pub dust_threshold_lamports: u64,    // Leftover capital above this is swept into top performers
+    pub sol_price_feed: Pubkey,          // Pyth SOL/USD feed for fee-token conversion (default = unset)
+    pub fee_token_price_feed: Pubkey,    // Pyth feed for the fee token in USD (default = unset)
+}
+
+impl RiskLimits {
+    // Same limits with platform and manager fees waived (fee-free rebalance)
+    pub fn with_fees_waived(&self) -> RiskLimits {
+        RiskLimits {
+            platform_fee_bps: 0,
+            manager_fee_bps: 0,
+            ..self.clone()
+        }
+    }
+    
+    // min_single_strategy_bps across all eligible strategies cannot exceed 100%
+    pub fn validate_minimum_feasible(&self, strategy_count: usize) -> Result<()> {
+        let required_bps = self.min_single_strategy_bps.saturating_mul(strategy_count as u64);
+        require!(required_bps <= 10000, ErrorCode::OverConstrainedAllocation);
+        Ok(())
+    }
+    
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_single_strategy_bps <= 10000
+                && self.min_single_strategy_bps <= self.max_single_strategy_bps
+                && self.risk_tolerance_bps <= 10000,
+            ErrorCode::InvalidRiskLimits
+        );
+        require!(
+            self.category_caps_bps().iter().all(|cap| *cap <= 10000),
+            ErrorCode::InvalidRiskLimits
+        );
+        require!(
+            (50..=99).contains(&self.top_performer_percentile)
+                && (1..=20).contains(&self.max_top_performers),
+            ErrorCode::InvalidRiskLimits
+        );
+        require!(self.platform_fee_bps + self.manager_fee_bps <= 1000, ErrorCode::InvalidRiskLimits); // Max 10% in fees
+        self.validate_treasuries()
+    }
+    
+    // Per-category exposure ceilings, indexed by ProtocolType::category_index
+    pub fn category_caps_bps(&self) -> [u64; ProtocolType::CATEGORY_COUNT] {
+        [self.max_lending_bps, self.max_farming_bps, self.max_staking_bps]
+    }
+    
+    // Smallest position worth opening in the protocol's category
+    pub fn minimum_balance(&self, protocol_type: &ProtocolType) -> u64 {
+        [self.min_balance_lending, self.min_balance_farming, self.min_balance_staking][protocol_type.category_index()]
+    }
+    
+    // Stored destination of a fee allocation; None for strategy allocations
+    pub fn treasury_for(&self, allocation_type: &AllocationType) -> Option<Pubkey> {
+        match allocation_type {
+            AllocationType::PlatformFee => Some(self.platform_treasury),
+            AllocationType::ManagerIncentive => Some(self.manager_treasury),
+            AllocationType::TopPerformer | AllocationType::RiskDiversification => None,
+        }
+    }
+    
+    pub fn validate_treasuries(&self) -> Result<()> {
+        require!(
+            self.platform_fee_bps == 0 || self.platform_treasury != Pubkey::default(),
+            ErrorCode::TreasuryNotConfigured
+        );
+        require!(
+            self.manager_fee_bps == 0 || self.manager_treasury != Pubkey::default(),
+            ErrorCode::TreasuryNotConfigured
+        );
+        Ok(())
+    }
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        RiskLimits {
+            max_single_strategy_bps: 4000,    // 40% max single strategy
+            min_single_strategy_bps: 100,     // 1% minimum allocation
+            platform_fee_bps: 50,             // 0.5% platform fee
+            manager_fee_bps: 150,              // 1.5% manager fee
+            risk_tolerance_bps: 8000,          // 80% risk tolerance (conservative)
+            platform_treasury: Pubkey::default(),
+            manager_treasury: Pubkey::default(),
+            floor_risk_adjusted_to_minimum: true,
+            max_skips: 3,
+            gas_cost_per_transfer: 0,
+            min_net_allocation: 0,
+            normalize_to_capital: false,
+            max_lending_bps: 10000,
+            max_farming_bps: 10000,
+            max_staking_bps: 10000,
+            min_balance_lending: 100_000_000,    // 0.1 SOL for lending protocols
+            min_balance_farming: 500_000_000,    // 0.5 SOL for LP positions (gas + slippage)
+            min_balance_staking: 1_000_000_000,  // 1 SOL for staking (epoch requirements)
+            top_performer_percentile: 75,        // Top quartile
+            max_top_performers: 5,               // Top 5 for diversification
+            dust_threshold_lamports: 1_000_000,  // 0.001 SOL
+            sol_price_feed: Pubkey::default(),
+            fee_token_price_feed: Pubkey::default(),
+        }
+    }
+}
+
+impl TieBreak {
+    // Tie key of a strategy; the higher key ranks above an equal score
+    pub fn key(&self, strategy: &Strategy) -> i128 {
+        match self {
+            TieBreak::StrategyId => 0,
+            TieBreak::OldestFirst => -(strategy.creation_time as i128),
+            TieBreak::HighestBalance => strategy.current_balance as i128,
+            TieBreak::LowestVolatility => -(strategy.volatility_score as i128),
+        }
+    }
+}
+
+impl StrategyStatus {
+    // Active <-> Paused freely; anything may be deprecated, but never revived
+    pub fn can_transition_to(&self, new_status: StrategyStatus) -> bool {
+        *self != new_status && *self != StrategyStatus::Deprecated
+    }
+}
+
+impl AllocationType {
+    // Fee allocations pay treasuries; the rest credit a Strategy account
+    pub fn targets_strategy(&self) -> bool {
+        matches!(self, AllocationType::TopPerformer | AllocationType::RiskDiversification)
+    }
+}
+
 impl Portfolio {
-    pub const MAX_SIZE: usize = 8 + 136;
+    pub const MAX_SIZE: usize = 8 + 739;
+    
+    // Older layouts must run migrate_portfolio first; newer ones need a newer program
+    pub fn schema_supported(&self) -> bool {
+        self.schema_version == CURRENT_PORTFOLIO_SCHEMA
+    }
+    
+    // Fields a pre-versioning layout lacks read 0 once the account is grown.
+    // Where 0 is outside a field's valid range it can only mean "absent", so
+    // those take initialize_portfolio's default; fields where 0 is a valid
+    // setting keep it.
+    pub fn fill_migrated_defaults(&mut self) {
+        if self.freshness_window_seconds == 0 {
+            self.freshness_window_seconds = 86_400;
+        }
+        if self.entry_price_tolerance_bps == 0 {
+            self.entry_price_tolerance_bps = 100;
+        }
+        if self.min_improvement_ratio_bps == 0 {
+            self.min_improvement_ratio_bps = 10000;
+        }
+        if self.max_portfolio_leverage == 0 {
+            self.max_portfolio_leverage = 10;
+        }
+        if self.max_positions_per_strategy == 0 {
+            self.max_positions_per_strategy = 4;
+        }
+        if self.free_rebalance_period_seconds == 0 {
+            self.free_rebalance_period_seconds = 2_592_000;
+        }
+        if self.min_strategies_for_rebalance == 0 {
+            self.min_strategies_for_rebalance = 2;
+        }
+        if self.tvl_snapshot_interval == 0 {
+            self.tvl_snapshot_interval = 3_600;
+        }
+        if self.max_strategies == 0 {
+            self.max_strategies = MAX_STRATEGIES_LIMIT;
+        }
+        let weights = self.scoring_weights();
+        self.yield_weight_bps = weights.yield_bps;
+        self.balance_weight_bps = weights.balance_bps;
+        self.volatility_weight_bps = weights.volatility_bps;
+    }
     
     pub fn validate_rebalance_threshold(threshold: u8) -> Result<()> {
         require!(threshold >= 1 && threshold <= 50, ErrorCode::InvalidRebalanceThreshold);
         Ok(())
     }
     
-    pub fn can_rebalance(&self, current_time: i64) -> bool {
-        !self.emergency_pause && 
-        current_time >= self.last_rebalance.saturating_add(self.min_rebalance_interval)
+    pub fn can_rebalance(&self, current_time: i64) -> bool {
+        !self.emergency_pause && 
+        current_time >= self.last_rebalance.saturating_add(self.min_rebalance_interval)
+    }
+    
+    // Optimistic concurrency for keepers: a caller that names the last_rebalance
+    // it observed fails cleanly if another cycle has run since
+    pub fn require_last_rebalance(&self, expected_last_rebalance: Option<i64>) -> Result<()> {
+        if let Some(expected) = expected_last_rebalance {
+            require!(expected == self.last_rebalance, ErrorCode::StaleRebalanceState);
+        }
+        Ok(())
+    }
+    
+    pub fn validate_min_interval(interval: i64) -> Result<()> {
+        require!(interval >= 3600 && interval <= 86400, ErrorCode::InvalidRebalanceInterval);
+        Ok(())
+    }
+    
+    pub fn validate_il_threshold(threshold_bps: u16) -> Result<()> {
+        require!(threshold_bps <= 10000, ErrorCode::InvalidIlThreshold);
+        Ok(())
+    }
+    
+    pub fn validate_freshness_window(seconds: i64) -> Result<()> {
+        require!((60..=2_592_000).contains(&seconds), ErrorCode::InvalidFreshnessWindow); // 1 minute to 30 days
+        Ok(())
+    }
+    
+    pub fn validate_min_strategies_for_rebalance(min_strategies: u8) -> Result<()> {
+        require!(min_strategies >= 2, ErrorCode::InvalidMinStrategies);
+        Ok(())
+    }
+    
+    // Portfolios created before the field existed read 0; ranking never makes sense below 2
+    pub fn has_enough_strategies_for_rebalance(&self) -> bool {
+        self.total_strategies >= self.min_strategies_for_rebalance.max(2) as u32
+    }
+    
+    pub fn validate_max_strategies(max_strategies: u16) -> Result<()> {
+        require!((1..=MAX_STRATEGIES_LIMIT).contains(&max_strategies), ErrorCode::InvalidMaxStrategies);
+        Ok(())
+    }
+    
+    // Emergency funds can't be committed to nowhere or back into the portfolio
+    pub fn validate_emergency_destination(destination: &Pubkey, portfolio: &Pubkey) -> Result<()> {
+        require!(
+            *destination != Pubkey::default() && destination != portfolio,
+            ErrorCode::UnauthorizedEmergencyDestination
+        );
+        Ok(())
+    }
+    
+    // Portfolios created before the cap existed read 0 and get the hard limit
+    pub fn strategy_cap(&self) -> u32 {
+        match self.max_strategies {
+            0 => MAX_STRATEGIES_LIMIT as u32,
+            cap => cap as u32,
+        }
+    }
+    
+    pub fn require_strategy_capacity(&self) -> Result<()> {
+        require!(self.total_strategies < self.strategy_cap(), ErrorCode::MaxStrategiesReached);
+        Ok(())
+    }
+    
+    pub fn validate_reconcile_tolerance(tolerance_bps: u16) -> Result<()> {
+        require!(tolerance_bps <= 10000, ErrorCode::InvalidReconcileTolerance);
+        Ok(())
+    }
+    
+    pub fn validate_volatility_circuit(delta_bps: u16) -> Result<()> {
+        require!(delta_bps <= 10000, ErrorCode::InvalidVolatilityCircuit);
+        Ok(())
+    }
+    
+    // Balance every extraction leaves in place; portfolios created before the
+    // field existed read 0 and keep the original 0.01 SOL
+    pub fn rent_buffer(&self) -> u64 {
+        if self.rent_buffer_lamports == 0 {
+            DEFAULT_RENT_BUFFER_LAMPORTS
+        } else {
+            self.rent_buffer_lamports
+        }
+    }
+    
+    pub fn extractable_balance(&self, balance: u64) -> u64 {
+        balance.saturating_sub(self.rent_buffer())
+    }
+    
+    // An emergency extraction must name the count it will become, so a stale
+    // or mistyped call fails instead of firing
+    pub fn record_emergency_extraction(&mut self, confirmation_nonce: u32) -> Result<u32> {
+        let next = self.emergency_extract_count
+            .checked_add(1)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        require!(confirmation_nonce == next, ErrorCode::InvalidEmergencyNonce);
+        self.emergency_extract_count = next;
+        Ok(next)
+    }
+    
+    // Rent-exempt minimum of a CapitalPosition, the account extracted capital sits behind
+    pub fn rent_exempt_floor(rent: &Rent) -> u64 {
+        rent.minimum_balance(CapitalPosition::MAX_SIZE)
+    }
+    
+    // Never below the rent-exempt floor, and at most 1 SOL
+    pub fn validate_rent_buffer(lamports: u64, rent_exempt_floor: u64) -> Result<()> {
+        require!(
+            lamports >= rent_exempt_floor && lamports <= 1_000_000_000,
+            ErrorCode::InvalidRentBuffer
+        );
+        Ok(())
+    }
+    
+    // Bounded by the largest yield a strategy may ever report
+    pub fn validate_max_yield_delta(delta_bps: u16) -> Result<()> {
+        require!(delta_bps as u64 <= 50000, ErrorCode::InvalidYieldDelta);
+        Ok(())
+    }
+    
+    pub fn validate_tvl_snapshot_interval(seconds: i64) -> Result<()> {
+        require!((60..=2_592_000).contains(&seconds), ErrorCode::InvalidTvlSnapshotInterval); // 1 minute to 30 days
+        Ok(())
+    }
+    
+    pub fn validate_strategy_cooldown(seconds: i64) -> Result<()> {
+        require!((0..=2_592_000).contains(&seconds), ErrorCode::InvalidStrategyCooldown); // Max 30 days
+        Ok(())
+    }
+    
+    pub fn validate_staleness_halflife(seconds: i64) -> Result<()> {
+        require!(
+            seconds == 0 || (3_600..=2_592_000).contains(&seconds),
+            ErrorCode::InvalidStalenessHalflife
+        ); // Disabled, or 1 hour to 30 days
+        Ok(())
+    }
+    
+    // Key a strategy ranks by this cycle, per ranking_mode and decayed for
+    // staleness; the stored performance_score is untouched
+    pub fn ranking_score(&self, strategy: &Strategy, current_time: i64) -> u64 {
+        let key = match self.ranking_mode {
+            RankingMode::Composite => strategy.performance_score,
+            RankingMode::RiskAdjusted => strategy.risk_adjusted_return(),
+        };
+        strategy.decay_for_staleness(key, current_time, self.freshness_window_seconds, self.staleness_halflife)
+    }
+    
+    // Percentile to store for a strategy whose ranking earned `percentile`;
+    // strategies past the staleness cutoff always land in the bottom percentile
+    pub fn ranking_percentile(&self, strategy: &Strategy, percentile: u8, current_time: i64) -> u8 {
+        if strategy.past_staleness_cutoff(current_time, self.freshness_window_seconds, self.staleness_halflife) {
+            0
+        } else {
+            percentile
+        }
+    }
+    
+    pub fn validate_fee_eligibility_seconds(seconds: i64) -> Result<()> {
+        require!((0..=31_536_000).contains(&seconds), ErrorCode::InvalidFeeEligibilityWindow); // Max 1 year
+        Ok(())
+    }
+    
+    // Adds a fee to the uncollected ledger, refusing once it would pass the cap
+    // so the liability cannot grow until the fees are collected
+    pub fn checked_fee_accrual(accrued: u64, fee: u64, max_uncollected: u64) -> Result<u64> {
+        let total = accrued.checked_add(fee).ok_or(ErrorCode::BalanceOverflow)?;
+        require!(max_uncollected == 0 || total <= max_uncollected, ErrorCode::UncollectedFeeCapReached);
+        Ok(total)
+    }
+    
+    pub fn fee_collection_due(accrued: u64, max_uncollected: u64) -> bool {
+        max_uncollected > 0
+            && accrued as u128 * 10000 >= max_uncollected as u128 * FEE_COLLECTION_NUDGE_BPS as u128
+    }
+    
+    pub fn validate_entry_price_tolerance(tolerance_bps: u16) -> Result<()> {
+        require!((1..=1000).contains(&tolerance_bps), ErrorCode::InvalidPriceTolerance); // Max 10%
+        Ok(())
+    }
+    
+    pub fn validate_dispersion_threshold(threshold: u16) -> Result<()> {
+        require!(threshold <= 10000, ErrorCode::InvalidDispersionThreshold);
+        Ok(())
+    }
+    
+    // Capital may move unless the dispersion trigger is on and the last ranking
+    // cycle found scores too tightly clustered
+    pub fn rebalance_permitted(&self) -> bool {
+        self.min_dispersion_threshold == 0 || self.dispersion_gate_open
+    }
+    
+    pub fn validate_max_positions(max_positions: u8) -> Result<()> {
+        require!((1..=16).contains(&max_positions), ErrorCode::InvalidPositionCap);
+        Ok(())
+    }
+    
+    pub fn validate_free_rebalance_allowance(per_period: u8, period_seconds: i64) -> Result<()> {
+        require!(per_period <= 31, ErrorCode::InvalidFreeRebalanceAllowance);
+        require!((86_400..=31_536_000).contains(&period_seconds), ErrorCode::InvalidFreeRebalanceAllowance); // 1 day to 1 year
+        Ok(())
+    }
+    
+    fn free_rebalances_used_at(&self, current_time: i64) -> u8 {
+        let window_end = self.free_rebalance_window_start.saturating_add(self.free_rebalance_period_seconds);
+        if current_time >= window_end { 0 } else { self.free_rebalances_used }
+    }
+    
+    pub fn free_rebalance_available(&self, current_time: i64) -> bool {
+        self.free_rebalances_used_at(current_time) < self.free_rebalances_per_period
+    }
+    
+    // Capital extracted back to the manager becomes available to redistribute
+    pub fn release_capital(&mut self, amount: u64) -> Result<()> {
+        self.undeployed_capital = self.undeployed_capital
+            .checked_add(amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        Ok(())
+    }
+    
+    // Redistribution can only credit strategies with capital that was released
+    pub fn deploy_capital(&mut self, amount: u64) -> Result<()> {
+        self.undeployed_capital = self.undeployed_capital
+            .checked_sub(amount)
+            .ok_or(ErrorCode::ExceedsUndeployedCapital)?;
+        Ok(())
+    }
+    
+    // Uses one fee-free rebalance if any remain this period, starting a new
+    // period once the current one has elapsed. Returns whether fees are waived.
+    pub fn consume_free_rebalance(&mut self, current_time: i64) -> bool {
+        let window_end = self.free_rebalance_window_start.saturating_add(self.free_rebalance_period_seconds);
+        if current_time >= window_end {
+            self.free_rebalance_window_start = current_time;
+            self.free_rebalances_used = 0;
+        }
+        
+        if self.free_rebalances_used >= self.free_rebalances_per_period {
+            return false;
+        }
+        self.free_rebalances_used += 1;
+        true
+    }
+    
+    pub fn validate_settlement_delays(delays: &[u32; ProtocolType::CATEGORY_COUNT]) -> Result<()> {
+        require!(delays.iter().all(|&d| d <= 604_800), ErrorCode::InvalidSettlementDelay); // Max 7 days
+        Ok(())
+    }
+    
+    pub fn settlement_delay_for(&self, protocol_type: &ProtocolType) -> i64 {
+        self.settlement_delay_seconds[protocol_type.category_index()] as i64
+    }
+    
+    // Portfolios created before configurable weights read zeroed reserved
+    // bytes here and keep scoring with the legacy split
+    pub fn scoring_weights(&self) -> ScoringWeights {
+        if self.yield_weight_bps == 0 && self.balance_weight_bps == 0 && self.volatility_weight_bps == 0 {
+            return ScoringWeights::LEGACY;
+        }
+        ScoringWeights {
+            yield_bps: self.yield_weight_bps,
+            balance_bps: self.balance_weight_bps,
+            volatility_bps: self.volatility_weight_bps,
+        }
+    }
+    
+    pub fn set_scoring_weights(&mut self, weights: ScoringWeights) -> Result<()> {
+        weights.validate()?;
+        self.yield_weight_bps = weights.yield_bps;
+        self.balance_weight_bps = weights.balance_bps;
+        self.volatility_weight_bps = weights.volatility_bps;
+        Ok(())
+    }
+    
+    pub fn validate_max_extraction_fee(max_fee_bps: u16) -> Result<()> {
+        require!(max_fee_bps <= 10000, ErrorCode::InvalidExtractionFeeCap);
+        Ok(())
+    }
+    
+    pub fn has_pending_manager(&self) -> bool {
+        self.pending_manager != Pubkey::default()
+    }
+    
+    pub fn validate_max_leverage(max_leverage: u8) -> Result<()> {
+        require!((1..=10).contains(&max_leverage), ErrorCode::InvalidLeverageCap); // Per-strategy limit is 10x
+        Ok(())
+    }
+    
+    pub fn leverage_within_cap(leverage_bps: u64, max_portfolio_leverage: u8) -> bool {
+        leverage_bps <= max_portfolio_leverage as u64 * 10000
+    }
+    
+    // Fund-wide leverage mandate, independent of per-protocol validation
+    pub fn validate_leverage_policy(&self, protocol_type: &ProtocolType) -> Result<()> {
+        require!(
+            Self::leverage_within_cap(protocol_type.leverage_bps(), self.max_portfolio_leverage),
+            ErrorCode::LeverageExceedsPolicy
+        );
+        Ok(())
+    }
+    
+    pub fn validate_longevity_bonus(bps_per_day: u16, max_bonus_bps: u16) -> Result<()> {
+        require!(bps_per_day <= 100, ErrorCode::InvalidLongevityBonus);
+        require!(max_bonus_bps <= 1000, ErrorCode::InvalidLongevityBonus); // Max 10% of the score scale
+        Ok(())
+    }
+    
+    pub fn validate_reentry_cooldown(seconds: i64) -> Result<()> {
+        require!((0..=31_536_000).contains(&seconds), ErrorCode::InvalidReentryCooldown); // Max 1 year
+        Ok(())
+    }
+    
+    pub fn validate_improvement_ratio(ratio_bps: u16) -> Result<()> {
+        require!(ratio_bps >= 10000, ErrorCode::InvalidImprovementRatio); // Never weaker than 1x
+        Ok(())
+    }
+}
+
+impl KeeperBudget {
+    pub const MAX_SIZE: usize = 8 + 65;
+}
+
+impl KeeperRegistration {
+    pub const MAX_SIZE: usize = 8 + 73;
+}
+
+impl PriceFeedBinding {
+    pub const UNBOUND: PriceFeedBinding = PriceFeedBinding {
+        source: OracleSource::Pyth,
+        feed_a: [0u8; 32],
+        feed_b: [0u8; 32],
+    };
+    
+    pub fn is_bound(&self) -> bool {
+        self.feed_a != [0u8; 32]
+    }
+    
+    // Token A always needs a feed; token B only for liquidity-pair strategies
+    pub fn validate_for(&self, protocol_type: &ProtocolType) -> Result<()> {
+        require!(self.is_bound(), ErrorCode::InvalidPriceFeedBinding);
+        if protocol_type.compatible_position_type() == PositionType::LiquidityPair {
+            require!(self.feed_b != [0u8; 32], ErrorCode::InvalidPriceFeedBinding);
+        }
+        Ok(())
+    }
+}
+
+impl RiskConfig {
+    pub const MAX_SIZE: usize = 8 + 301;
+}
+
+impl RecentExits {
+    pub fn record(&mut self, target: Pubkey, exited_at: i64) {
+        let slot = self.entries
+            .iter()
+            .position(|e| e.target == target)
+            .unwrap_or_else(|| {
+                self.entries
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, e)| e.exited_at)
+                    .map(|(index, _)| index)
+                    .unwrap_or(0)
+            });
+        self.entries[slot] = ExitedTarget { target, exited_at };
+    }
+    
+    pub fn in_cooldown(&self, target: &Pubkey, current_time: i64, cooldown_seconds: i64) -> bool {
+        cooldown_seconds > 0 && self.entries.iter().any(|e| {
+            e.exited_at > 0
+                && e.target == *target
+                && current_time < e.exited_at.saturating_add(cooldown_seconds)
+        })
+    }
+    
+    // Manager override: forget a target so it can be registered again immediately
+    pub fn clear(&mut self, target: &Pubkey) -> bool {
+        match self.entries.iter_mut().find(|e| e.exited_at > 0 && e.target == *target) {
+            Some(entry) => {
+                *entry = ExitedTarget::default();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl RankingBuffer {
+    pub const MAX_ENTRIES: usize = MAX_STRATEGIES_LIMIT as usize; // One entry per registered strategy
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 1 + 4 + 1 + 4 + 66 * Self::MAX_ENTRIES;
+    
+    // A cycle is complete once every submitted score has been written back
+    pub fn is_complete(&self) -> bool {
+        !self.entries.is_empty() && self.written_back as usize == self.entries.len()
+    }
+}
+
+impl AllocationLog {
+    pub const CAPACITY: usize = 32;
+    pub const MAX_SIZE: usize = 8 + 873;
+    
+    // Write the next entry into the oldest slot and return its sequence number
+    pub fn append(&mut self, total_allocated: u64, strategy_count: u16, timestamp: i64) -> u64 {
+        let sequence = self.next_sequence.max(1);
+        self.entries[((sequence - 1) % Self::CAPACITY as u64) as usize] = AllocationLogEntry {
+            sequence,
+            total_allocated,
+            strategy_count,
+            timestamp,
+        };
+        self.next_sequence = sequence + 1;
+        sequence
+    }
+}
+
+impl TvlHistory {
+    pub const CAPACITY: usize = 64;
+    pub const MAX_SIZE: usize = 8 + 1065;
+    
+    pub fn latest_snapshot(&self) -> Option<&TvlSnapshot> {
+        self.latest(1).into_iter().next()
+    }
+    
+    // Snapshots closer together than the interval are refused to bound write costs
+    pub fn record(&mut self, total_tvl: u64, timestamp: i64, min_interval: i64) -> Result<()> {
+        if let Some(last) = self.latest_snapshot() {
+            require!(
+                timestamp >= last.timestamp.saturating_add(min_interval),
+                ErrorCode::TvlSnapshotTooSoon
+            );
+        }
+        self.entries[(self.snapshot_count % Self::CAPACITY as u64) as usize] = TvlSnapshot {
+            timestamp,
+            total_tvl,
+        };
+        self.snapshot_count += 1;
+        Ok(())
+    }
+    
+    // Up to `count` most recent snapshots, newest first
+    pub fn latest(&self, count: usize) -> Vec<&TvlSnapshot> {
+        let stored = self.snapshot_count.min(Self::CAPACITY as u64) as usize;
+        (1..=count.min(stored))
+            .map(|back| {
+                let index = (self.snapshot_count - back as u64) % Self::CAPACITY as u64;
+                &self.entries[index as usize]
+            })
+            .collect()
+    }
+}
+
+impl DenominationConfig {
+    pub const MAX_SIZE: usize = 8 + 98;
+    
+    // Fresh price from the configured feed; a quote older than
+    // MAX_PRICE_AGE_SECONDS fails with StalePrice rather than scoring on it
+    pub fn read_price(&self, feed: &AccountInfo, current_time: i64) -> Result<u64> {
+        require!(feed.key() == self.price_feed, ErrorCode::InvalidOracleAccount);
+        let (price, published) = crate::oracle::read_oracle_quote_from(feed, self.oracle_source)?;
+        require!(current_time - published <= crate::oracle::MAX_PRICE_AGE_SECONDS, ErrorCode::StalePrice);
+        require!(price > 0, ErrorCode::InvalidPrice);
+        Ok(price)
+    }
+    
+    // Lamports re-expressed as lamports-at-reference-price, so the scorer's
+    // 0.1-100 SOL balance range keeps its meaning in the stable unit.
+    // Saturates, since the scorer caps balances far below u64::MAX.
+    pub fn scoring_balance(&self, balance: u64, price: u64) -> u64 {
+        if self.reference_price == 0 {
+            return balance;
+        }
+        (balance as u128 * price as u128 / self.reference_price as u128).min(u64::MAX as u128) as u64
+    }
+}
+
+impl RedistributionSession {
+    pub const MAX_ALLOCATIONS: usize = 200;
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 4 + 32 * Self::MAX_ALLOCATIONS;
+    
+    // Adds a validated chunk, refusing destinations already paid this session
+    // and any total beyond the amount the session was opened for
+    pub fn record_chunk(&mut self, allocations: &[CapitalAllocation], chunk_total: u64) -> Result<()> {
+        require!(
+            self.allocated_ids.len() + allocations.len() <= Self::MAX_ALLOCATIONS,
+            ErrorCode::TooManyStrategies
+        );
+        for allocation in allocations.iter() {
+            require!(!self.allocated_ids.contains(&allocation.strategy_id), ErrorCode::DuplicateStrategy);
+            self.allocated_ids.push(allocation.strategy_id);
+        }
+        
+        self.accumulated_total = self.accumulated_total
+            .checked_add(chunk_total)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        require!(self.accumulated_total <= self.expected_total, ErrorCode::RedistributionTotalMismatch);
+        Ok(())
+    }
+}
+
+impl Strategy {
+    pub const MAX_SIZE: usize = 8 + 349; // Account for largest protocol type
+    pub const WITHDRAWAL_WINDOW_SECONDS: i64 = 86_400;
+    
+    // Older layouts must run migrate_strategy first; newer ones need a newer program
+    pub fn schema_supported(&self) -> bool {
+        self.schema_version == CURRENT_STRATEGY_SCHEMA
+    }
+    
+    // Binds the strategy's price feeds; a binding, once made, is never replaced
+    pub fn bind_price_feeds(&mut self, price_feeds: PriceFeedBinding) -> Result<()> {
+        require!(!self.price_feeds.is_bound(), ErrorCode::InvalidPriceFeedBinding);
+        price_feeds.validate_for(&self.protocol_type)?;
+        self.price_feeds = price_feeds;
+        Ok(())
+    }
+    
+    // Fields an older layout lacks read 0 once the account is grown; those
+    // tracking the balance start from it and the rest take register_strategy's
+    // default where 0 isn't a valid setting
+    pub fn fill_migrated_defaults(&mut self) {
+        if self.high_water_mark == 0 {
+            self.high_water_mark = self.current_balance;
+        }
+        if self.peak_balance == 0 {
+            self.peak_balance = self.current_balance;
+        }
+        if self.compound_frequency_seconds == 0 {
+            self.compound_frequency_seconds = self.protocol_type.default_compound_frequency();
+        }
+    }
+    
+    // Deserialize a strategy passed via remaining_accounts and verify it is the
+    // program-owned PDA [b"strategy", portfolio, strategy_id] of this portfolio
+    pub fn load_checked<'info>(
+        account_info: &'info AccountInfo<'info>,
+        portfolio: &Pubkey,
+    ) -> Result<Account<'info, Strategy>> {
+        let strategy: Account<'info, Strategy> = Account::try_from(account_info)
+            .map_err(|_| error!(ErrorCode::StrategyNotFound))?;
+        
+        let expected_address = Pubkey::create_program_address(
+            &[
+                b"strategy",
+                portfolio.as_ref(),
+                strategy.strategy_id.as_ref(),
+                &[strategy.bump],
+            ],
+            &crate::ID,
+        ).map_err(|_| error!(ErrorCode::StrategyNotFound))?;
+        require_keys_eq!(expected_address, account_info.key(), ErrorCode::StrategyNotFound);
+        require!(strategy.schema_supported(), ErrorCode::UnsupportedSchemaVersion);
+        
+        Ok(strategy)
+    }
+    
+    pub fn validate_yield_rate(rate: u64) -> Result<()> {
+        require!(rate <= 50000, ErrorCode::ExcessiveYieldRate);
+        Ok(())
+    }
+    
+    pub fn validate_balance_update(new_balance: u64) -> Result<()> {
+        require!(new_balance < u64::MAX / 1000, ErrorCode::BalanceOverflow);
+        Ok(())
+    }
+    
+    pub fn validate_volatility_score(score: u32) -> Result<()> {
+        require!(score <= 10000, ErrorCode::InvalidVolatilityScore);
+        Ok(())
+    }
+    
+    pub fn seconds_since_update(&self, current_time: i64) -> i64 {
+        current_time.saturating_sub(self.last_updated).max(0)
+    }
+    
+    pub fn is_stale(&self, current_time: i64, freshness_window_seconds: i64) -> bool {
+        self.seconds_since_update(current_time) > freshness_window_seconds
+    }
+    
+    // SHARPE-STYLE RANKING KEY
+    // yield_rate per unit of volatility, scaled by RISK_ADJUSTED_PRECISION. A
+    // volatility_score of 0 is treated as 1 so the ratio stays finite.
+    pub fn risk_adjusted_return(&self) -> u64 {
+        let volatility = self.volatility_score.max(1) as u128;
+        (self.yield_rate as u128 * RISK_ADJUSTED_PRECISION as u128 / volatility)
+            .min(u64::MAX as u128) as u64
+    }
+    
+    // STALENESS DECAY
+    // Full score inside the freshness window, then halved every halflife seconds,
+    // falling linearly between halvings. A halflife of 0 disables decay.
+    pub fn decay_for_staleness(&self, score: u64, current_time: i64, freshness_window_seconds: i64, halflife: i64) -> u64 {
+        if halflife <= 0 {
+            return score;
+        }
+        let overdue = self.seconds_since_update(current_time) - freshness_window_seconds;
+        if overdue <= 0 {
+            return score;
+        }
+        
+        let halvings = overdue / halflife;
+        if halvings >= STALE_CUTOFF_HALFLIVES {
+            return 0;
+        }
+        let base = score >> halvings;
+        let into_halving = (overdue % halflife) as u128;
+        base - ((base / 2) as u128 * into_halving / halflife as u128) as u64
+    }
+    
+    pub fn past_staleness_cutoff(&self, current_time: i64, freshness_window_seconds: i64, halflife: i64) -> bool {
+        halflife > 0
+            && self.seconds_since_update(current_time) - freshness_window_seconds
+                >= halflife.saturating_mul(STALE_CUTOFF_HALFLIVES)
+    }
+    
+    // Gains only count toward performance fees once the strategy has been
+    // active for the portfolio's eligibility window
+    pub fn is_fee_eligible(&self, current_time: i64, fee_eligibility_seconds: i64) -> bool {
+        self.status == StrategyStatus::Active &&
+        current_time.saturating_sub(self.creation_time) >= fee_eligibility_seconds
+    }
+    
+    // Fee owed on the gain above the high-water mark; nothing on flat or
+    // losing balances, so a drawdown's recovery is never charged twice
+    pub fn fee_eligible_gain(&self, fee_bps: u16) -> u64 {
+        let gain = self.current_balance.saturating_sub(self.high_water_mark);
+        (gain as u128 * fee_bps as u128 / 10000u128) as u64
+    }
+    
+    // The mark only ever moves up to a new peak balance
+    pub fn advance_high_water_mark(&mut self) {
+        self.high_water_mark = self.high_water_mark.max(self.current_balance);
+    }
+    
+    // Drop of the current balance below its running peak, in basis points
+    pub fn current_drawdown_bps(&self) -> u16 {
+        if self.peak_balance == 0 {
+            return 0;
+        }
+        let drop = self.peak_balance.saturating_sub(self.current_balance) as u128;
+        (drop * 10000 / self.peak_balance as u128) as u16
+    }
+    
+    // Advance the running peak, or record the drawdown below it if it is the worst yet
+    pub fn track_drawdown(&mut self) {
+        if self.current_balance >= self.peak_balance {
+            self.peak_balance = self.current_balance;
+        } else {
+            self.max_drawdown_bps = self.max_drawdown_bps.max(self.current_drawdown_bps());
+        }
+    }
+    
+    pub fn validate_compound_frequency(seconds: i64) -> Result<()> {
+        require!((60..=2_592_000).contains(&seconds), ErrorCode::InvalidCompoundFrequency); // 1 minute to 30 days
+        Ok(())
+    }
+    
+    pub fn can_compound(&self, current_time: i64) -> bool {
+        current_time >= self.last_compounded.saturating_add(self.compound_frequency_seconds)
+    }
+    
+    // Lamports that reached the vault without passing through deposit_capital
+    // or a previous compound: the protocol's rewards not yet in current_balance
+    pub fn unaccounted_vault_rewards(&self, vault_lamports: u64) -> u64 {
+        vault_lamports.saturating_sub(self.vault_accounted)
+    }
+    
+    // A strategy may only receive capital while active and with a currently-valid
+    // protocol config (e.g. its reserve has not since been deprecated)
+    pub fn validate_as_destination(&self) -> Result<()> {
+        require!(self.status == StrategyStatus::Active, ErrorCode::InvalidDestinationStrategy);
+        
+        if let Err(error) = self.protocol_type.validate() {
+            msg!("Destination strategy {} failed protocol validation: {}", self.strategy_id, error);
+            return Err(ErrorCode::InvalidDestinationStrategy.into());
+        }
+        
+        Ok(())
+    }
+    
+    pub fn validate_withdrawal_limit(limit_bps: u16) -> Result<()> {
+        require!(limit_bps <= 10000, ErrorCode::InvalidWithdrawalLimit);
+        Ok(())
     }
     
-    pub fn validate_min_interval(interval: i64) -> Result<()> {
-        require!(interval >= 3600 && interval <= 86400, ErrorCode::InvalidRebalanceInterval);
+    // ROLLING DAILY WITHDRAWAL LIMIT
+    // The limit is a share of the balance held when the window opened, i.e. the
+    // balance before this withdrawal plus everything already withdrawn in the window
+    pub fn record_withdrawal(&mut self, amount: u64, balance_before: u64, current_time: i64) -> Result<()> {
+        if current_time >= self.withdrawal_window_start.saturating_add(Self::WITHDRAWAL_WINDOW_SECONDS) {
+            self.withdrawal_window_start = current_time;
+            self.withdrawn_in_window = 0;
+        }
+        
+        let withdrawn = self.withdrawn_in_window
+            .checked_add(amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        
+        if self.daily_withdrawal_limit_bps > 0 {
+            let window_balance = balance_before as u128 + self.withdrawn_in_window as u128;
+            let allowed = window_balance * self.daily_withdrawal_limit_bps as u128 / 10000;
+            require!(withdrawn as u128 <= allowed, ErrorCode::WithdrawalRateLimitExceeded);
+        }
+        
+        self.withdrawn_in_window = withdrawn;
         Ok(())
     }
-}
-
-impl Strategy {
-    pub const MAX_SIZE: usize = 8 + 200; // Account for largest protocol type
     
-    pub fn validate_yield_rate(rate: u64) -> Result<()> {
-        require!(rate <= 50000, ErrorCode::ExcessiveYieldRate);
+    // Track consecutive allocation cycles in which the strategy was skipped
+    pub fn record_allocation_outcome(&mut self, allocated: bool) {
+        self.skip_count = if allocated { 0 } else { self.skip_count.saturating_add(1) };
+    }
+    
+    // Claim the next position index, bounded so strategy-level aggregation over
+    // every position still fits in one transaction's account set
+    pub fn reserve_position_slot(&mut self, max_positions: u8) -> Result<u8> {
+        require!(self.position_count < max_positions, ErrorCode::MaxPositionsReached);
+        let index = self.position_count;
+        self.position_count += 1;
+        Ok(index)
+    }
+    
+    // Credit deposited or redistributed capital. The high-water mark rises with
+    // the deposit so new capital is never mistaken for a performance gain.
+    pub fn credit_allocation(&mut self, amount: u64) -> Result<()> {
+        self.current_balance = self.current_balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        self.total_deposits = self.total_deposits
+            .checked_add(amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        self.high_water_mark = self.high_water_mark
+            .checked_add(amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
         Ok(())
     }
     
-    pub fn validate_balance_update(new_balance: u64) -> Result<()> {
-        require!(new_balance < u64::MAX / 1000, ErrorCode::BalanceOverflow);
+    // A volatility report rising more than circuit_bps above the last one
+    pub fn volatility_circuit_tripped(&self, new_volatility: u32, circuit_bps: u16) -> bool {
+        circuit_bps > 0 && new_volatility > self.volatility_score.saturating_add(circuit_bps as u32)
+    }
+    
+    // An APY moving more than max_delta_bps either way from the stored one;
+    // a strategy that has never reported (yield_rate 0) has nothing to compare against
+    pub fn yield_jump_suspicious(&self, new_yield_apy: u64, max_delta_bps: u16) -> bool {
+        max_delta_bps > 0
+            && self.yield_rate > 0
+            && new_yield_apy.abs_diff(self.yield_rate) > max_delta_bps as u64
+    }
+    
+    // ANTI-THRASHING COOLDOWN
+    // A strategy extracted from or redistributed into within the portfolio's
+    // strategy_cooldown is left alone by the next rebalance
+    pub fn in_rebalance_cooldown(&self, current_time: i64, strategy_cooldown: i64) -> bool {
+        strategy_cooldown > 0
+            && self.last_rebalance > 0
+            && current_time.saturating_sub(self.last_rebalance) < strategy_cooldown
+    }
+    
+    // Mark newly redistributed capital as pending until the protocol's settlement
+    // delay passes. A further credit extends the wait for the combined amount.
+    pub fn add_pending_capital(&mut self, amount: u64, current_time: i64, delay_seconds: i64) -> Result<()> {
+        if delay_seconds <= 0 {
+            return Ok(());
+        }
+        if current_time >= self.pending_until {
+            self.pending_balance = 0;
+        }
+        self.pending_balance = self.pending_balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::BalanceOverflow)?;
+        self.pending_until = current_time.saturating_add(delay_seconds);
         Ok(())
     }
     
-    pub fn validate_volatility_score(score: u32) -> Result<()> {
-        require!(score <= 10000, ErrorCode::InvalidVolatilityScore);
+    // Balance counted as deployed and earning: unsettled capital is excluded
+    pub fn settled_balance(&self, balance: u64, current_time: i64) -> u64 {
+        if current_time < self.pending_until {
+            balance.saturating_sub(self.pending_balance)
+        } else {
+            balance
+        }
+    }
+    
+    // Scale the high-water mark with the balance left after an extraction so an
+    // underwater strategy keeps its loss carryforward instead of resetting it
+    pub fn rescale_high_water_mark(&mut self, balance_before: u64) -> Result<()> {
+        if balance_before == 0 {
+            return Ok(());
+        }
+        
+        let scaled_mark = (self.high_water_mark as u128)
+            .checked_mul(self.current_balance as u128)
+            .ok_or(ErrorCode::BalanceOverflow)?
+            / balance_before as u128;
+        
+        self.high_water_mark = scaled_mark as u64;
         Ok(())
     }
 }
@@ -178,6 +1426,15 @@ impl ProtocolType {
         }
     }
     
+    // The only position shape each protocol's extraction path can unwind
+    pub fn compatible_position_type(&self) -> PositionType {
+        match self {
+            ProtocolType::StableLending { .. } => PositionType::SingleAsset,
+            ProtocolType::YieldFarming { .. } => PositionType::LiquidityPair,
+            ProtocolType::LiquidStaking { .. } => PositionType::StakedPosition,
+        }
+    }
+    
     pub fn get_protocol_name(&self) -> &'static str {
         match self {
             ProtocolType::StableLending { .. } => "Stable Lending",
@@ -186,6 +1443,17 @@ impl ProtocolType {
         }
     }
     
+    pub const CATEGORY_COUNT: usize = 3;
+    
+    // Stable index of the protocol category for per-category aggregation
+    pub fn category_index(&self) -> usize {
+        match self {
+            ProtocolType::StableLending { .. } => 0,
+            ProtocolType::YieldFarming { .. } => 1,
+            ProtocolType::LiquidStaking { .. } => 2,
+        }
+    }
+    
     pub fn get_expected_tokens(&self) -> Vec<Pubkey> {
         match self {
             ProtocolType::StableLending { reserve_address, .. } => {
@@ -200,21 +1468,36 @@ impl ProtocolType {
         }
     }
     
-    pub fn validate_balance_constraints(&self, balance: u64) -> Result<()> {
+    // The external venue a strategy deploys into, used to track exits
+    pub fn target_id(&self) -> Pubkey {
         match self {
-            ProtocolType::StableLending { .. } => {
-                // Minimum 0.1 SOL for lending protocols
-                require!(balance >= 100_000_000, ErrorCode::InsufficientBalance);
-            },
-            ProtocolType::YieldFarming { .. } => {
-                // Minimum 0.5 SOL for LP positions (gas + slippage)
-                require!(balance >= 500_000_000, ErrorCode::InsufficientBalance);
-            },
-            ProtocolType::LiquidStaking { .. } => {
-                // Minimum 1 SOL for staking (epoch requirements)
-                require!(balance >= 1_000_000_000, ErrorCode::InsufficientBalance);
-            },
+            ProtocolType::StableLending { pool_id, .. } => *pool_id,
+            ProtocolType::YieldFarming { pair_id, .. } => *pair_id,
+            ProtocolType::LiquidStaking { validator_id, .. } => *validator_id,
+        }
+    }
+    
+    // Position leverage in basis points (10000 = 1x). Every current protocol is
+    // unleveraged; a leveraged variant (e.g. perpetual futures) reports its own.
+    pub fn leverage_bps(&self) -> u64 {
+        match self {
+            ProtocolType::StableLending { .. }
+            | ProtocolType::YieldFarming { .. }
+            | ProtocolType::LiquidStaking { .. } => 10000,
+        }
+    }
+    
+    // Compounding cadence suited to how each protocol accrues rewards
+    pub fn default_compound_frequency(&self) -> i64 {
+        match self {
+            ProtocolType::StableLending { .. } => 3_600,   // Interest accrues continuously: hourly
+            ProtocolType::YieldFarming { .. } => 86_400,   // Farm rewards per daily reward period
+            ProtocolType::LiquidStaking { .. } => 172_800, // Staking rewards per ~2 day epoch
         }
+    }
+    
+    pub fn validate_balance_constraints(&self, balance: u64, risk_limits: &RiskLimits) -> Result<()> {
+        require!(balance >= risk_limits.minimum_balance(self), ErrorCode::InsufficientBalance);
         Ok(())
     }
 }
@@ -222,12 +1505,39 @@ impl ProtocolType {
 impl CapitalPosition {
     pub const MAX_SIZE: usize = 8 + 145;
     
+    // Deserialize a CapitalPosition passed via remaining_accounts and verify it is
+    // one of the position PDAs of the given strategy account
+    pub fn load_checked<'info>(
+        account_info: &'info AccountInfo<'info>,
+        strategy: &Pubkey,
+    ) -> Result<Account<'info, CapitalPosition>> {
+        let position: Account<'info, CapitalPosition> = Account::try_from(account_info)
+            .map_err(|_| error!(ErrorCode::StrategyNotFound))?;
+        
+        let expected_address = Pubkey::create_program_address(
+            &[b"position", strategy.as_ref(), &[position.position_index], &[position.bump]],
+            &crate::ID,
+        ).map_err(|_| error!(ErrorCode::StrategyNotFound))?;
+        require_keys_eq!(expected_address, account_info.key(), ErrorCode::StrategyNotFound);
+        
+        Ok(position)
+    }
+    
+    // A position only makes sense under the protocol whose extraction path unwinds it
+    pub fn require_compatible(&self, protocol_type: &ProtocolType) -> Result<()> {
+        require!(
+            self.position_type == protocol_type.compatible_position_type(),
+            ErrorCode::PositionTypeMismatch
+        );
+        Ok(())
+    }
+    
     // AMM-SAFE WITHDRAWAL CALCULATIONS
     pub fn calculate_lp_withdrawal_amounts(
         &self,
         current_reserve_a: u64,
         current_reserve_b: u64,
-        total_lp_supply: u64,
+        total_lp_supply: u128,
         lp_tokens_to_burn: u64,
     ) -> Result<(u64, u64)> {
         // Validate invariant preservation
@@ -236,11 +1546,11 @@ impl CapitalPosition {
         
         // SAFE: Use 128-bit arithmetic to prevent overflow
         let token_a_out = (lp_tokens_to_burn as u128 * current_reserve_a as u128)
-            .checked_div(total_lp_supply as u128)
+            .checked_div(total_lp_supply)
             .ok_or(ErrorCode::BalanceOverflow)? as u64;
             
         let token_b_out = (lp_tokens_to_burn as u128 * current_reserve_b as u128)
-            .checked_div(total_lp_supply as u128)
+            .checked_div(total_lp_supply)
             .ok_or(ErrorCode::BalanceOverflow)? as u64;
         
         // VERIFY: x*y=k invariant maintained
@@ -266,31 +1576,29 @@ impl CapitalPosition {
     ) -> Result<i64> {
         // Validate price freshness (max 60 seconds old)
         let current_time = Clock::get()?.unix_timestamp;
-        require!(current_time - price_timestamp <= 60, ErrorCode::StalePrice);
+        require!(current_time - price_timestamp <= crate::oracle::MAX_PRICE_AGE_SECONDS, ErrorCode::StalePrice);
         
         // Prevent division by zero
         require!(self.entry_price_b > 0 && current_price_b > 0, ErrorCode::InvalidPrice);
         
-        // Calculate price ratio changes using safe arithmetic
-        let entry_ratio = (self.entry_price_a as u128 * 1_000_000u128) / self.entry_price_b as u128;
-        let current_ratio = (current_price_a as u128 * 1_000_000u128) / current_price_b as u128;
-        
-        // IL = 2 * sqrt(price_ratio) / (1 + price_ratio) - 1
-        let ratio_change = current_ratio.checked_div(entry_ratio)
-            .ok_or(ErrorCode::BalanceOverflow)?;
-            
-        // Use integer square root for safety
-        let sqrt_ratio = sqrt_u128(ratio_change * 1_000_000u128);
-        let il_numerator = 2u128 * sqrt_ratio;
-        let il_denominator = 1_000_000u128 + ratio_change;
-        
-        let il_ratio = il_numerator.checked_div(il_denominator)
-            .ok_or(ErrorCode::BalanceOverflow)?;
-            
-        // Convert to signed percentage (can be negative for gains)
-        let il_percentage = (il_ratio as i64) - 1_000_000i64; // Subtract 100%
+        // Calculate price ratios in 1e6 fixed point using safe arithmetic
+        let entry_ratio = (self.entry_price_a as u128 * IL_PRECISION) / self.entry_price_b as u128;
+        let current_ratio = (current_price_a as u128 * IL_PRECISION) / current_price_b as u128;
         
-        Ok(il_percentage)
+        impermanent_loss_from_ratios(entry_ratio, current_ratio)
+    }
+    
+    // Recorded IL as a loss in basis points (impermanent_loss is 1e6 fixed point, negative = loss)
+    pub fn impermanent_loss_bps(&self) -> u64 {
+        if self.impermanent_loss < 0 {
+            self.impermanent_loss.unsigned_abs() / 100
+        } else {
+            0
+        }
+    }
+    
+    pub fn exceeds_il_threshold(&self, max_acceptable_il_bps: u16) -> bool {
+        max_acceptable_il_bps > 0 && self.impermanent_loss_bps() > max_acceptable_il_bps as u64
     }
     
     // PROTOCOL-AWARE WITHDRAWAL VALIDATION
@@ -321,17 +1629,784 @@ impl CapitalPosition {
 }
 
 // MATHEMATICAL SAFETY HELPERS
+const IL_PRECISION: u128 = 1_000_000;
+
+// IMPERMANENT LOSS
+// IL = 2 * sqrt(r) / (1 + r) - 1 with r = current_ratio / entry_ratio, all in 1e6
+// fixed point. 0 = no loss, negative = loss relative to holding (never positive).
+pub fn impermanent_loss_from_ratios(entry_ratio: u128, current_ratio: u128) -> Result<i64> {
+    require!(entry_ratio > 0, ErrorCode::InvalidPrice);
+    
+    // Scale before dividing so r keeps its six decimals
+    let r = current_ratio
+        .checked_mul(IL_PRECISION)
+        .ok_or(ErrorCode::BalanceOverflow)?
+        / entry_ratio;
+    let sqrt_r = sqrt_u128(r.checked_mul(IL_PRECISION).ok_or(ErrorCode::BalanceOverflow)?);
+    
+    let lp_value = (2 * sqrt_r)
+        .checked_mul(IL_PRECISION)
+        .ok_or(ErrorCode::BalanceOverflow)?
+        / (IL_PRECISION + r);
+    
+    // AM-GM bounds lp_value by IL_PRECISION; rounding never reports a gain
+    Ok(lp_value.min(IL_PRECISION) as i64 - IL_PRECISION as i64)
+}
+
+// floor(sqrt(x)) by Newton's method, starting from a power of two at or above
+// the root so the iterates decrease monotonically onto the floor
 fn sqrt_u128(x: u128) -> u128 {
-    if x == 0 { return 0; }
-    let mut sqrt = x / 2;
-    let mut temp = (sqrt + x / sqrt) / 2;
-    while temp < sqrt {
-        sqrt = temp;
-        temp = (sqrt + x / sqrt) / 2;
+    if x < 2 { return x; }
+    let bits = 128 - x.leading_zeros();
+    let mut sqrt = 1u128 << bits.div_ceil(2);
+    loop {
+        let next = (sqrt + x / sqrt) / 2;
+        if next >= sqrt {
+            return sqrt;
+        }
+        sqrt = next;
     }
-    sqrt
 }
 
     fn sqrt_u64(x: u64) -> Result<u64> {
         Ok(sqrt_u128(x as u128) as u64)
     }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    
+    pub(crate) fn test_strategy(current_balance: u64, high_water_mark: u64) -> Strategy {
+        Strategy {
+            strategy_id: Pubkey::new_unique(),
+            protocol_type: ProtocolType::StableLending {
+                pool_id: Pubkey::new_unique(),
+                utilization: 5000,
+                reserve_address: Pubkey::new_unique(),
+            },
+            current_balance,
+            yield_rate: 0,
+            volatility_score: 5000,
+            performance_score: 0,
+            percentile_rank: 50,
+            last_updated: 0,
+            status: StrategyStatus::Active,
+            total_deposits: high_water_mark,
+            total_withdrawals: 0,
+            creation_time: 0,
+            high_water_mark,
+            skip_count: 0,
+            bump: 255,
+            compound_frequency_seconds: 86_400,
+            last_compounded: 0,
+            daily_withdrawal_limit_bps: 0,
+            withdrawn_in_window: 0,
+            withdrawal_window_start: 0,
+            position_count: 0,
+            pending_balance: 0,
+            pending_until: 0,
+            last_rebalance: 0,
+            schema_version: CURRENT_STRATEGY_SCHEMA,
+            peak_balance: current_balance,
+            max_drawdown_bps: 0,
+            price_feeds: PriceFeedBinding::UNBOUND,
+            vault_accounted: 0,
+            reserved: [0u8; 2],
+        }
+    }
+    
+    // All-zero account image, as a freshly allocated Portfolio would decode
+    fn zeroed_portfolio() -> Portfolio {
+        Portfolio::deserialize(&mut &[0u8; Portfolio::MAX_SIZE - 8][..]).unwrap()
+    }
+    
+    pub(crate) fn test_position(impermanent_loss: i64) -> CapitalPosition {
+        CapitalPosition {
+            strategy_id: Pubkey::new_unique(),
+            token_a_amount: 1_000_000_000,
+            token_b_amount: 1_000_000_000,
+            lp_tokens: 1_000_000,
+            platform_controlled_lp: 1_000_000,
+            position_type: PositionType::LiquidityPair,
+            entry_price_a: 1_000_000,
+            entry_price_b: 1_000_000,
+            last_rebalance: 0,
+            accrued_fees: 0,
+            impermanent_loss,
+            bump: 255,
+            position_index: 0,
+            reserved: [0u8; 14],
+        }
+    }
+    
+    #[test]
+    fn test_il_threshold_breach() {
+        // -5.72% IL recorded in 1e6 fixed point
+        let position = test_position(-57_200);
+        assert_eq!(position.impermanent_loss_bps(), 572);
+        
+        assert!(position.exceeds_il_threshold(500));   // 5% stop-loss crossed
+        assert!(!position.exceeds_il_threshold(1000)); // 10% stop-loss not reached
+        assert!(!position.exceeds_il_threshold(0));    // disabled
+        
+        // Gains never trip the stop-loss
+        assert!(!test_position(20_000).exceeds_il_threshold(1));
+    }
+    
+    #[test]
+    fn test_extraction_preserves_high_water_mark_proportionally() {
+        // Underwater strategy: mark at 10 SOL, balance fell to 8 SOL
+        let mut strategy = test_strategy(8_000_000_000, 10_000_000_000);
+        
+        // Extract half of the remaining balance
+        let balance_before = strategy.current_balance;
+        strategy.current_balance = 4_000_000_000;
+        strategy.rescale_high_water_mark(balance_before).unwrap();
+        
+        // Mark keeps the same 25% loss carryforward on the smaller balance
+        assert_eq!(strategy.high_water_mark, 5_000_000_000);
+        assert!(strategy.current_balance < strategy.high_water_mark);
+    }
+    
+    #[test]
+    fn test_fee_eligibility_window() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.creation_time = 1_000;
+        
+        assert!(!strategy.is_fee_eligible(1_000, 86_400));
+        assert!(!strategy.is_fee_eligible(87_399, 86_400));
+        assert!(strategy.is_fee_eligible(87_400, 86_400));
+        
+        strategy.status = StrategyStatus::Paused;
+        assert!(!strategy.is_fee_eligible(87_400, 86_400));
+    }
+    
+    #[test]
+    fn test_stale_scores_decay_for_ranking() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.performance_score = 8000;
+        strategy.last_updated = 0;
+        let (window, halflife) = (86_400, 43_200);
+        
+        // Fresh, disabled, one halving, and halfway to the next
+        assert_eq!(strategy.decay_for_staleness(8000, 86_400, window, halflife), 8000);
+        assert_eq!(strategy.decay_for_staleness(8000, 1_000_000, window, 0), 8000);
+        assert_eq!(strategy.decay_for_staleness(8000, 129_600, window, halflife), 4000);
+        assert_eq!(strategy.decay_for_staleness(8000, 151_200, window, halflife), 3000);
+        assert_eq!(strategy.performance_score, 8000);
+        
+        // Past the cutoff the strategy scores zero and ranks at the bottom
+        let cutoff = window + halflife * STALE_CUTOFF_HALFLIVES;
+        assert!(!strategy.past_staleness_cutoff(cutoff - 1, window, halflife));
+        assert!(strategy.past_staleness_cutoff(cutoff, window, halflife));
+        assert_eq!(strategy.decay_for_staleness(8000, cutoff, window, halflife), 0);
+    }
+    
+    #[test]
+    fn test_risk_adjusted_ranking_key() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.yield_rate = 1200;
+        strategy.volatility_score = 3000;
+        assert_eq!(strategy.risk_adjusted_return(), 4000);
+        
+        // Lower yield at much lower risk ranks higher
+        let mut steady = test_strategy(1_000_000_000, 1_000_000_000);
+        steady.yield_rate = 600;
+        steady.volatility_score = 500;
+        assert!(steady.risk_adjusted_return() > strategy.risk_adjusted_return());
+        
+        // Zero volatility is floored at 1 rather than dividing by zero
+        steady.volatility_score = 0;
+        assert_eq!(steady.risk_adjusted_return(), 6_000_000);
+    }
+    
+    #[test]
+    fn test_strategy_rebalance_cooldown() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        
+        // Never rebalanced, or cooldown disabled
+        assert!(!strategy.in_rebalance_cooldown(1_000, 86_400));
+        strategy.last_rebalance = 1_000;
+        assert!(!strategy.in_rebalance_cooldown(1_000, 0));
+        
+        assert!(strategy.in_rebalance_cooldown(87_399, 86_400));
+        assert!(!strategy.in_rebalance_cooldown(87_400, 86_400));
+    }
+    
+    #[test]
+    fn test_staleness_against_freshness_window() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.last_updated = 10_000;
+        
+        assert_eq!(strategy.seconds_since_update(13_600), 3_600);
+        assert!(!strategy.is_stale(13_600, 3_600));
+        assert!(strategy.is_stale(13_601, 3_600));
+        
+        // Clock skew never reports negative age
+        assert_eq!(strategy.seconds_since_update(9_000), 0);
+    }
+    
+    #[test]
+    fn test_skip_count_resets_on_allocation() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.record_allocation_outcome(false);
+        strategy.record_allocation_outcome(false);
+        assert_eq!(strategy.skip_count, 2);
+        
+        strategy.record_allocation_outcome(true);
+        assert_eq!(strategy.skip_count, 0);
+    }
+    
+    #[test]
+    fn test_compound_frequency_window() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        assert!(strategy.can_compound(1_700_000_000)); // Never compounded
+        
+        strategy.last_compounded = 1_700_000_000;
+        assert!(!strategy.can_compound(1_700_000_000 + 86_399));
+        assert!(strategy.can_compound(1_700_000_000 + 86_400));
+    }
+    
+    #[test]
+    fn test_only_unaccounted_vault_lamports_compound() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.vault_accounted = 1_000_000_000;
+        assert_eq!(strategy.unaccounted_vault_rewards(1_000_000_000), 0);
+        assert_eq!(strategy.unaccounted_vault_rewards(1_025_000_000), 25_000_000);
+        
+        // A vault drained below what was accounted has nothing to compound
+        assert_eq!(strategy.unaccounted_vault_rewards(900_000_000), 0);
+    }
+    
+    #[test]
+    fn test_full_extraction_zeroes_high_water_mark() {
+        let mut strategy = test_strategy(8_000_000_000, 10_000_000_000);
+        strategy.current_balance = 0;
+        strategy.rescale_high_water_mark(8_000_000_000).unwrap();
+        assert_eq!(strategy.high_water_mark, 0);
+    }
+    
+    #[test]
+    fn test_leverage_policy_cap() {
+        // A 10x position passes per-protocol limits but not a 5x fund mandate
+        assert!(!Portfolio::leverage_within_cap(100_000, 5));
+        assert!(Portfolio::leverage_within_cap(50_000, 5));
+        assert!(Portfolio::leverage_within_cap(100_000, 10));
+        
+        // Current protocols are unleveraged and fit any valid cap
+        let lending = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        assert!(Portfolio::leverage_within_cap(lending.leverage_bps(), 1));
+        
+        assert!(Portfolio::validate_max_leverage(0).is_err());
+        assert!(Portfolio::validate_max_leverage(11).is_err());
+    }
+    
+    #[test]
+    fn test_daily_withdrawal_limit_resets_after_window() {
+        let mut strategy = test_strategy(10_000_000_000, 10_000_000_000);
+        strategy.daily_withdrawal_limit_bps = 2000; // 20% per day
+        let day_start = 1_700_000_000;
+        
+        // Up to the limit across several withdrawals in the same day
+        strategy.record_withdrawal(1_500_000_000, 10_000_000_000, day_start).unwrap();
+        strategy.record_withdrawal(500_000_000, 8_500_000_000, day_start + 3_600).unwrap();
+        assert_eq!(strategy.withdrawn_in_window, 2_000_000_000);
+        
+        // Any further same-day withdrawal is rejected
+        assert!(strategy.record_withdrawal(1, 8_000_000_000, day_start + 7_200).is_err());
+        assert_eq!(strategy.withdrawn_in_window, 2_000_000_000);
+        
+        // A new window resets the accumulator against the current balance
+        strategy.record_withdrawal(1_600_000_000, 8_000_000_000, day_start + 86_400).unwrap();
+        assert_eq!(strategy.withdrawal_window_start, day_start + 86_400);
+        assert_eq!(strategy.withdrawn_in_window, 1_600_000_000);
+    }
+    
+    #[test]
+    fn test_invalid_destination_rejected() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        assert!(strategy.validate_as_destination().is_ok());
+        
+        // Protocol config no longer valid: reserve cleared
+        strategy.protocol_type = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::default(),
+        };
+        assert!(strategy.validate_as_destination().is_err());
+        
+        // A valid config on a deprecated strategy is rejected too
+        let mut deprecated = test_strategy(1_000_000_000, 1_000_000_000);
+        deprecated.status = StrategyStatus::Deprecated;
+        assert!(deprecated.validate_as_destination().is_err());
+    }
+    
+    #[test]
+    fn test_reentry_cooldown_for_exited_targets() {
+        let mut exits = RecentExits::default();
+        let target = Pubkey::new_unique();
+        let exited_at = 1_700_000_000;
+        let cooldown = 604_800;
+        
+        assert!(!exits.in_cooldown(&target, exited_at, cooldown));
+        exits.record(target, exited_at);
+        
+        // Re-registering the exited target inside the cooldown is blocked
+        assert!(exits.in_cooldown(&target, exited_at + 86_400, cooldown));
+        assert!(!exits.in_cooldown(&Pubkey::new_unique(), exited_at + 86_400, cooldown));
+        assert!(!exits.in_cooldown(&target, exited_at + cooldown, cooldown));
+        assert!(!exits.in_cooldown(&target, exited_at + 86_400, 0));
+        
+        // Override clears the record
+        assert!(exits.clear(&target));
+        assert!(!exits.in_cooldown(&target, exited_at + 86_400, cooldown));
+        assert!(!exits.clear(&target));
+    }
+    
+    #[test]
+    fn test_recent_exits_reuse_oldest_slot() {
+        let mut exits = RecentExits::default();
+        let first = Pubkey::new_unique();
+        exits.record(first, 1_000);
+        for i in 0..8 {
+            exits.record(Pubkey::new_unique(), 2_000 + i);
+        }
+        
+        // The ring is full, so the oldest exit was overwritten
+        assert!(!exits.in_cooldown(&first, 3_000, 604_800));
+        assert!(exits.entries.iter().all(|e| e.exited_at >= 2_000));
+    }
+    
+    #[test]
+    fn test_credit_allocation_raises_balance_deposits_and_mark() {
+        let mut strategy = test_strategy(2_000_000_000, 2_000_000_000);
+        let deposits_before = strategy.total_deposits;
+        
+        strategy.credit_allocation(500_000_000).unwrap();
+        
+        assert_eq!(strategy.current_balance, 2_500_000_000);
+        assert_eq!(strategy.total_deposits, deposits_before + 500_000_000);
+        assert_eq!(strategy.high_water_mark, 2_500_000_000);
+        assert!(strategy.credit_allocation(u64::MAX).is_err());
+    }
+    
+    #[test]
+    fn test_position_slots_stop_at_cap() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        
+        for expected in 0..3u8 {
+            assert_eq!(strategy.reserve_position_slot(3).unwrap(), expected);
+        }
+        assert!(strategy.reserve_position_slot(3).is_err());
+        assert_eq!(strategy.position_count, 3);
+    }
+    
+    #[test]
+    fn test_pending_capital_excluded_until_settled() {
+        let mut strategy = test_strategy(2_000_000_000, 2_000_000_000);
+        let now = 1_700_000_000;
+        
+        strategy.credit_allocation(3_000_000_000).unwrap();
+        strategy.add_pending_capital(3_000_000_000, now, 86_400).unwrap();
+        
+        // Only the original 2 SOL scores until the delay passes
+        assert_eq!(strategy.settled_balance(strategy.current_balance, now + 3_600), 2_000_000_000);
+        assert_eq!(strategy.settled_balance(strategy.current_balance, now + 86_400), 5_000_000_000);
+        
+        // No delay configured: capital counts immediately
+        let mut immediate = test_strategy(2_000_000_000, 2_000_000_000);
+        immediate.add_pending_capital(3_000_000_000, now, 0).unwrap();
+        assert_eq!(immediate.settled_balance(5_000_000_000, now), 5_000_000_000);
+    }
+    
+    #[test]
+    fn test_status_transitions() {
+        assert!(StrategyStatus::Active.can_transition_to(StrategyStatus::Paused));
+        assert!(StrategyStatus::Paused.can_transition_to(StrategyStatus::Active));
+        assert!(StrategyStatus::Paused.can_transition_to(StrategyStatus::Deprecated));
+        assert!(StrategyStatus::Active.can_transition_to(StrategyStatus::Deprecated));
+        
+        // Deprecation is one-way
+        assert!(!StrategyStatus::Deprecated.can_transition_to(StrategyStatus::Active));
+        assert!(!StrategyStatus::Deprecated.can_transition_to(StrategyStatus::Paused));
+        assert!(!StrategyStatus::Active.can_transition_to(StrategyStatus::Active));
+    }
+    
+    #[test]
+    fn test_sqrt_u128_is_exact_floor() {
+        let check = |x: u128| {
+            let root = sqrt_u128(x);
+            assert!(root * root <= x, "sqrt({}) = {} too high", x, root);
+            assert!(
+                (root + 1).checked_mul(root + 1).is_none_or(|next| next > x),
+                "sqrt({}) = {} too low", x, root
+            );
+        };
+        
+        for x in 0..=10_000u128 {
+            check(x);
+        }
+        for shift in 0..128 {
+            let power = 1u128 << shift;
+            check(power - 1);
+            check(power);
+            check(power + 1);
+        }
+        check(u128::MAX);
+        check(u128::MAX - 1);
+        
+        assert_eq!(sqrt_u128(1), 1);
+        assert_eq!(sqrt_u128(3), 1);
+        assert_eq!(sqrt_u128(4), 2);
+        assert_eq!(sqrt_u128(u128::MAX), u64::MAX as u128);
+    }
+    
+    #[test]
+    fn test_impermanent_loss_formula() {
+        let entry = 1_000_000u128;
+        
+        // r = 1: no price divergence, no loss
+        assert_eq!(impermanent_loss_from_ratios(entry, 1_000_000).unwrap(), 0);
+        
+        // r = 4 and r = 0.25 are symmetric: 2 * 2 / 5 - 1 = -20%
+        assert_eq!(impermanent_loss_from_ratios(entry, 4_000_000).unwrap(), -200_000);
+        assert_eq!(impermanent_loss_from_ratios(entry, 250_000).unwrap(), -200_000);
+        
+        // Ratios that don't divide evenly keep their precision: r = 1.5 -> ~-2.02%
+        let il = impermanent_loss_from_ratios(2_000_000, 3_000_000).unwrap();
+        assert!((-20_205..=-20_203).contains(&il));
+        
+        assert!(impermanent_loss_from_ratios(0, 1_000_000).is_err());
+    }
+    
+    #[test]
+    fn test_redistribution_session_accumulates_chunks() {
+        let mut session = RedistributionSession {
+            portfolio: Pubkey::new_unique(),
+            expected_total: 3_000,
+            accumulated_total: 0,
+            fees_allocated: 0,
+            opened_at: 0,
+            active: true,
+            fees_waived: false,
+            bump: 255,
+            allocated_ids: Vec::new(),
+        };
+        let allocation = |strategy_id: Pubkey, amount: u64| CapitalAllocation {
+            strategy_id,
+            amount,
+            allocation_type: AllocationType::TopPerformer,
+        };
+        let first = Pubkey::new_unique();
+        
+        session.record_chunk(&[allocation(first, 1_000)], 1_000).unwrap();
+        session.record_chunk(&[allocation(Pubkey::new_unique(), 1_500)], 1_500).unwrap();
+        assert_eq!(session.accumulated_total, 2_500);
+        
+        // Destinations already paid in an earlier chunk are refused
+        assert_eq!(
+            session.record_chunk(&[allocation(first, 500)], 500).unwrap_err(),
+            ErrorCode::DuplicateStrategy.into()
+        );
+        
+        // Chunks may not overshoot the expected total
+        assert_eq!(
+            session.record_chunk(&[allocation(Pubkey::new_unique(), 600)], 600).unwrap_err(),
+            ErrorCode::RedistributionTotalMismatch.into()
+        );
+    }
+    
+    #[test]
+    fn test_high_water_mark_fees_gain_loss_recovery() {
+        // Gain: 2% of the 0.5 SOL above the mark, then the mark moves up
+        let mut strategy = test_strategy(10_500_000_000, 10_000_000_000);
+        assert_eq!(strategy.fee_eligible_gain(200), 10_000_000);
+        strategy.advance_high_water_mark();
+        assert_eq!(strategy.high_water_mark, 10_500_000_000);
+        
+        // Loss: nothing owed and the mark holds
+        strategy.current_balance = 9_000_000_000;
+        assert_eq!(strategy.fee_eligible_gain(200), 0);
+        strategy.advance_high_water_mark();
+        assert_eq!(strategy.high_water_mark, 10_500_000_000);
+        
+        // Recovery short of the old peak is still not charged
+        strategy.current_balance = 10_400_000_000;
+        assert_eq!(strategy.fee_eligible_gain(200), 0);
+        strategy.advance_high_water_mark();
+        assert_eq!(strategy.high_water_mark, 10_500_000_000);
+    }
+    
+    #[test]
+    fn test_position_type_follows_protocol() {
+        let lending = ProtocolType::StableLending {
+            pool_id: Pubkey::new_unique(),
+            utilization: 5000,
+            reserve_address: Pubkey::new_unique(),
+        };
+        let staking = ProtocolType::LiquidStaking {
+            validator_id: Pubkey::new_unique(),
+            commission: 500,
+            stake_pool: Pubkey::new_unique(),
+            unstake_delay: 10,
+        };
+        
+        let farming = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 30,
+        };
+        
+        assert_eq!(lending.compatible_position_type(), PositionType::SingleAsset);
+        assert_eq!(farming.compatible_position_type(), PositionType::LiquidityPair);
+        assert_eq!(staking.compatible_position_type(), PositionType::StakedPosition);
+        
+        // Each valid pairing passes
+        let mut position = test_position(0);
+        assert!(position.require_compatible(&farming).is_ok());
+        position.position_type = PositionType::SingleAsset;
+        assert!(position.require_compatible(&lending).is_ok());
+        position.position_type = PositionType::StakedPosition;
+        assert!(position.require_compatible(&staking).is_ok());
+        
+        // A single-asset position can't back an LP strategy
+        position.position_type = PositionType::SingleAsset;
+        assert_eq!(position.require_compatible(&farming).unwrap_err(), ErrorCode::PositionTypeMismatch.into());
+    }
+    
+    #[test]
+    fn test_schema_version_gate() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        assert!(strategy.schema_supported());
+        
+        // Older layouts must be migrated before they are read
+        strategy.schema_version = 0;
+        assert!(!strategy.schema_supported());
+        strategy.schema_version = CURRENT_STRATEGY_SCHEMA - 1;
+        assert!(!strategy.schema_supported());
+        
+        // A layout from a newer program is refused rather than misread
+        strategy.schema_version = CURRENT_STRATEGY_SCHEMA + 1;
+        assert!(!strategy.schema_supported());
+    }
+    
+    #[test]
+    fn test_drawdown_tracking() {
+        // Monotonic climb: every report is a new peak
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        for balance in [1_100_000_000, 1_200_000_000, 1_500_000_000] {
+            strategy.current_balance = balance;
+            strategy.track_drawdown();
+        }
+        assert_eq!(strategy.peak_balance, 1_500_000_000);
+        assert_eq!(strategy.max_drawdown_bps, 0);
+        assert_eq!(strategy.current_drawdown_bps(), 0);
+        
+        // V-shape: 2000 -> 1400 (-30%) -> 1700 -> 2200 keeps the worst drop
+        let mut strategy = test_strategy(2_000_000_000, 2_000_000_000);
+        for balance in [1_400_000_000, 1_700_000_000] {
+            strategy.current_balance = balance;
+            strategy.track_drawdown();
+        }
+        assert_eq!(strategy.max_drawdown_bps, 3000);
+        assert_eq!(strategy.current_drawdown_bps(), 1500);
+        
+        strategy.current_balance = 2_200_000_000;
+        strategy.track_drawdown();
+        assert_eq!(strategy.peak_balance, 2_200_000_000);
+        assert_eq!(strategy.current_drawdown_bps(), 0);
+        assert_eq!(strategy.max_drawdown_bps, 3000);
+    }
+    
+    #[test]
+    fn test_allocation_log_overwrites_oldest() {
+        let mut log = AllocationLog {
+            portfolio: Pubkey::new_unique(),
+            next_sequence: 0,
+            bump: 255,
+            entries: [AllocationLogEntry::default(); AllocationLog::CAPACITY],
+        };
+        
+        for i in 0..AllocationLog::CAPACITY as u64 + 3 {
+            assert_eq!(log.append(1_000 + i, 2, 1_700_000_000 + i as i64), i + 1);
+        }
+        
+        // The three oldest slots were reused; sequences stay contiguous
+        assert_eq!(log.entries[0].sequence, AllocationLog::CAPACITY as u64 + 1);
+        assert_eq!(log.entries[2].total_allocated, 1_000 + AllocationLog::CAPACITY as u64 + 2);
+        assert_eq!(log.entries[3].sequence, 4);
+        assert_eq!(log.next_sequence, AllocationLog::CAPACITY as u64 + 4);
+        
+        let mut sequences: Vec<u64> = log.entries.iter().map(|e| e.sequence).collect();
+        sequences.sort();
+        assert!(sequences.windows(2).all(|w| w[1] == w[0] + 1));
+    }
+    
+    #[test]
+    fn test_volatility_circuit_breaker() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.volatility_score = 2000;
+        
+        // Within the band, or falling, never trips
+        assert!(!strategy.volatility_circuit_tripped(4500, 2500));
+        assert!(!strategy.volatility_circuit_tripped(500, 2500));
+        
+        // A spike past the band trips; a disabled breaker never does
+        assert!(strategy.volatility_circuit_tripped(4501, 2500));
+        assert!(!strategy.volatility_circuit_tripped(9000, 0));
+        
+        assert!(Portfolio::validate_volatility_circuit(10000).is_ok());
+        assert_eq!(
+            Portfolio::validate_volatility_circuit(10001).unwrap_err(),
+            ErrorCode::InvalidVolatilityCircuit.into()
+        );
+    }
+    
+    #[test]
+    fn test_yield_jump_detection() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        strategy.yield_rate = 800;
+        
+        // Moves within the band either way are accepted
+        assert!(!strategy.yield_jump_suspicious(1800, 1000));
+        assert!(!strategy.yield_jump_suspicious(0, 1000));
+        
+        // A jump past the band is flagged; a disabled check never flags
+        assert!(strategy.yield_jump_suspicious(1801, 1000));
+        assert!(strategy.yield_jump_suspicious(50000, 1000));
+        assert!(!strategy.yield_jump_suspicious(50000, 0));
+        
+        // First report has no baseline
+        strategy.yield_rate = 0;
+        assert!(!strategy.yield_jump_suspicious(50000, 1000));
+        
+        assert!(Portfolio::validate_max_yield_delta(50000).is_ok());
+        assert_eq!(
+            Portfolio::validate_max_yield_delta(50001).unwrap_err(),
+            ErrorCode::InvalidYieldDelta.into()
+        );
+    }
+    
+    #[test]
+    fn test_emergency_extraction_requires_next_nonce() {
+        let mut portfolio = Portfolio::try_deserialize_unchecked(&mut &[0u8; Portfolio::MAX_SIZE][..]).unwrap();
+        
+        // Only the next count confirms; a repeat or a skip-ahead does not
+        assert_eq!(
+            portfolio.record_emergency_extraction(0).unwrap_err(),
+            ErrorCode::InvalidEmergencyNonce.into()
+        );
+        assert_eq!(portfolio.record_emergency_extraction(1).unwrap(), 1);
+        assert!(portfolio.record_emergency_extraction(1).is_err());
+        assert!(portfolio.record_emergency_extraction(3).is_err());
+        assert_eq!(portfolio.record_emergency_extraction(2).unwrap(), 2);
+        assert_eq!(portfolio.emergency_extract_count, 2);
+    }
+    
+    #[test]
+    fn test_tvl_history_ring() {
+        let mut history = TvlHistory {
+            portfolio: Pubkey::new_unique(),
+            snapshot_count: 0,
+            bump: 255,
+            entries: [TvlSnapshot::default(); TvlHistory::CAPACITY],
+        };
+        assert!(history.latest(5).is_empty());
+        
+        let start = 1_700_000_000;
+        for i in 0..TvlHistory::CAPACITY as i64 + 2 {
+            history.record(1_000 + i as u64, start + i * 3_600, 3_600).unwrap();
+        }
+        
+        // Too soon after the last snapshot
+        assert_eq!(
+            history.record(5_000, start + (TvlHistory::CAPACITY as i64 + 1) * 3_600 + 60, 3_600).unwrap_err(),
+            ErrorCode::TvlSnapshotTooSoon.into()
+        );
+        
+        // Newest first, across the wrap point, and capped at what is stored
+        let latest = history.latest(3);
+        assert_eq!(latest.len(), 3);
+        assert_eq!(latest[0].total_tvl, 1_000 + TvlHistory::CAPACITY as u64 + 1);
+        assert_eq!(latest[2].total_tvl, 1_000 + TvlHistory::CAPACITY as u64 - 1);
+        assert!(latest.windows(2).all(|w| w[0].timestamp - w[1].timestamp == 3_600));
+        assert_eq!(history.latest(1_000).len(), TvlHistory::CAPACITY);
+        assert_eq!(history.latest(1_000).last().unwrap().total_tvl, 1_002);
+    }
+    
+    #[test]
+    fn test_denomination_scores_stable_value() {
+        let config = DenominationConfig {
+            portfolio: Pubkey::new_unique(),
+            price_feed: Pubkey::new_unique(),
+            oracle_source: OracleSource::Pyth,
+            reference_price: 150_000_000, // $150
+            last_updated: 0,
+            bump: 255,
+            reserved: [0; 16],
+        };
+        
+        // At the reference price lamports score unchanged
+        assert_eq!(config.scoring_balance(10_000_000_000, 150_000_000), 10_000_000_000);
+        
+        // A halving SOL price halves the dollar value the scorer sees
+        assert_eq!(config.scoring_balance(10_000_000_000, 75_000_000), 5_000_000_000);
+        
+        // Huge balances saturate instead of overflowing
+        assert_eq!(config.scoring_balance(u64::MAX, 300_000_000), u64::MAX);
+    }
+    
+    #[test]
+    fn test_strategy_cap() {
+        assert!(Portfolio::validate_max_strategies(0).is_err());
+        assert!(Portfolio::validate_max_strategies(1).is_ok());
+        assert!(Portfolio::validate_max_strategies(MAX_STRATEGIES_LIMIT).is_ok());
+        assert!(Portfolio::validate_max_strategies(MAX_STRATEGIES_LIMIT + 1).is_err());
+        
+        let mut portfolio = zeroed_portfolio();
+        portfolio.max_strategies = 3;
+        portfolio.total_strategies = 2;
+        assert!(portfolio.require_strategy_capacity().is_ok());
+        portfolio.total_strategies = 3;
+        assert_eq!(portfolio.require_strategy_capacity().unwrap_err(), ErrorCode::MaxStrategiesReached.into());
+        
+        // Portfolios created before the cap fall back to the hard limit
+        portfolio.max_strategies = 0;
+        assert!(portfolio.require_strategy_capacity().is_ok());
+        portfolio.total_strategies = MAX_STRATEGIES_LIMIT as u32;
+        assert!(portfolio.require_strategy_capacity().is_err());
+    }
+    
+    #[test]
+    fn test_price_feed_binding_fixed_once() {
+        let mut strategy = test_strategy(1_000_000_000, 1_000_000_000);
+        assert!(!strategy.price_feeds.is_bound());
+        
+        // Lending strategies hold one asset; an unbound token A leg is refused
+        let single = PriceFeedBinding { source: OracleSource::Pyth, feed_a: [1u8; 32], feed_b: [0u8; 32] };
+        assert!(strategy.bind_price_feeds(PriceFeedBinding::UNBOUND).is_err());
+        strategy.bind_price_feeds(single).unwrap();
+        assert_eq!(strategy.price_feeds, single);
+        
+        // Once bound the manager cannot swap in another asset's feed
+        let other = PriceFeedBinding { feed_a: [2u8; 32], ..single };
+        assert_eq!(strategy.bind_price_feeds(other).unwrap_err(), ErrorCode::InvalidPriceFeedBinding.into());
+        
+        // Liquidity pairs need both legs
+        let farming = ProtocolType::YieldFarming {
+            pair_id: Pubkey::new_unique(),
+            reward_multiplier: 2,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            fee_tier: 30,
+        };
+        assert!(single.validate_for(&farming).is_err());
+        assert!(PriceFeedBinding { feed_b: [3u8; 32], ..single }.validate_for(&farming).is_ok());
+    }
+}
@@ -0,0 +1,339 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::TokenAccount;
+use crate::oracle::read_bytes;
+use crate::error::ErrorCode;
+
+// Orca Whirlpool program, owner of every Whirlpool and Position account
+pub const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+// sha256("account:Whirlpool")[..8] and sha256("account:Position")[..8]
+pub const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+pub const POSITION_DISCRIMINATOR: [u8; 8] = [170, 188, 143, 228, 122, 64, 247, 208];
+
+// sha256("global:decrease_liquidity")[..8] and sha256("global:collect_fees")[..8]
+pub const DECREASE_LIQUIDITY_DISCRIMINATOR: [u8; 8] = [160, 38, 208, 111, 104, 91, 44, 1];
+pub const COLLECT_FEES_DISCRIMINATOR: [u8; 8] = [164, 152, 207, 99, 30, 186, 19, 182];
+
+// Whirlpool offsets (whirlpool 0.3, including the discriminator)
+pub const WHIRLPOOL_TICK_SPACING_OFFSET: usize = 41;
+pub const WHIRLPOOL_LIQUIDITY_OFFSET: usize = 49;
+pub const WHIRLPOOL_TOKEN_MINT_A_OFFSET: usize = 101;
+pub const WHIRLPOOL_TOKEN_VAULT_A_OFFSET: usize = 133;
+pub const WHIRLPOOL_TOKEN_MINT_B_OFFSET: usize = 181;
+pub const WHIRLPOOL_TOKEN_VAULT_B_OFFSET: usize = 213;
+
+// Position offsets (whirlpool 0.3, including the discriminator)
+pub const POSITION_WHIRLPOOL_OFFSET: usize = 8;
+pub const POSITION_MINT_OFFSET: usize = 40;
+pub const POSITION_LIQUIDITY_OFFSET: usize = 72;
+pub const POSITION_TICK_LOWER_OFFSET: usize = 88;
+pub const POSITION_TICK_UPPER_OFFSET: usize = 92;
+
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+// WHIRLPOOL ACCOUNT DECODING
+// Decoded by hand, like the oracle feeds, so the program does not pull in the
+// Orca SDK dependency tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhirlpoolState {
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrcaPosition {
+    pub whirlpool: Pubkey,
+    pub position_mint: Pubkey,
+    pub liquidity: u128,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+}
+
+pub fn decode_whirlpool(data: &[u8]) -> Result<WhirlpoolState> {
+    require!(data.len() >= WHIRLPOOL_TOKEN_VAULT_B_OFFSET + 32, ErrorCode::InvalidPoolState);
+    require!(data[..8] == WHIRLPOOL_DISCRIMINATOR, ErrorCode::InvalidPoolState);
+    
+    Ok(WhirlpoolState {
+        tick_spacing: u16::from_le_bytes(read_bytes(data, WHIRLPOOL_TICK_SPACING_OFFSET)?),
+        liquidity: u128::from_le_bytes(read_bytes(data, WHIRLPOOL_LIQUIDITY_OFFSET)?),
+        token_mint_a: Pubkey::new_from_array(read_bytes(data, WHIRLPOOL_TOKEN_MINT_A_OFFSET)?),
+        token_vault_a: Pubkey::new_from_array(read_bytes(data, WHIRLPOOL_TOKEN_VAULT_A_OFFSET)?),
+        token_mint_b: Pubkey::new_from_array(read_bytes(data, WHIRLPOOL_TOKEN_MINT_B_OFFSET)?),
+        token_vault_b: Pubkey::new_from_array(read_bytes(data, WHIRLPOOL_TOKEN_VAULT_B_OFFSET)?),
+    })
+}
+
+pub fn decode_orca_position(data: &[u8]) -> Result<OrcaPosition> {
+    require!(data.len() >= POSITION_TICK_UPPER_OFFSET + 4, ErrorCode::InvalidPoolState);
+    require!(data[..8] == POSITION_DISCRIMINATOR, ErrorCode::InvalidPoolState);
+    
+    Ok(OrcaPosition {
+        whirlpool: Pubkey::new_from_array(read_bytes(data, POSITION_WHIRLPOOL_OFFSET)?),
+        position_mint: Pubkey::new_from_array(read_bytes(data, POSITION_MINT_OFFSET)?),
+        liquidity: u128::from_le_bytes(read_bytes(data, POSITION_LIQUIDITY_OFFSET)?),
+        tick_lower_index: i32::from_le_bytes(read_bytes(data, POSITION_TICK_LOWER_OFFSET)?),
+        tick_upper_index: i32::from_le_bytes(read_bytes(data, POSITION_TICK_UPPER_OFFSET)?),
+    })
+}
+
+// First tick of the tick array holding `tick_index`, rounded toward negative infinity
+pub fn tick_array_start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+    let ticks_per_array = tick_spacing as i32 * TICK_ARRAY_SIZE;
+    tick_index.div_euclid(ticks_per_array) * ticks_per_array
+}
+
+pub fn tick_array_address(whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"tick_array", whirlpool.as_ref(), start_tick_index.to_string().as_bytes()],
+        &WHIRLPOOL_PROGRAM_ID,
+    ).0
+}
+
+// Token amounts received from a Whirlpool exit, split between liquidity and fees
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WhirlpoolWithdrawal {
+    pub token_a: u64,
+    pub token_b: u64,
+    pub fees_a: u64,
+    pub fees_b: u64,
+}
+
+// ACCOUNTS FOR A REAL WHIRLPOOL EXIT
+// Everything decrease_liquidity and collect_fees need, located in
+// remaining_accounts. The Orca position NFT is held by the platform's
+// CapitalPosition PDA, which signs both CPIs.
+pub struct WhirlpoolExit<'info> {
+    pub pool: WhirlpoolState,
+    pub orca_position: OrcaPosition,
+    pub whirlpool_program: &'info AccountInfo<'info>,
+    pub whirlpool: &'info AccountInfo<'info>,
+    pub position: &'info AccountInfo<'info>,
+    pub position_token_account: &'info AccountInfo<'info>,
+    pub token_vault_a: &'info AccountInfo<'info>,
+    pub token_vault_b: &'info AccountInfo<'info>,
+    pub tick_array_lower: &'info AccountInfo<'info>,
+    pub tick_array_upper: &'info AccountInfo<'info>,
+    pub destination_a: &'info AccountInfo<'info>,
+    pub destination_b: &'info AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub position_authority: &'info AccountInfo<'info>,
+    pub strategy_key: Pubkey,
+}
+
+impl<'info> WhirlpoolExit<'info> {
+    pub fn load(
+        accounts: &'info [AccountInfo<'info>],
+        pair_id: &Pubkey,
+        strategy_key: Pubkey,
+        position_authority: &'info AccountInfo<'info>,
+        destination_a: &'info AccountInfo<'info>,
+        destination_b: &'info AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+    ) -> Result<Self> {
+        let by_key = |key: &Pubkey| {
+            accounts.iter()
+                .find(|info| info.key == key)
+                .ok_or(error!(ErrorCode::TokenAccountNotProvided))
+        };
+        
+        let whirlpool_program = by_key(&WHIRLPOOL_PROGRAM_ID)?;
+        let whirlpool = by_key(pair_id)?;
+        require!(whirlpool.owner == &WHIRLPOOL_PROGRAM_ID, ErrorCode::InvalidPoolState);
+        let pool = decode_whirlpool(&whirlpool.try_borrow_data()?)?;
+        
+        // The Orca position on this pool whose NFT the CapitalPosition PDA holds
+        let (position, orca_position, position_token_account) = accounts.iter()
+            .filter(|info| info.owner == &WHIRLPOOL_PROGRAM_ID)
+            .filter_map(|info| {
+                let orca_position = decode_orca_position(&info.try_borrow_data().ok()?).ok()?;
+                if orca_position.whirlpool != *pair_id {
+                    return None;
+                }
+                let holder = accounts.iter().find(|token_info| {
+                    Account::<TokenAccount>::try_from(token_info).is_ok_and(|account| {
+                        account.mint == orca_position.position_mint
+                            && account.owner == position_authority.key()
+                            && account.amount == 1
+                    })
+                })?;
+                Some((info, orca_position, holder))
+            })
+            .next()
+            .ok_or(ErrorCode::TokenAccountNotProvided)?;
+        
+        let lower_start = tick_array_start_index(orca_position.tick_lower_index, pool.tick_spacing);
+        let upper_start = tick_array_start_index(orca_position.tick_upper_index, pool.tick_spacing);
+        
+        Ok(Self {
+            whirlpool_program,
+            whirlpool,
+            position,
+            position_token_account,
+            token_vault_a: by_key(&pool.token_vault_a)?,
+            token_vault_b: by_key(&pool.token_vault_b)?,
+            tick_array_lower: by_key(&tick_array_address(pair_id, lower_start))?,
+            tick_array_upper: by_key(&tick_array_address(pair_id, upper_start))?,
+            destination_a,
+            destination_b,
+            token_program,
+            position_authority,
+            strategy_key,
+            pool,
+            orca_position,
+        })
+    }
+    
+    // Reserves currently held by the pool's vaults
+    pub fn reserves(&self) -> Result<(u64, u64)> {
+        Ok((token_balance(self.token_vault_a)?, token_balance(self.token_vault_b)?))
+    }
+    
+    // Decrease `liquidity` from the Orca position and collect its fees into the
+    // destination accounts. Received amounts are measured from balance deltas.
+    pub fn withdraw(&self, liquidity: u128, position_index: u8, position_bump: u8) -> Result<WhirlpoolWithdrawal> {
+        require!(liquidity <= self.orca_position.liquidity, ErrorCode::InsufficientBalance);
+        
+        let seeds: &[&[u8]] = &[
+            b"position",
+            self.strategy_key.as_ref(),
+            &[position_index],
+            &[position_bump],
+        ];
+        let (start_a, start_b) = (token_balance(self.destination_a)?, token_balance(self.destination_b)?);
+        
+        // Minimums are left at zero: Orca prices the exit from the position's tick
+        // range, while the pool-wide reserves only back the invariant check
+        let mut data = DECREASE_LIQUIDITY_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&liquidity.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        
+        invoke_signed(
+            &Instruction {
+                program_id: WHIRLPOOL_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new(self.whirlpool.key(), false),
+                    AccountMeta::new_readonly(self.token_program.key(), false),
+                    AccountMeta::new_readonly(self.position_authority.key(), true),
+                    AccountMeta::new(self.position.key(), false),
+                    AccountMeta::new_readonly(self.position_token_account.key(), false),
+                    AccountMeta::new(self.destination_a.key(), false),
+                    AccountMeta::new(self.destination_b.key(), false),
+                    AccountMeta::new(self.token_vault_a.key(), false),
+                    AccountMeta::new(self.token_vault_b.key(), false),
+                    AccountMeta::new(self.tick_array_lower.key(), false),
+                    AccountMeta::new(self.tick_array_upper.key(), false),
+                ],
+                data,
+            },
+            &[
+                self.whirlpool.clone(),
+                self.token_program.clone(),
+                self.position_authority.clone(),
+                self.position.clone(),
+                self.position_token_account.clone(),
+                self.destination_a.clone(),
+                self.destination_b.clone(),
+                self.token_vault_a.clone(),
+                self.token_vault_b.clone(),
+                self.tick_array_lower.clone(),
+                self.tick_array_upper.clone(),
+                self.whirlpool_program.clone(),
+            ],
+            &[seeds],
+        )?;
+        
+        let (after_a, after_b) = (token_balance(self.destination_a)?, token_balance(self.destination_b)?);
+        
+        invoke_signed(
+            &Instruction {
+                program_id: WHIRLPOOL_PROGRAM_ID,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.whirlpool.key(), false),
+                    AccountMeta::new_readonly(self.position_authority.key(), true),
+                    AccountMeta::new(self.position.key(), false),
+                    AccountMeta::new_readonly(self.position_token_account.key(), false),
+                    AccountMeta::new(self.destination_a.key(), false),
+                    AccountMeta::new(self.token_vault_a.key(), false),
+                    AccountMeta::new(self.destination_b.key(), false),
+                    AccountMeta::new(self.token_vault_b.key(), false),
+                    AccountMeta::new_readonly(self.token_program.key(), false),
+                ],
+                data: COLLECT_FEES_DISCRIMINATOR.to_vec(),
+            },
+            &[
+                self.whirlpool.clone(),
+                self.position_authority.clone(),
+                self.position.clone(),
+                self.position_token_account.clone(),
+                self.destination_a.clone(),
+                self.token_vault_a.clone(),
+                self.destination_b.clone(),
+                self.token_vault_b.clone(),
+                self.token_program.clone(),
+                self.whirlpool_program.clone(),
+            ],
+            &[seeds],
+        )?;
+        
+        let (final_a, final_b) = (token_balance(self.destination_a)?, token_balance(self.destination_b)?);
+        
+        Ok(WhirlpoolWithdrawal {
+            token_a: after_a.saturating_sub(start_a),
+            token_b: after_b.saturating_sub(start_b),
+            fees_a: final_a.saturating_sub(after_a),
+            fees_b: final_b.saturating_sub(after_b),
+        })
+    }
+}
+
+fn token_balance<'info>(info: &'info AccountInfo<'info>) -> Result<u64> {
+    Ok(Account::<TokenAccount>::try_from(info)?.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_decode_whirlpool_fixture() {
+        let mint_a = Pubkey::new_unique();
+        let vault_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+        let vault_b = Pubkey::new_unique();
+        
+        let mut data = vec![0u8; 653];
+        data[..8].copy_from_slice(&WHIRLPOOL_DISCRIMINATOR);
+        data[41..43].copy_from_slice(&64u16.to_le_bytes());
+        data[49..65].copy_from_slice(&5_000_000_000u128.to_le_bytes());
+        data[101..133].copy_from_slice(mint_a.as_ref());
+        data[133..165].copy_from_slice(vault_a.as_ref());
+        data[181..213].copy_from_slice(mint_b.as_ref());
+        data[213..245].copy_from_slice(vault_b.as_ref());
+        
+        let pool = decode_whirlpool(&data).unwrap();
+        assert_eq!(pool.tick_spacing, 64);
+        assert_eq!(pool.liquidity, 5_000_000_000);
+        assert_eq!((pool.token_mint_a, pool.token_vault_a), (mint_a, vault_a));
+        assert_eq!((pool.token_mint_b, pool.token_vault_b), (mint_b, vault_b));
+        
+        data[0] ^= 1;
+        assert!(decode_whirlpool(&data).is_err());
+    }
+    
+    #[test]
+    fn test_tick_array_start_index_rounds_down() {
+        // 64 * 88 = 5632 ticks per array
+        assert_eq!(tick_array_start_index(0, 64), 0);
+        assert_eq!(tick_array_start_index(5631, 64), 0);
+        assert_eq!(tick_array_start_index(5632, 64), 5632);
+        assert_eq!(tick_array_start_index(-1, 64), -5632);
+        assert_eq!(tick_array_start_index(-5632, 64), -5632);
+    }
+}
@@ -22,7 +22,9 @@ describe("rebalancer", () => {
       .initializePortfolio(
         manager.publicKey,
         25, // 25% rebalance threshold
-        new BN(3600) // 1 hour minimum interval
+        new BN(3600), // 1 hour minimum interval
+        50, // At most 50 strategies
+        anchor.web3.Keypair.generate().publicKey // Emergency destination
       )
       .accounts({
         manager: manager.publicKey,
@@ -50,7 +52,8 @@ describe("rebalancer", () => {
           utilization: 7500,
           reserveAddress: anchor.web3.Keypair.generate().publicKey,
         }},
-        new anchor.BN(1000000000) // 1 SOL
+        new anchor.BN(1000000000), // 1 SOL
+        null
       )
       .accounts({
         portfolio: portfolioPda,
@@ -83,7 +86,8 @@ describe("rebalancer", () => {
           tokenBMint: anchor.web3.Keypair.generate().publicKey,
           feeTier: 300,
         }},
-        new anchor.BN(2000000000) // 2 SOL
+        new anchor.BN(2000000000), // 2 SOL
+        null
       )
       .accounts({
         portfolio: portfolioPda,
@@ -135,7 +139,9 @@ describe.skip("rebalancer performance scoring", () => {
       .initializePortfolio(
         manager.publicKey,
         25, // 25% rebalance threshold
-        new BN(3600) // 1 hour minimum interval
+        new BN(3600), // 1 hour minimum interval
+        50, // At most 50 strategies
+        anchor.web3.Keypair.generate().publicKey // Emergency destination
       )
       .accounts({
         manager: manager.publicKey,
@@ -205,7 +211,8 @@ describe.skip("rebalancer performance scoring", () => {
         .registerStrategy(
           strategy.id,
           strategy.protocol,
-          strategy.balance
+          strategy.balance,
+          null
         )
         .accounts({
           portfolio: portfolioPda,
@@ -225,7 +232,8 @@ describe.skip("rebalancer performance scoring", () => {
         strategy1Id,
         new anchor.BN(15000), // 150% yield
         2000, // 20% volatility (low risk)
-        new anchor.BN(5000000000) // 5 SOL balance
+        new anchor.BN(5000000000), // 5 SOL balance
+        { apy: {} }, false
       )
       .accounts({
         portfolio: portfolioPda,
@@ -241,7 +249,8 @@ describe.skip("rebalancer performance scoring", () => {
         strategy2Id,
         new anchor.BN(10000), // 100% yield
         5000, // 50% volatility (medium risk)
-        new anchor.BN(2000000000) // 2 SOL balance
+        new anchor.BN(2000000000), // 2 SOL balance
+        { apy: {} }, false
       )
       .accounts({
         portfolio: portfolioPda,
@@ -257,7 +266,8 @@ describe.skip("rebalancer performance scoring", () => {
         strategy3Id,
         new anchor.BN(3000), // 30% yield
         8000, // 80% volatility (high risk)
-        new anchor.BN(1000000000) // 1 SOL balance
+        new anchor.BN(1000000000), // 1 SOL balance
+        { apy: {} }, false
       )
       .accounts({
         portfolio: portfolioPda,
@@ -349,7 +359,8 @@ describe.skip("rebalancer performance scoring", () => {
             reserveAddress: anchor.web3.Keypair.generate().publicKey,
           }
         },
-        new anchor.BN(100000000) // 0.1 SOL minimum
+        new anchor.BN(100000000), // 0.1 SOL minimum
+        null
       )
       .accounts({
         manager: manager.publicKey,
@@ -363,7 +374,8 @@ describe.skip("rebalancer performance scoring", () => {
         extremeStrategyId,
         new anchor.BN(50000), // 500% yield (maximum allowed)
         10000, // 100% volatility (maximum risk)
-        new anchor.BN(100000000) // 0.1 SOL (minimum balance)
+        new anchor.BN(100000000), // 0.1 SOL (minimum balance)
+        { apy: {} }, false
       )
       .accounts({
         manager: manager.publicKey,
@@ -390,7 +402,8 @@ describe.skip("rebalancer performance scoring", () => {
           strategy1Id,
           new anchor.BN(60000), // 600% yield (over maximum)
           2000,
-          new anchor.BN(5000000000)
+          new anchor.BN(5000000000),
+          { apy: {} }, false
         )
         .accounts({
           manager: manager.publicKey,
@@ -410,7 +423,8 @@ describe.skip("rebalancer performance scoring", () => {
           strategy1Id,
           new anchor.BN(15000),
           15000, // 150% volatility (over maximum)
-          new anchor.BN(5000000000)
+          new anchor.BN(5000000000),
+          { apy: {} }, false
         )
         .accounts({
           manager: manager.publicKey,
@@ -468,7 +482,8 @@ describe.skip("rebalancer performance scoring", () => {
               reserveAddress: anchor.web3.Keypair.generate().publicKey,
             }
           },
-          new anchor.BN(testCase.balance)
+          new anchor.BN(testCase.balance),
+          null
         )
         .accounts({
           manager: manager.publicKey,
@@ -482,7 +497,8 @@ describe.skip("rebalancer performance scoring", () => {
           testStrategyId,
           new anchor.BN(testCase.yield),
           testCase.volatility,
-          new anchor.BN(testCase.balance)
+          new anchor.BN(testCase.balance),
+          { apy: {} }, false
         )
         .accounts({
           manager: manager.publicKey,
@@ -522,7 +538,8 @@ describe.skip("rebalancer performance scoring", () => {
             reserveAddress: anchor.web3.Keypair.generate().publicKey,
           }
         },
-        new anchor.BN(1000000000)
+        new anchor.BN(1000000000),
+        null
       )
       .accounts({
         manager: manager.publicKey,
@@ -542,7 +559,8 @@ describe.skip("rebalancer performance scoring", () => {
           consistencyStrategyId,
           new anchor.BN(testYield),
           testVolatility,
-          new anchor.BN(testBalance)
+          new anchor.BN(testBalance),
+          { apy: {} }, false
         )
         .accounts({
           manager: manager.publicKey,
@@ -588,7 +606,8 @@ describe.skip("rebalancer performance scoring", () => {
               reserveAddress: anchor.web3.Keypair.generate().publicKey,
             }
           },
-          new anchor.BN(testCase.balance)
+          new anchor.BN(testCase.balance),
+          null
         )
         .accounts({
           manager: manager.publicKey,
@@ -601,7 +620,8 @@ describe.skip("rebalancer performance scoring", () => {
           boundaryStrategyId,
           new anchor.BN(testCase.yield),
           testCase.volatility,
-          new anchor.BN(testCase.balance)
+          new anchor.BN(testCase.balance),
+          { apy: {} }, false
         )
         .accounts({
           manager: manager.publicKey,
@@ -652,7 +672,9 @@ describe.skip("rebalancer complete workflow", () => {
       .initializePortfolio(
         manager.publicKey,
         25, // 25% rebalance threshold
-        new anchor.BN(3600) // 1 hour minimum interval
+        new anchor.BN(3600), // 1 hour minimum interval
+        50, // At most 50 strategies
+        anchor.web3.Keypair.generate().publicKey // Emergency destination
       )
       .accounts({
         payer: provider.wallet.publicKey,
@@ -715,7 +737,8 @@ describe.skip("rebalancer complete workflow", () => {
         .registerStrategy(
           strategies[config.key].id,
           config.protocol,
-          new anchor.BN(config.balance)
+          new anchor.BN(config.balance),
+          null
         )
         .accounts({
           portfolio: portfolioPda,
@@ -763,7 +786,8 @@ describe.skip("rebalancer complete workflow", () => {
           strategies[update.strategy].id,
           new anchor.BN(update.yield),
           update.volatility,
-          new anchor.BN(update.balance)
+          new anchor.BN(update.balance),
+          { apy: {} }, false
         )
         .accounts({
           portfolio: portfolioPda,
@@ -781,11 +805,18 @@ describe.skip("rebalancer complete workflow", () => {
     console.log("\nStep 2: Executing ranking cycle...");
     
     await program.methods
-      .executeRankingCycle()
+      .executeRankingCycle(null)
       .accounts({
         portfolio: portfolioPda,
         manager: manager.publicKey,
       })
+      .remainingAccounts(
+        Object.values(strategies).map((strategy) => ({
+          pubkey: strategy.pda,
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
       .signers([manager])
       .rpc();
 
@@ -817,20 +848,54 @@ describe.skip("rebalancer complete workflow", () => {
     expect(strategyAccounts[0].performanceScore.gt(strategyAccounts[1].performanceScore)).to.be.true;
     expect(strategyAccounts[1].performanceScore.gt(strategyAccounts[2].performanceScore)).to.be.true;
 
+    // Percentiles persisted by the ranking cycle follow the score order,
+    // weighted by capital: medium outranks 2 of the other 7 SOL
+    expect(strategyAccounts[0].percentileRank).to.equal(100);
+    expect(strategyAccounts[1].percentileRank).to.equal(28);
+    expect(strategyAccounts[2].percentileRank).to.equal(0);
+
     // STEP 4: Test capital extraction
     console.log("\nStep 4: Testing capital extraction...");
     
     const preExtractionBalance = strategyAccounts[2].currentBalance;
-    
+    const [lowPositionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), strategies.low.pda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+
+    await program.methods
+      .createCapitalPosition(
+        strategies.low.id, { stakedPosition: {} },
+        preExtractionBalance, new anchor.BN(0), new anchor.BN(0),
+        new anchor.BN(1_000_000), new anchor.BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: strategies.low.pda,
+        position: lowPositionPda,
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
     await program.methods
-      .extractCapital([strategies.low.id]) // Extract from worst performer
+      .extractCapital([strategies.low.id], false, { liquid: {} }, 1000) // Extract from worst performer
       .accounts({
         portfolio: portfolioPda,
         manager: manager.publicKey,
       })
+      .remainingAccounts([
+        { pubkey: strategies.low.pda, isWritable: true, isSigner: false },
+        { pubkey: lowPositionPda, isWritable: true, isSigner: false },
+      ])
       .signers([manager])
       .rpc();
 
+    const portfolioAfterExtraction = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolioAfterExtraction.totalCapitalMoved.gt(new anchor.BN(0))).to.be.true;
+
     console.log(`  Extraction initiated for low performer (${preExtractionBalance.toString()} lamports)`);
 
     // STEP 5: Test capital redistribution
@@ -850,14 +915,23 @@ describe.skip("rebalancer complete workflow", () => {
     ];
 
     await program.methods
-      .redistributeCapital(allocations)
+      .redistributeCapital(allocations, false)
       .accounts({
         portfolio: portfolioPda,
         manager: manager.publicKey,
       })
+      .remainingAccounts([
+        { pubkey: strategies.high.pda, isWritable: true, isSigner: false },
+        { pubkey: strategies.medium.pda, isWritable: true, isSigner: false },
+      ])
       .signers([manager])
       .rpc();
 
+    const highAfterRedistribution = await program.account.strategy.fetch(strategies.high.pda);
+    expect(highAfterRedistribution.currentBalance.eq(
+      strategyAccounts[0].currentBalance.add(new anchor.BN(1_000_000_000))
+    )).to.be.true;
+
     console.log("  Capital redistribution completed:");
     allocations.forEach(allocation => {
       const strategyName = Object.keys(strategies).find(key => 
@@ -931,7 +1005,7 @@ describe.skip("rebalancer complete workflow", () => {
     
     try {
       await program.methods
-        .extractCapital([]) // Empty array
+        .extractCapital([], false, { liquid: {} }, 1000) // Empty array
         .accounts({
           portfolio: portfolioPda,
           manager: manager.publicKey,
@@ -949,7 +1023,7 @@ describe.skip("rebalancer complete workflow", () => {
     
     try {
       await program.methods
-        .redistributeCapital([]) // Empty allocations
+        .redistributeCapital([], false) // Empty allocations
         .accounts({
           portfolio: portfolioPda,
           manager: manager.publicKey,
@@ -975,7 +1049,7 @@ describe.skip("rebalancer complete workflow", () => {
     
     try {
       await program.methods
-        .executeRankingCycle()
+        .executeRankingCycle(null)
         .accounts({
           portfolio: portfolioPda,
           manager: unauthorizedUser.publicKey,
@@ -1006,7 +1080,8 @@ describe.skip("rebalancer complete workflow", () => {
               strategies.high.id,
               new anchor.BN(15000),
               2000,
-              new anchor.BN(5_000_000_000)
+              new anchor.BN(5_000_000_000),
+              { apy: {} }, false
             )
             .accounts({
               portfolio: portfolioPda,
@@ -1021,7 +1096,7 @@ describe.skip("rebalancer complete workflow", () => {
         name: "Ranking Cycle",
         operation: async () => {
           await program.methods
-            .executeRankingCycle()
+            .executeRankingCycle(null)
             .accounts({
               portfolio: portfolioPda,
               manager: manager.publicKey,
@@ -1048,3 +1123,2305 @@ describe.skip("rebalancer complete workflow", () => {
     console.log("\n✅ Performance benchmarking COMPLETED");
   });
 });
+
+describe("rebalancer keeper budget", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const keeper = anchor.web3.Keypair.generate();
+  const sourceId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let keeperBudgetPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [keeperBudgetPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("keeper_budget"), portfolioPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Funds the budget only from undeployed portfolio capital", async () => {
+    // Nothing extracted yet, so there is no capital to set aside
+    try {
+      await program.methods
+        .fundKeeperBudget(new BN(500_000_000))
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with ExceedsUndeployedCapital");
+    } catch (error) {
+      expect(error.message).to.include("ExceedsUndeployedCapital");
+    }
+
+    const [sourcePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), sourceId.toBuffer()],
+      program.programId
+    );
+    const [sourcePositionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), sourcePda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+
+    await program.methods
+      .registerStrategy(
+        sourceId,
+        {
+          liquidStaking: {
+            validatorId: anchor.web3.Keypair.generate().publicKey,
+            commission: 500,
+            stakePool: anchor.web3.Keypair.generate().publicKey,
+            unstakeDelay: 10,
+          }
+        },
+        new BN(2_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: sourcePda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .createCapitalPosition(
+        sourceId, { stakedPosition: {} },
+        new BN(2_000_000_000), new BN(0), new BN(0),
+        new BN(1_000_000), new BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: sourcePda,
+        position: sourcePositionPda,
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .extractCapital([sourceId], true, { liquid: {} }, 1000)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .remainingAccounts([
+        { pubkey: sourcePda, isWritable: true, isSigner: false },
+        { pubkey: sourcePositionPda, isWritable: true, isSigner: false },
+      ])
+      .signers([manager])
+      .rpc();
+
+    // Hold part of the released capital in the portfolio account
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: manager.publicKey,
+          toPubkey: portfolioPda,
+          lamports: 500_000_000,
+        })
+      ),
+      [manager]
+    );
+
+    const before = await program.account.portfolio.fetch(portfolioPda);
+    const portfolioLamportsBefore = await provider.connection.getBalance(portfolioPda);
+
+    await program.methods
+      .fundKeeperBudget(new BN(500_000_000)) // 0.5 SOL
+      .accounts({
+        portfolio: portfolioPda,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
+    const after = await program.account.portfolio.fetch(portfolioPda);
+    expect(before.undeployedCapital.sub(after.undeployedCapital).toNumber()).to.equal(500_000_000);
+    expect(portfolioLamportsBefore - await provider.connection.getBalance(portfolioPda)).to.equal(500_000_000);
+  });
+
+  it("Draws from the keeper budget", async () => {
+
+    // An unregistered keeper can't draw
+    try {
+      await program.methods
+        .drawKeeperBudget(new BN(5_000_000))
+        .accounts({ portfolio: portfolioPda, keeper: keeper.publicKey })
+        .signers([keeper])
+        .rpc();
+
+      expect.fail("Should have failed for an unregistered keeper");
+    } catch (error) {
+      expect(error.message).to.include("AccountNotInitialized");
+    }
+
+    await program.methods
+      .registerKeeper(keeper.publicKey)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    let budget = await program.account.keeperBudget.fetch(keeperBudgetPda);
+    expect(budget.balance.toString()).to.equal("500000000");
+    expect(budget.totalFunded.toString()).to.equal("500000000");
+
+    const keeperBefore = await provider.connection.getBalance(keeper.publicKey);
+
+    // The keeper draws on its own signature, without the manager
+    await program.methods
+      .drawKeeperBudget(new BN(5_000_000)) // 0.005 SOL for claim transactions
+      .accounts({
+        portfolio: portfolioPda,
+        keeper: keeper.publicKey,
+      })
+      .signers([keeper])
+      .rpc();
+
+    budget = await program.account.keeperBudget.fetch(keeperBudgetPda);
+    expect(budget.balance.toString()).to.equal("495000000");
+    expect(budget.totalDrawn.toString()).to.equal("5000000");
+
+    const keeperAfter = await provider.connection.getBalance(keeper.publicKey);
+    expect(keeperAfter - keeperBefore).to.equal(5_000_000);
+
+    // Cannot overdraw the tracked budget
+    try {
+      await program.methods
+        .drawKeeperBudget(new BN(1_000_000_000))
+        .accounts({
+          portfolio: portfolioPda,
+          keeper: keeper.publicKey,
+        })
+        .signers([keeper])
+        .rpc();
+
+      expect.fail("Should have failed with insufficient budget");
+    } catch (error) {
+      expect(error.message).to.include("InsufficientBalance");
+    }
+
+    // A revoked keeper loses access
+    await program.methods
+      .revokeKeeper(keeper.publicKey)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    try {
+      await program.methods
+        .drawKeeperBudget(new BN(5_000_000))
+        .accounts({ portfolio: portfolioPda, keeper: keeper.publicKey })
+        .signers([keeper])
+        .rpc();
+
+      expect.fail("Should have failed for a revoked keeper");
+    } catch (error) {
+      expect(error.message).to.include("AccountNotInitialized");
+    }
+  });
+});
+
+describe("rebalancer strategy count audit", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+
+  let portfolioPda: anchor.web3.PublicKey;
+  const strategyPdas: anchor.web3.PublicKey[] = [];
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    for (let i = 0; i < 3; i++) {
+      const strategyId = anchor.web3.Keypair.generate().publicKey;
+      const [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+        program.programId
+      );
+      strategyPdas.push(strategyPda);
+
+      await program.methods
+        .registerStrategy(
+          strategyId,
+          {
+            stableLending: {
+              poolId: anchor.web3.Keypair.generate().publicKey,
+              utilization: 5000,
+              reserveAddress: anchor.web3.Keypair.generate().publicKey,
+            }
+          },
+          new BN(1_000_000_000),
+          null
+        )
+        .accounts({
+          portfolio: portfolioPda,
+          strategy: strategyPda,
+          manager: manager.publicKey,
+        })
+        .signers([manager])
+        .rpc();
+    }
+  });
+
+  const asRemaining = (pdas: anchor.web3.PublicKey[]) =>
+    pdas.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }));
+
+  it("Detects a desynced strategy counter and never repairs it downward", async () => {
+    // Counter matches when every strategy is passed
+    await program.methods
+      .verifyStrategyCount(false)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .remainingAccounts(asRemaining(strategyPdas))
+      .signers([manager])
+      .rpc();
+
+    // Detection: a partial set doesn't match the counter
+    try {
+      await program.methods
+        .verifyStrategyCount(false)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts(asRemaining(strategyPdas.slice(0, 2)))
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with StrategyCountMismatch");
+    } catch (error) {
+      expect(error.message).to.include("StrategyCountMismatch");
+    }
+
+    // Repairing against the partial set can't lower the count
+    try {
+      await program.methods
+        .verifyStrategyCount(true)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts(asRemaining(strategyPdas.slice(0, 2)))
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with StrategyCountCannotDecrease");
+    } catch (error) {
+      expect(error.message).to.include("StrategyCountCannotDecrease");
+    }
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.totalStrategies).to.equal(3);
+  });
+
+  it("Rejects accounts that are not strategies of this portfolio", async () => {
+    try {
+      await program.methods
+        .verifyStrategyCount(true)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts(asRemaining([portfolioPda]))
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with StrategyNotFound");
+    } catch (error) {
+      expect(error.message).to.include("StrategyNotFound");
+    }
+  });
+});
+
+describe("rebalancer performance fees", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    // Short eligibility window so the test can cross it
+    await program.methods
+      .updatePortfolioConfig({ maxAcceptableIlBps: null, feeEligibilitySeconds: new BN(3) })
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: strategyPda,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Accrues no fees on a just-registered strategy until it is eligible", async () => {
+    // Quick gain right after registration
+    await program.methods
+      .updatePerformance(strategyId, new BN(1000), 3000, new BN(2_000_000_000), { apy: {} }, false)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    let portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.accruedPerformanceFees.toString()).to.equal("0");
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    // Further gain after the window: 1 SOL above the mark at the 2% default fee
+    await program.methods
+      .updatePerformance(strategyId, new BN(1000), 3000, new BN(3_000_000_000), { apy: {} }, false)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.accruedPerformanceFees.toString()).to.equal("20000000");
+  });
+
+  it("Claims accrued fees to the manager's wallet and zeroes the counter", async () => {
+    // Fund the portfolio account so SOL fees can be paid out
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: manager.publicKey,
+          toPubkey: portfolioPda,
+          lamports: 100_000_000,
+        })
+      ),
+      [manager]
+    );
+    const managerBefore = await provider.connection.getBalance(manager.publicKey);
+
+    await program.methods
+      .claimPerformanceFees()
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const managerAfter = await provider.connection.getBalance(manager.publicKey);
+    expect(managerAfter - managerBefore).to.equal(20_000_000);
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.accruedPerformanceFees.toString()).to.equal("0");
+
+    // Nothing left to claim
+    try {
+      await program.methods
+        .claimPerformanceFees()
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with InsufficientBalance");
+    } catch (error) {
+      expect(error.message).to.include("InsufficientBalance");
+    }
+  });
+});
+
+describe("rebalancer two-phase ranking", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const STRATEGY_COUNT = 40;
+  const BATCH_SIZE = 10;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let rankingBufferPda: anchor.web3.PublicKey;
+  const strategies: { id: anchor.web3.PublicKey; pda: anchor.web3.PublicKey }[] = [];
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 100 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [rankingBufferPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("ranking_buffer"), portfolioPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    for (let i = 0; i < STRATEGY_COUNT; i++) {
+      const id = anchor.web3.Keypair.generate().publicKey;
+      const [pda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("strategy"), portfolioPda.toBuffer(), id.toBuffer()],
+        program.programId
+      );
+      strategies.push({ id, pda });
+
+      await program.methods
+        .registerStrategy(
+          id,
+          {
+            stableLending: {
+              poolId: anchor.web3.Keypair.generate().publicKey,
+              utilization: 5000,
+              reserveAddress: anchor.web3.Keypair.generate().publicKey,
+            }
+          },
+          new BN(1_000_000_000),
+          null
+        )
+        .accounts({ portfolio: portfolioPda, strategy: pda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      // Distinct yields give each strategy a distinct score
+      await program.methods
+        .updatePerformance(id, new BN(500 * (i + 1)), 3000, new BN(1_000_000_000), { apy: {} }, false)
+        .accounts({ portfolio: portfolioPda, strategy: pda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+    }
+  });
+
+  const batches = () => {
+    const result = [];
+    for (let i = 0; i < strategies.length; i += BATCH_SIZE) {
+      result.push(strategies.slice(i, i + BATCH_SIZE));
+    }
+    return result;
+  };
+
+  it("Buffers 40 strategies across multiple submit calls", async () => {
+    for (const batch of batches()) {
+      await program.methods
+        .submitScores()
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts(batch.map((s) => ({ pubkey: s.pda, isWritable: false, isSigner: false })))
+        .signers([manager])
+        .rpc();
+    }
+
+    const buffer = await program.account.rankingBuffer.fetch(rankingBufferPda);
+    expect(buffer.entries.length).to.equal(STRATEGY_COUNT);
+  });
+
+  it("Holds finalization to the minimum rebalance interval", async () => {
+    // The portfolio was just initialized, so the interval hasn't elapsed
+    try {
+      await program.methods
+        .finalizeRanking()
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts(batches()[0].map((s) => ({ pubkey: s.pda, isWritable: true, isSigner: false })))
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with RebalanceTooSoon");
+    } catch (error) {
+      expect(error.message).to.include("RebalanceTooSoon");
+    }
+
+    const buffer = await program.account.rankingBuffer.fetch(rankingBufferPda);
+    expect(buffer.sorted).to.equal(false);
+    expect(buffer.writtenBack).to.equal(0);
+  });
+
+  it("Lets only the manager reset an unfinished cycle", async () => {
+    const outsider = anchor.web3.Keypair.generate();
+    try {
+      await program.methods
+        .resetRankingBuffer()
+        .accounts({ portfolio: portfolioPda, manager: outsider.publicKey })
+        .signers([outsider])
+        .rpc();
+
+      expect.fail("Should have failed with UnauthorizedManager");
+    } catch (error) {
+      expect(error.message).to.include("UnauthorizedManager");
+    }
+
+    await program.methods
+      .resetRankingBuffer()
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const buffer = await program.account.rankingBuffer.fetch(rankingBufferPda);
+    expect(buffer.entries.length).to.equal(0);
+    expect(buffer.sorted).to.equal(false);
+    expect(buffer.writtenBack).to.equal(0);
+  });
+
+  it("Refuses to finalize before every strategy is submitted", async () => {
+    // A fresh cycle starts with a partial submission
+    await program.methods
+      .submitScores()
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .remainingAccounts(strategies.slice(0, BATCH_SIZE).map((s) => ({ pubkey: s.pda, isWritable: false, isSigner: false })))
+      .signers([manager])
+      .rpc();
+
+    try {
+      await program.methods
+        .finalizeRanking()
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts(strategies.slice(0, BATCH_SIZE).map((s) => ({ pubkey: s.pda, isWritable: true, isSigner: false })))
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with RankingIncomplete");
+    } catch (error) {
+      expect(error.message).to.include("RankingIncomplete");
+    }
+  });
+});
+
+describe("rebalancer reward compounding", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+  let vaultPda: anchor.web3.PublicKey;
+
+  // Stands in for the protocol paying rewards into the strategy's vault
+  const payRewards = async (lamports: number) => {
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: manager.publicKey,
+          toPubkey: vaultPda,
+          lamports,
+        })
+      ),
+      [manager]
+    );
+  };
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+    [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), strategyPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Rejects compounding rewards the vault never received", async () => {
+    try {
+      await program.methods
+        .compoundRewards(strategyId)
+        .accounts({ portfolio: portfolioPda, strategy: strategyPda, vault: vaultPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with InsufficientBalance");
+    } catch (error) {
+      expect(error.message).to.include("InsufficientBalance");
+    }
+  });
+
+  it("Rejects compounding twice within the protocol frequency", async () => {
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.compoundFrequencySeconds.toNumber()).to.equal(3600); // Lending: hourly
+
+    await payRewards(10_000_000);
+    await program.methods
+      .compoundRewards(strategyId)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, vault: vaultPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const compounded = await program.account.strategy.fetch(strategyPda);
+    expect(compounded.currentBalance.toString()).to.equal("1010000000");
+    expect(compounded.vaultAccounted.toString()).to.equal("10000000");
+
+    await payRewards(10_000_000);
+    try {
+      await program.methods
+        .compoundRewards(strategyId)
+        .accounts({ portfolio: portfolioPda, strategy: strategyPda, vault: vaultPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with CompoundTooSoon");
+    } catch (error) {
+      expect(error.message).to.include("CompoundTooSoon");
+    }
+  });
+});
+
+describe("rebalancer extraction pause", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Blocks extraction while performance updates keep working", async () => {
+    await program.methods
+      .setExtractionPaused(true)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.extractionPaused).to.be.true;
+    expect(portfolio.emergencyPause).to.be.false;
+
+    await program.methods
+      .updatePerformance(strategyId, new BN(1200), 2500, new BN(1_000_000_000), { apy: {} }, false)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.yieldRate.toString()).to.equal("1200");
+
+    try {
+      await program.methods
+        .extractCapital([strategyId], false, { liquid: {} }, 1000)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with ExtractionPaused");
+    } catch (error) {
+      expect(error.message).to.include("ExtractionPaused");
+    }
+
+    await program.methods
+      .setExtractionPaused(false)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const resumed = await program.account.portfolio.fetch(portfolioPda);
+    expect(resumed.extractionPaused).to.be.false;
+  });
+});
+
+describe("rebalancer entry price policy", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyIds = [0, 1].map(() => anchor.web3.Keypair.generate().publicKey);
+  const boundFeedId = anchor.web3.Keypair.generate().publicKey.toBuffer();
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPdas: anchor.web3.PublicKey[];
+
+  const positionPda = (strategyPda: anchor.web3.PublicKey, index = 0) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), strategyPda.toBuffer(), Buffer.from([index])],
+      program.programId
+    )[0];
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    strategyPdas = strategyIds.map((id) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("strategy"), portfolioPda.toBuffer(), id.toBuffer()],
+        program.programId
+      )[0]
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    for (let i = 0; i < strategyIds.length; i++) {
+      await program.methods
+        .registerStrategy(
+          strategyIds[i],
+          {
+            stableLending: {
+              poolId: anchor.web3.Keypair.generate().publicKey,
+              utilization: 5000,
+              reserveAddress: anchor.web3.Keypair.generate().publicKey,
+            }
+          },
+          new BN(1_000_000_000),
+          // Only the second strategy commits to a feed for its token
+          i === 1 ? { source: { pyth: {} }, feedA: Array.from(boundFeedId), feedB: new Array(32).fill(0) } : null
+        )
+        .accounts({ portfolio: portfolioPda, strategy: strategyPdas[i], manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+    }
+  });
+
+  it("Records manager-provided entry prices by default", async () => {
+    await program.methods
+      .createCapitalPosition(
+        strategyIds[0], { singleAsset: {} },
+        new BN(1_000_000_000), new BN(0), new BN(0),
+        new BN(150_000_000), new BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: strategyPdas[0],
+        position: positionPda(strategyPdas[0]),
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
+    const position = await program.account.capitalPosition.fetch(positionPda(strategyPdas[0]));
+    expect(position.entryPriceA.toString()).to.equal("150000000");
+  });
+
+  it("Rejects unverified entry prices under the oracle-verified policy", async () => {
+    await program.methods
+      .updatePortfolioConfig({ entryPricePolicy: { oracleVerified: {} }, entryPriceToleranceBps: 100 })
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    try {
+      await program.methods
+        .createCapitalPosition(
+          strategyIds[1], { singleAsset: {} },
+          new BN(1_000_000_000), new BN(0), new BN(0),
+          new BN(150_000_000), new BN(0)
+        )
+        .accounts({
+          portfolio: portfolioPda,
+          strategy: strategyPdas[1],
+          position: positionPda(strategyPdas[1]),
+          priceFeedA: null,
+          priceFeedB: null,
+          manager: manager.publicKey,
+        })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with OracleFeedRequired");
+    } catch (error) {
+      expect(error.message).to.include("OracleFeedRequired");
+    }
+
+    // A fabricated "feed" not owned by the Pyth receiver cannot vouch for the price.
+    // Deviation from a genuine feed is covered by the oracle unit tests.
+    try {
+      await program.methods
+        .createCapitalPosition(
+          strategyIds[1], { singleAsset: {} },
+          new BN(1_000_000_000), new BN(0), new BN(0),
+          new BN(150_000_000), new BN(0)
+        )
+        .accounts({
+          portfolio: portfolioPda,
+          strategy: strategyPdas[1],
+          position: positionPda(strategyPdas[1]),
+          priceFeedA: strategyPdas[0],
+          priceFeedB: null,
+          manager: manager.publicKey,
+        })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with InvalidOracleAccount");
+    } catch (error) {
+      expect(error.message).to.include("InvalidOracleAccount");
+    }
+
+    // A strategy registered without a feed binding can't verify prices at all
+    try {
+      await program.methods
+        .createCapitalPosition(
+          strategyIds[0], { singleAsset: {} },
+          new BN(1_000_000_000), new BN(0), new BN(0),
+          new BN(150_000_000), new BN(0)
+        )
+        .accounts({
+          portfolio: portfolioPda,
+          strategy: strategyPdas[0],
+          position: positionPda(strategyPdas[0], 1),
+          priceFeedA: strategyPdas[1],
+          priceFeedB: null,
+          manager: manager.publicKey,
+        })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with OracleFeedNotBound");
+    } catch (error) {
+      expect(error.message).to.include("OracleFeedNotBound");
+    }
+  });
+});
+
+describe("rebalancer manager handoff", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const proposedManager = anchor.web3.Keypair.generate();
+
+  let portfolioPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Rejects cancelling when nothing is pending", async () => {
+    try {
+      await program.methods
+        .cancelManagerProposal()
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with NoPendingManager");
+    } catch (error) {
+      expect(error.message).to.include("NoPendingManager");
+    }
+  });
+
+  it("Cancels a pending proposal so it can no longer be accepted", async () => {
+    await program.methods
+      .transferManager(proposedManager.publicKey)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    let portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.pendingManager.toString()).to.equal(proposedManager.publicKey.toString());
+
+    await program.methods
+      .cancelManagerProposal()
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.pendingManager.toString()).to.equal(anchor.web3.PublicKey.default.toString());
+
+    try {
+      await program.methods
+        .acceptManager()
+        .accounts({ portfolio: portfolioPda, newManager: proposedManager.publicKey })
+        .signers([proposedManager])
+        .rpc();
+
+      expect.fail("Should have failed with NoPendingManager");
+    } catch (error) {
+      expect(error.message).to.include("NoPendingManager");
+    }
+
+    portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.manager.toString()).to.equal(manager.publicKey.toString());
+  });
+
+  it("Rejects a transfer to the default key", async () => {
+    try {
+      await program.methods
+        .transferManager(anchor.web3.PublicKey.default)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with InvalidManager");
+    } catch (error) {
+      expect(error.message).to.include("InvalidManager");
+    }
+  });
+
+  it("Hands authority to the new key and locks out the old manager", async () => {
+    await program.methods
+      .transferManager(proposedManager.publicKey)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .acceptManager()
+      .accounts({ portfolio: portfolioPda, newManager: proposedManager.publicKey })
+      .signers([proposedManager])
+      .rpc();
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.manager.toString()).to.equal(proposedManager.publicKey.toString());
+    expect(portfolio.originalManager.toString()).to.equal(manager.publicKey.toString());
+
+    try {
+      await program.methods
+        .setEmergencyPause(true)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with UnauthorizedManager");
+    } catch (error) {
+      expect(error.message).to.include("UnauthorizedManager");
+    }
+
+    // Same PDA address, new authority
+    await program.methods
+      .setEmergencyPause(true)
+      .accounts({ portfolio: portfolioPda, manager: proposedManager.publicKey })
+      .signers([proposedManager])
+      .rpc();
+
+    expect((await program.account.portfolio.fetch(portfolioPda)).emergencyPause).to.be.true;
+  });
+});
+
+describe("rebalancer fee collection", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const treasury = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .updatePortfolioConfig({ feeEligibilitySeconds: new BN(3), feeTreasury: treasury.publicKey })
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    // 1 SOL above the mark at the 2% default fee accrues 0.02 SOL
+    await program.methods
+      .updatePerformance(strategyId, new BN(1000), 3000, new BN(2_000_000_000), { apy: {} }, false)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    // Fund the portfolio account so SOL fees can be paid out
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: manager.publicKey,
+          toPubkey: portfolioPda,
+          lamports: 100_000_000,
+        })
+      ),
+      [manager]
+    );
+  });
+
+  const collectAccounts = (overrides = {}) => ({
+    portfolio: portfolioPda,
+    riskConfig: null,
+    feeTreasury: treasury.publicKey,
+    feeTokenMint: null,
+    feeTokenVault: null,
+    treasuryTokenAccount: null,
+    solPriceFeed: null,
+    feeTokenPriceFeed: null,
+    tokenProgram: null,
+    manager: manager.publicKey,
+    ...overrides,
+  });
+
+  it("Collects accrued fees in SOL to the treasury", async () => {
+    await program.methods
+      .collectPerformanceFees()
+      .accounts(collectAccounts())
+      .signers([manager])
+      .rpc();
+
+    const treasuryBalance = await provider.connection.getBalance(treasury.publicKey);
+    expect(treasuryBalance).to.equal(20_000_000);
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.accruedPerformanceFees.toString()).to.equal("0");
+  });
+
+  it("Leaves fees owed to a configured treasury out of the manager's claim", async () => {
+    try {
+      await program.methods
+        .claimPerformanceFees()
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with FeeTreasuryConfigured");
+    } catch (error) {
+      expect(error.message).to.include("FeeTreasuryConfigured");
+    }
+  });
+
+  it("Requires oracle feeds when fees are denominated in a fee token", async () => {
+    const feeTokenMint = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .updatePortfolioConfig({ feeTokenMint })
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .updatePerformance(strategyId, new BN(1000), 3000, new BN(3_000_000_000), { apy: {} }, false)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    // Converted amounts at fresh prices are covered by the conversion unit tests
+    try {
+      await program.methods
+        .collectPerformanceFees()
+        .accounts(collectAccounts())
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should not settle a fee-token portfolio in SOL");
+    } catch (error) {
+      expect(error.message).to.include("InvalidTokenMint");
+    }
+  });
+
+  it("Rejects price feeds that are not bound in the risk config", async () => {
+    // No risk config binds a SOL/USD feed, so any supplied feed is refused
+    // before its price is read
+    try {
+      await program.methods
+        .collectPerformanceFees()
+        .accounts(collectAccounts({ solPriceFeed: anchor.web3.Keypair.generate().publicKey }))
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with OracleFeedMismatch");
+    } catch (error) {
+      expect(error.message).to.include("OracleFeedMismatch");
+    }
+  });
+});
+
+describe("rebalancer emergency destination", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const safeDestination = anchor.web3.Keypair.generate();
+  const attackerDestination = anchor.web3.Keypair.generate();
+
+  let portfolioPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, safeDestination.publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: manager.publicKey,
+          toPubkey: portfolioPda,
+          lamports: 500_000_000,
+        })
+      ),
+      [manager]
+    );
+  });
+
+  it("Rejects emergency extraction to a non-whitelisted address", async () => {
+    try {
+      await program.methods
+        .emergencyExtract()
+        .accounts({ portfolio: portfolioPda, destination: attackerDestination.publicKey, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with UnauthorizedEmergencyDestination");
+    } catch (error) {
+      expect(error.message).to.include("UnauthorizedEmergencyDestination");
+    }
+
+    expect(await provider.connection.getBalance(attackerDestination.publicKey)).to.equal(0);
+  });
+
+  it("Never credits the manager's wallet from an emergency capital extraction", async () => {
+    const managerBalance = await provider.connection.getBalance(manager.publicKey);
+
+    try {
+      await program.methods
+        .emergencyExtractCapital([anchor.web3.Keypair.generate().publicKey], 1, false, 1000)
+        .accounts({
+          portfolio: portfolioPda,
+          destination: manager.publicKey,
+          manager: manager.publicKey,
+          tokenProgram: null,
+        })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with UnauthorizedEmergencyDestination");
+    } catch (error) {
+      expect(error.message).to.include("UnauthorizedEmergencyDestination");
+    }
+
+    // The provider wallet pays the fee, so any change would be proceeds
+    expect(await provider.connection.getBalance(manager.publicKey)).to.equal(managerBalance);
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.emergencyExtractCount).to.equal(0);
+  });
+
+  it("Commits the destination at initialization", async () => {
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.emergencyDestination.toString()).to.equal(safeDestination.publicKey.toString());
+    expect(program.methods).to.not.have.property("setEmergencyDestination");
+  });
+
+  it("Sweeps idle funds to the committed destination", async () => {
+    await program.methods
+      .emergencyExtract()
+      .accounts({ portfolio: portfolioPda, destination: safeDestination.publicKey, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    expect(await provider.connection.getBalance(safeDestination.publicKey)).to.equal(500_000_000);
+  });
+});
+
+describe("rebalancer emergency pause", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+
+  let portfolioPda: anchor.web3.PublicKey;
+
+  const registerLending = (strategyId: anchor.web3.PublicKey) => {
+    const [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+    return program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  };
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Blocks registration while paused and resumes after unpausing", async () => {
+    await program.methods
+      .setEmergencyPause(true)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    expect((await program.account.portfolio.fetch(portfolioPda)).emergencyPause).to.be.true;
+
+    try {
+      await registerLending(anchor.web3.Keypair.generate().publicKey);
+      expect.fail("Should have failed with EmergencyPaused");
+    } catch (error) {
+      expect(error.message).to.include("EmergencyPaused");
+    }
+
+    await program.methods
+      .setEmergencyPause(false)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await registerLending(anchor.web3.Keypair.generate().publicKey);
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.emergencyPause).to.be.false;
+    expect(portfolio.totalStrategies).to.equal(1);
+  });
+
+  it("Rejects ranking a one-strategy portfolio before anything else", async () => {
+    try {
+      await program.methods
+        .executeRankingCycle(null)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+      expect.fail("Should have failed with TooFewStrategiesToRebalance");
+    } catch (error) {
+      expect(error.message).to.include("TooFewStrategiesToRebalance");
+      expect(error.message).to.not.include("InsufficientStrategies");
+    }
+  });
+
+  it("Rejects a ranking cycle inside the minimum interval with RebalanceTooSoon", async () => {
+    await registerLending(anchor.web3.Keypair.generate().publicKey);
+
+    try {
+      await program.methods
+        .executeRankingCycle(null)
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+      expect.fail("Should have failed with RebalanceTooSoon");
+    } catch (error) {
+      expect(error.message).to.include("RebalanceTooSoon");
+      expect(error.message).to.not.include("InvalidRebalanceInterval");
+    }
+  });
+});
+
+describe("rebalancer position cap", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+
+  const positionPda = (index: number) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), strategyPda.toBuffer(), Buffer.from([index])],
+      program.programId
+    )[0];
+
+  const createPosition = (index: number) =>
+    program.methods
+      .createCapitalPosition(
+        strategyId, { singleAsset: {} },
+        new BN(100_000_000), new BN(0), new BN(0),
+        new BN(150_000_000), new BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: strategyPda,
+        position: positionPda(index),
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .updatePortfolioConfig({ maxPositionsPerStrategy: 2 })
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Creates positions up to the cap and rejects the next", async () => {
+    await createPosition(0);
+    await createPosition(1);
+
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.positionCount).to.equal(2);
+    expect((await program.account.capitalPosition.fetch(positionPda(1))).positionIndex).to.equal(1);
+
+    try {
+      await createPosition(2);
+      expect.fail("Should have failed with MaxPositionsReached");
+    } catch (error) {
+      expect(error.message).to.include("MaxPositionsReached");
+    }
+  });
+});
+
+describe("rebalancer capital vault", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+  let vaultPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+    [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), strategyPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Moves deposited lamports into the strategy vault", async () => {
+    const vaultBefore = await provider.connection.getBalance(vaultPda);
+
+    await program.methods
+      .depositCapital(strategyId, new BN(2_000_000_000))
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, vault: vaultPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    expect(await provider.connection.getBalance(vaultPda)).to.equal(vaultBefore + 2_000_000_000);
+
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.currentBalance.toString()).to.equal("3000000000");
+    expect(strategy.totalDeposits.toString()).to.equal("3000000000");
+  });
+
+  it("Returns withdrawn lamports to the destination and keeps the vault rent-exempt", async () => {
+    const [positionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), strategyPda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    const destination = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .createCapitalPosition(
+        strategyId, { singleAsset: {} },
+        new BN(3_000_000_000), new BN(0), new BN(0),
+        new BN(150_000_000), new BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: strategyPda,
+        position: positionPda,
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
+    const withdrawAccounts = {
+      portfolio: portfolioPda,
+      strategy: strategyPda,
+      position: positionPda,
+      vault: vaultPda,
+      destination,
+      manager: manager.publicKey,
+    };
+
+    await program.methods
+      .withdrawCapital(strategyId, new BN(1_000_000_000))
+      .accounts(withdrawAccounts)
+      .signers([manager])
+      .rpc();
+
+    expect(await provider.connection.getBalance(destination)).to.equal(1_000_000_000);
+    const strategy = await program.account.strategy.fetch(strategyPda);
+    expect(strategy.currentBalance.toString()).to.equal("2000000000");
+    expect(strategy.totalWithdrawals.toString()).to.equal("1000000000");
+
+    // The vault only holds the 1 SOL still deposited; draining it is refused
+    try {
+      await program.methods
+        .withdrawCapital(strategyId, new BN(1_000_000_000))
+        .accounts(withdrawAccounts)
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with VaultBelowRentExemption");
+    } catch (error) {
+      expect(error.message).to.include("VaultBelowRentExemption");
+    }
+  });
+
+  it("Refuses withdrawals while extraction is paused", async () => {
+    const [positionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), strategyPda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    const destination = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .setExtractionPaused(true)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    try {
+      await program.methods
+        .withdrawCapital(strategyId, new BN(100_000_000))
+        .accounts({
+          portfolio: portfolioPda,
+          strategy: strategyPda,
+          position: positionPda,
+          vault: vaultPda,
+          destination,
+          manager: manager.publicKey,
+        })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with ExtractionPaused");
+    } catch (error) {
+      expect(error.message).to.include("ExtractionPaused");
+    }
+    expect(await provider.connection.getBalance(destination)).to.equal(0);
+
+    await program.methods
+      .setExtractionPaused(false)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  });
+});
+
+describe("rebalancer risk config", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const platformTreasury = anchor.web3.Keypair.generate().publicKey;
+  const managerTreasury = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let riskConfigPda: anchor.web3.PublicKey;
+
+  const limits = (platform: anchor.web3.PublicKey, managerFeeTo: anchor.web3.PublicKey) => ({
+    maxSingleStrategyBps: new BN(4000),
+    minSingleStrategyBps: new BN(100),
+    platformFeeBps: new BN(50),
+    managerFeeBps: new BN(150),
+    riskToleranceBps: new BN(8000),
+    platformTreasury: platform,
+    managerTreasury: managerFeeTo,
+    floorRiskAdjustedToMinimum: true,
+    maxSkips: 3,
+    gasCostPerTransfer: new BN(0),
+    minNetAllocation: new BN(0),
+    normalizeToCapital: false,
+    maxLendingBps: new BN(10000),
+    maxFarmingBps: new BN(10000),
+    maxStakingBps: new BN(10000),
+    minBalanceLending: new BN(100_000_000),
+    minBalanceFarming: new BN(500_000_000),
+    minBalanceStaking: new BN(1_000_000_000),
+    topPerformerPercentile: 75,
+    maxTopPerformers: 5,
+    dustThresholdLamports: new BN(1_000_000),
+    solPriceFeed: anchor.web3.PublicKey.default,
+    feeTokenPriceFeed: anchor.web3.PublicKey.default,
+  });
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [riskConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("risk_config"), portfolioPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Rejects fees without treasuries, then stores configured treasuries", async () => {
+    try {
+      await program.methods
+        .setRiskConfig(limits(anchor.web3.PublicKey.default, managerTreasury))
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+      expect.fail("Should have failed with TreasuryNotConfigured");
+    } catch (error) {
+      expect(error.message).to.include("TreasuryNotConfigured");
+    }
+
+    await program.methods
+      .setRiskConfig(limits(platformTreasury, managerTreasury))
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const riskConfig = await program.account.riskConfig.fetch(riskConfigPda);
+    expect(riskConfig.portfolio.toBase58()).to.equal(portfolioPda.toBase58());
+    expect(riskConfig.limits.platformTreasury.toBase58()).to.equal(platformTreasury.toBase58());
+    expect(riskConfig.limits.managerTreasury.toBase58()).to.equal(managerTreasury.toBase58());
+  });
+});
+
+describe("rebalancer chunked redistribution", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyIds = [anchor.web3.Keypair.generate().publicKey, anchor.web3.Keypair.generate().publicKey];
+  const sourceId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let sessionPda: anchor.web3.PublicKey;
+  let strategyPdas: anchor.web3.PublicKey[];
+
+  const chunk = (index: number, amount: number) =>
+    program.methods
+      .redistributeChunk([
+        { strategyId: strategyIds[index], amount: new BN(amount), allocationType: { topPerformer: {} } },
+      ])
+      .accounts({ portfolio: portfolioPda, session: sessionPda, manager: manager.publicKey })
+      .remainingAccounts([{ pubkey: strategyPdas[index], isWritable: true, isSigner: false }])
+      .signers([manager])
+      .rpc();
+
+  const finalize = () =>
+    program.methods
+      .finalizeRedistribution()
+      .accounts({ portfolio: portfolioPda, session: sessionPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [sessionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("redistribution"), portfolioPda.toBuffer()],
+      program.programId
+    );
+    strategyPdas = strategyIds.map((id) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("strategy"), portfolioPda.toBuffer(), id.toBuffer()],
+        program.programId
+      )[0]
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    for (let i = 0; i < strategyIds.length; i++) {
+      await program.methods
+        .registerStrategy(
+          strategyIds[i],
+          {
+            stableLending: {
+              poolId: anchor.web3.Keypair.generate().publicKey,
+              utilization: 5000,
+              reserveAddress: anchor.web3.Keypair.generate().publicKey,
+            }
+          },
+          new BN(1_000_000_000),
+          null
+        )
+        .accounts({ portfolio: portfolioPda, strategy: strategyPdas[i], manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+    }
+
+    // Redistribution hands out extracted capital, so release some from a staking position first
+    const [sourcePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), sourceId.toBuffer()],
+      program.programId
+    );
+    const [sourcePositionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), sourcePda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+
+    await program.methods
+      .registerStrategy(
+        sourceId,
+        {
+          liquidStaking: {
+            validatorId: anchor.web3.Keypair.generate().publicKey,
+            commission: 500,
+            stakePool: anchor.web3.Keypair.generate().publicKey,
+            unstakeDelay: 10,
+          }
+        },
+        new BN(2_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: sourcePda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .createCapitalPosition(
+        sourceId, { stakedPosition: {} },
+        new BN(2_000_000_000), new BN(0), new BN(0),
+        new BN(1_000_000), new BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: sourcePda,
+        position: sourcePositionPda,
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .extractCapital([sourceId], true, { liquid: {} }, 1000)
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .remainingAccounts([
+        { pubkey: sourcePda, isWritable: true, isSigner: false },
+        { pubkey: sourcePositionPda, isWritable: true, isSigner: false },
+      ])
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Refuses to plan more than the extracted capital", async () => {
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.undeployedCapital.toNumber()).to.be.greaterThan(1_500_000_000);
+
+    try {
+      await program.methods
+        .beginRedistribution(portfolio.undeployedCapital.addn(1))
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with ExceedsUndeployedCapital");
+    } catch (error) {
+      expect(error.message).to.include("ExceedsUndeployedCapital");
+    }
+  });
+
+  it("Accumulates chunks and finalizes only at the expected total", async () => {
+    const before = await program.account.portfolio.fetch(portfolioPda);
+
+    await program.methods
+      .beginRedistribution(new BN(1_500_000_000))
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await chunk(0, 1_000_000_000);
+
+    try {
+      await finalize();
+      expect.fail("Should have failed with RedistributionTotalMismatch");
+    } catch (error) {
+      expect(error.message).to.include("RedistributionTotalMismatch");
+    }
+
+    try {
+      await chunk(0, 500_000_000);
+      expect.fail("Should have failed with DuplicateStrategy");
+    } catch (error) {
+      expect(error.message).to.include("DuplicateStrategy");
+    }
+
+    await chunk(1, 500_000_000);
+    await finalize();
+
+    const session = await program.account.redistributionSession.fetch(sessionPda);
+    expect(session.active).to.be.false;
+    expect(session.accumulatedTotal.toNumber()).to.equal(1_500_000_000);
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.totalCapitalMoved.sub(before.totalCapitalMoved).toNumber()).to.equal(1_500_000_000);
+    expect(before.undeployedCapital.sub(portfolio.undeployedCapital).toNumber()).to.equal(1_500_000_000);
+
+    const second = await program.account.strategy.fetch(strategyPdas[1]);
+    expect(second.currentBalance.toNumber()).to.equal(1_500_000_000);
+  });
+
+  it("Cancels a session that falls short and blocks one-shot redistribution while open", async () => {
+    const before = await program.account.portfolio.fetch(portfolioPda);
+
+    await program.methods
+      .beginRedistribution(new BN(100_000_000))
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await chunk(0, 60_000_000);
+
+    try {
+      await program.methods
+        .redistributeCapital(
+          [{ strategyId: strategyIds[1], amount: new BN(10_000_000), allocationType: { topPerformer: {} } }],
+          false
+        )
+        .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+        .remainingAccounts([{ pubkey: strategyPdas[1], isWritable: true, isSigner: false }])
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with RedistributionInProgress");
+    } catch (error) {
+      expect(error.message).to.include("RedistributionInProgress");
+    }
+
+    await program.methods
+      .cancelRedistribution()
+      .accounts({ portfolio: portfolioPda, session: sessionPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const session = await program.account.redistributionSession.fetch(sessionPda);
+    expect(session.active).to.be.false;
+
+    // Only the credited chunk was booked; the shortfall stays undeployed
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.totalCapitalMoved.sub(before.totalCapitalMoved).toNumber()).to.equal(60_000_000);
+    expect(before.undeployedCapital.sub(portfolio.undeployedCapital).toNumber()).to.equal(60_000_000);
+
+    try {
+      await program.methods
+        .cancelRedistribution()
+        .accounts({ portfolio: portfolioPda, session: sessionPda, manager: manager.publicKey })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with NoActiveRedistribution");
+    } catch (error) {
+      expect(error.message).to.include("NoActiveRedistribution");
+    }
+  });
+});
+
+describe("rebalancer stable denomination", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          stableLending: {
+            poolId: anchor.web3.Keypair.generate().publicKey,
+            utilization: 5000,
+            reserveAddress: anchor.web3.Keypair.generate().publicKey,
+          }
+        },
+        new BN(1_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Refuses to score in lamports once a denomination is set", async () => {
+    await program.methods
+      .setDenominationConfig({ pyth: {} }, anchor.web3.Keypair.generate().publicKey, new BN(150_000_000))
+      .accounts({ portfolio: portfolioPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.denominationEnabled).to.be.true;
+
+    try {
+      await program.methods
+        .updatePerformance(strategyId, new BN(1200), 2500, new BN(1_000_000_000), { apy: {} }, false)
+        .accounts({
+          portfolio: portfolioPda,
+          strategy: strategyPda,
+          denominationConfig: null,
+          priceFeed: null,
+          manager: manager.publicKey,
+        })
+        .signers([manager])
+        .rpc();
+
+      expect.fail("Should have failed with DenominationConfigRequired");
+    } catch (error) {
+      expect(error.message).to.include("DenominationConfigRequired");
+    }
+  });
+});
+
+describe("rebalancer strategy close", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.PortfolioRebalancer as Program<PortfolioRebalancer>;
+  const manager = anchor.web3.Keypair.generate();
+  const strategyId = anchor.web3.Keypair.generate().publicKey;
+
+  let portfolioPda: anchor.web3.PublicKey;
+  let strategyPda: anchor.web3.PublicKey;
+  let positionPda: anchor.web3.PublicKey;
+
+  const close = () =>
+    program.methods
+      .closeStrategy(strategyId)
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+  before(async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(manager.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+      "confirmed"
+    );
+
+    [portfolioPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("portfolio"), manager.publicKey.toBuffer()],
+      program.programId
+    );
+    [strategyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), portfolioPda.toBuffer(), strategyId.toBuffer()],
+      program.programId
+    );
+    [positionPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), strategyPda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+
+    await program.methods
+      .initializePortfolio(manager.publicKey, 25, new BN(3600), 50, anchor.web3.Keypair.generate().publicKey)
+      .accounts({
+        manager: manager.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .registerStrategy(
+        strategyId,
+        {
+          liquidStaking: {
+            validatorId: anchor.web3.Keypair.generate().publicKey,
+            commission: 500,
+            stakePool: anchor.web3.Keypair.generate().publicKey,
+            unstakeDelay: 10,
+          }
+        },
+        new BN(2_000_000_000),
+        null
+      )
+      .accounts({ portfolio: portfolioPda, strategy: strategyPda, manager: manager.publicKey })
+      .signers([manager])
+      .rpc();
+
+    await program.methods
+      .createCapitalPosition(
+        strategyId, { stakedPosition: {} },
+        new BN(2_000_000_000), new BN(0), new BN(0),
+        new BN(1_000_000), new BN(0)
+      )
+      .accounts({
+        portfolio: portfolioPda,
+        strategy: strategyPda,
+        position: positionPda,
+        priceFeedA: null,
+        priceFeedB: null,
+        manager: manager.publicKey,
+      })
+      .signers([manager])
+      .rpc();
+  });
+
+  it("Refuses to close a strategy that still holds capital", async () => {
+    try {
+      await close();
+      expect.fail("Should have failed with StrategyNotWoundDown");
+    } catch (error) {
+      expect(error.message).to.include("StrategyNotWoundDown");
+    }
+  });
+
+  it("Closes a wound-down strategy and frees its slot", async () => {
+    await program.methods
+      .windDownStrategy(strategyId, manager.publicKey, 1000)
+      .accounts({
+        portfolio: portfolioPda,
+        recipient: manager.publicKey,
+        manager: manager.publicKey,
+        tokenProgram: null,
+      })
+      .remainingAccounts([
+        { pubkey: strategyPda, isWritable: true, isSigner: false },
+        { pubkey: positionPda, isWritable: true, isSigner: false },
+      ])
+      .signers([manager])
+      .rpc();
+
+    const before = await program.account.portfolio.fetch(portfolioPda);
+    expect(before.totalStrategies).to.equal(1);
+
+    await close();
+
+    const portfolio = await program.account.portfolio.fetch(portfolioPda);
+    expect(portfolio.totalStrategies).to.equal(0);
+    expect(await program.account.strategy.fetchNullable(strategyPda)).to.be.null;
+  });
+});
@@ -26,7 +26,8 @@ describe("portfolio-rebalancer-working", () => {
       .initializePortfolio(
         manager.publicKey,
         25, // 25% rebalance threshold
-        new anchor.BN(3600) // 1 hour minimum interval
+        new anchor.BN(3600), // 1 hour minimum interval
+        50 // At most 50 strategies
       )
       .accounts({
         manager: manager.publicKey,